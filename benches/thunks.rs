@@ -0,0 +1,76 @@
+//! Benchmarks of the compiled examples; run with `cargo bench --bench thunks`.
+
+#![feature(test)]
+
+extern crate test;
+
+use rast_jit_vm::{examples, prelude::*};
+use test::Bencher;
+
+/// Replaces `print()`s and `println()`s with empty blocks, so that the
+/// benchmarks measure just the computations.
+struct WithoutOutput;
+
+impl<'s> Fold<'s> for WithoutOutput {
+    fn fold_node(&mut self, node: Node<'s>) -> Node<'s> {
+        match node {
+            Node::Print(_) | Node::Println(_) => Node::Block(Vec::new()),
+            node => walk_fold_node(self, node),
+        }
+    }
+}
+
+#[bench]
+fn fibonacci(b: &mut Bencher) {
+    let fib = vm::try_compile::<i64, i64>(examples::fibonacci()).unwrap();
+
+    b.iter(|| fib.run(test::black_box(90)).unwrap());
+}
+
+#[bench]
+fn mandelbrot(b: &mut Bencher) {
+    let mandelbrot = WithoutOutput.fold_program(examples::mandelbrot());
+    let mandelbrot = vm::try_compile::<_, ()>(mandelbrot).unwrap();
+
+    b.iter(|| {
+        mandelbrot
+            .run(test::black_box((
+                24,    // viewport_width
+                12,    // viewport_height
+                -2.05, // x1
+                -1.12, // y1
+                0.47,  // x2
+                1.12,  // y2
+                100,   // max_iterations
+            )))
+            .unwrap()
+    });
+}
+
+#[bench]
+fn tuples_and_strings(b: &mut Bencher) {
+    // Reads heap-allocated variables (a tuple and a runtime-created string) in
+    // a loop, without modifying them
+    let prog = parse(
+        r#"fn(input: (int, (str, int))) -> int {
+            var pair = input;
+            var str = format("{}{}", pair.1.0, pair.1.0);
+            var char = ' ';
+            var sum = 0;
+            var i = 0;
+
+            while i < 1000 {
+                char = str[i % 8];
+                sum = sum + pair.0 + pair.1.1;
+                i = i + 1;
+            }
+
+            sum
+        }"#,
+    )
+    .unwrap();
+
+    let prog = vm::try_compile::<_, i64>(prog).unwrap();
+
+    b.iter(|| prog.run(test::black_box((1, ("Hello, ", 2)))).unwrap());
+}
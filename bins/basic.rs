@@ -12,5 +12,5 @@ fn main() {
 
     let mul2 = vm::compile::<_, i32>(mul2);
 
-    println!("{}", mul2(15));
+    println!("{}", mul2(15).unwrap());
 }
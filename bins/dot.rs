@@ -0,0 +1,64 @@
+//! Renders a program as a Graphviz graph.
+//!
+//! Usage:
+//!
+//! ```text
+//! cargo run --bin dot -- <fibonacci | mandelbrot | path/to/program.rast> <output.dot> [--types]
+//! ```
+//!
+//! ... and then e.g. `dot -Tsvg output.dot > output.svg`.
+
+use rast_jit_vm::{examples, prelude::*};
+use std::{env, fs, process};
+
+fn main() {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let types = args.iter().any(|arg| arg == "--types");
+    let args: Vec<_> = args.iter().filter(|arg| *arg != "--types").collect();
+
+    let (prog, output) = match args.as_slice() {
+        [prog, output] => (prog.as_str(), output.as_str()),
+
+        _ => {
+            eprintln!(
+                "usage: dot <fibonacci | mandelbrot | path/to/program.rast> <output.dot> [--types]"
+            );
+            process::exit(1);
+        }
+    };
+
+    let mut src = String::new();
+
+    let prog = match prog {
+        "fibonacci" => examples::fibonacci(),
+        "mandelbrot" => examples::mandelbrot(),
+
+        path => {
+            src = fs::read_to_string(path).unwrap_or_else(|err| {
+                eprintln!("couldn't read `{}`: {}", path, err);
+                process::exit(1);
+            });
+
+            parse(&src).unwrap_or_else(|err| {
+                eprintln!("{}:{}", path, err);
+                process::exit(1);
+            })
+        }
+    };
+
+    let dot = if types {
+        if let Err(errs) = typecheck(&prog) {
+            eprint!("{}", render_errors(&src, &errs));
+            process::exit(1);
+        }
+
+        vm::to_dot(&prog)
+    } else {
+        to_dot(&prog)
+    };
+
+    fs::write(output, dot).unwrap_or_else(|err| {
+        eprintln!("couldn't write `{}`: {}", output, err);
+        process::exit(1);
+    });
+}
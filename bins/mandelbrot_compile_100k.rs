@@ -11,5 +11,6 @@ fn main() {
         0.47,   // x2
         1.12,   // y2
         100000, // max_iterations
-    ));
+    ))
+    .unwrap();
 }
@@ -12,5 +12,6 @@ fn main() {
             1.12,   // y2
             100000, // max_iterations
         ),
-    );
+    )
+    .unwrap();
 }
@@ -0,0 +1,381 @@
+//! Procedural macros for rast-jit-vm; see `rast_jit_vm::rast!`.
+
+// The AST (along with the parser) is shared with the main crate by compiling
+// its sources here a second time, instead of moving it into a separate crate
+// that both would depend on - that's deliberate, since the VM adds inherent
+// impls to the AST's types (`impl ast::Node { fn compile() ... }`), which is
+// possible only within the crate that defines them.
+//
+// The price is that this crate works only as a part of the repository (it's
+// not publishable on its own) and that `ast` can refer to other modules only
+// through `crate::ast` (re-exported below), never through e.g. `crate::vm`;
+// CI checks the latter by building this crate separately.
+#[path = "../../src"]
+#[allow(dead_code, unused_imports)]
+mod shared {
+    pub mod ast;
+}
+
+use self::shared::ast::{self, *};
+use proc_macro2::{Delimiter, Literal, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use std::ops::Range;
+
+/// Builds [`Program`] (or a [`Node`]) out of the textual syntax, at compile
+/// time.
+///
+/// Input starting with `fn` is parsed as a program, anything else - as a
+/// single expression:
+///
+/// ```ignore
+/// let prog = rast! { fn(input: int) -> int { input * 2 } };
+/// let node = rast! { var x = input.0 + 1 };
+/// ```
+///
+/// See `rast_jit_vm::ast::parse()` for the grammar; syntax errors are
+/// reported as compilation errors.
+#[proc_macro]
+pub fn rast(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = TokenStream::from(input);
+    let starts_with_fn =
+        matches!(input.clone().into_iter().next(), Some(TokenTree::Ident(ident)) if ident == "fn");
+
+    let mut src = Source::default();
+
+    src.push_stream(input);
+
+    // Spans point into `src.code` rather than into the Rust source, so
+    // there's no point in keeping them
+    let result = if starts_with_fn {
+        parse(&src.code).map(|prog| program(&prog.without_spans()))
+    } else {
+        parse_node(&src.code).map(|node| self::node(&node.without_spans()))
+    };
+
+    let tokens = result.unwrap_or_else(|err| {
+        let span = src.span_at(err.offset);
+        let message = err.message;
+
+        quote_spanned!(span=> compile_error!(#message))
+    });
+
+    tokens.into()
+}
+
+/// Macro's input converted back into source code, so that it can be given to
+/// the parser.
+#[derive(Default)]
+struct Source {
+    code: String,
+
+    /// Spans of the tokens, used to point at the invalid token when parsing
+    /// fails
+    spans: Vec<(Range<usize>, Span)>,
+}
+
+impl Source {
+    fn push_stream(&mut self, stream: TokenStream) {
+        for tt in stream {
+            match tt {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+
+                    self.push(open, group.span_open());
+                    self.push_stream(group.stream());
+                    self.push(close, group.span_close());
+                }
+
+                TokenTree::Punct(punct) => {
+                    // Joint punctuation (e.g. `-` in `->`) must not be
+                    // separated from the next character
+                    let start = self.code.len();
+
+                    self.code.push(punct.as_char());
+                    self.spans.push((start..self.code.len(), punct.span()));
+
+                    if punct.spacing() == proc_macro2::Spacing::Alone {
+                        self.code.push(' ');
+                    }
+                }
+
+                tt => {
+                    self.push(&tt.to_string(), tt.span());
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, code: &str, span: Span) {
+        let start = self.code.len();
+
+        self.code.push_str(code);
+        self.spans.push((start..self.code.len(), span));
+        self.code.push(' ');
+    }
+
+    /// Returns span of the token at given offset (or of the nearest token
+    /// after it).
+    fn span_at(&self, offset: usize) -> Span {
+        self.spans
+            .iter()
+            .find(|(range, _)| offset < range.end)
+            .or_else(|| self.spans.last())
+            .map_or_else(Span::call_site, |(_, span)| *span)
+    }
+}
+
+fn program(prog: &Program) -> TokenStream {
+    let input = ty(&prog.input);
+    let output = ty(&prog.output);
+    let body = node(&prog.body);
+
+    quote! {
+        ::rast_jit_vm::ast::Program {
+            input: #input,
+            output: #output,
+            body: #body,
+        }
+    }
+}
+
+fn ty(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Unit => quote! { ::rast_jit_vm::ast::Type::Unit },
+        Type::Bool => quote! { ::rast_jit_vm::ast::Type::Bool },
+        Type::Char => quote! { ::rast_jit_vm::ast::Type::Char },
+        Type::Int => quote! { ::rast_jit_vm::ast::Type::Int },
+        Type::Float => quote! { ::rast_jit_vm::ast::Type::Float },
+        Type::Str => quote! { ::rast_jit_vm::ast::Type::Str },
+
+        Type::Tuple(tys) => {
+            let tys = tys.iter().map(self::ty);
+
+            quote! { ::rast_jit_vm::ast::Type::Tuple(::std::vec![#(#tys),*]) }
+        }
+    }
+}
+
+fn value(value: &Value) -> TokenStream {
+    match value {
+        Value::Unit => quote! { ::rast_jit_vm::ast::Value::Unit },
+
+        Value::Bool(value) => quote! { ::rast_jit_vm::ast::Value::Bool(#value) },
+        Value::Char(value) => quote! { ::rast_jit_vm::ast::Value::Char(#value) },
+
+        Value::Int(value) => {
+            let value = Literal::i64_suffixed(*value);
+
+            quote! { ::rast_jit_vm::ast::Value::Int(#value) }
+        }
+
+        Value::Float(value) if value.is_finite() => {
+            let value = Literal::f32_suffixed(*value);
+
+            quote! { ::rast_jit_vm::ast::Value::Float(#value) }
+        }
+
+        // Too-large literals (e.g. `1e99`) get parsed as infinity, which
+        // doesn't have a literal syntax
+        Value::Float(value) => {
+            let bits = value.to_bits();
+
+            quote! { ::rast_jit_vm::ast::Value::Float(::std::primitive::f32::from_bits(#bits)) }
+        }
+
+        Value::Str(value) => {
+            let value = value.as_ref();
+
+            quote! {
+                ::rast_jit_vm::ast::Value::Str(::std::borrow::Cow::Borrowed(#value))
+            }
+        }
+
+        Value::Tuple(values) => {
+            let values = values.iter().map(self::value);
+
+            quote! { ::rast_jit_vm::ast::Value::Tuple(::std::vec![#(#values),*]) }
+        }
+    }
+}
+
+fn pattern(pat: &Pattern) -> TokenStream {
+    match pat {
+        Pattern::Var(name) => quote! { ::rast_jit_vm::ast::Pattern::Var(#name) },
+        Pattern::Ignore => quote! { ::rast_jit_vm::ast::Pattern::Ignore },
+
+        Pattern::Tuple(pats) => {
+            let pats = pats.iter().map(self::pattern);
+
+            quote! { ::rast_jit_vm::ast::Pattern::Tuple(::std::vec![#(#pats),*]) }
+        }
+    }
+}
+
+fn boxed(node: &Node) -> TokenStream {
+    let node = self::node(node);
+
+    quote! { ::std::boxed::Box::new(#node) }
+}
+
+fn node(node: &Node) -> TokenStream {
+    if let Some((op, lhs, rhs)) = BinOp::of(node) {
+        let variant = match op {
+            BinOp::Or => quote! { Or },
+            BinOp::And => quote! { And },
+            BinOp::Eq => quote! { Eq },
+            BinOp::Neq => quote! { Neq },
+            BinOp::Gt => quote! { Gt },
+            BinOp::GtEq => quote! { GtEq },
+            BinOp::Lt => quote! { Lt },
+            BinOp::LtEq => quote! { LtEq },
+            BinOp::Add => quote! { Add },
+            BinOp::Sub => quote! { Sub },
+            BinOp::Mul => quote! { Mul },
+            BinOp::Div => quote! { Div },
+            BinOp::Mod => quote! { Mod },
+        };
+
+        let lhs = boxed(lhs);
+        let rhs = boxed(rhs);
+
+        return quote! {
+            ::rast_jit_vm::ast::Node::#variant { lhs: #lhs, rhs: #rhs }
+        };
+    }
+
+    match node {
+        Node::Const(value) => {
+            let value = self::value(value);
+
+            quote! { ::rast_jit_vm::ast::Node::Const(#value) }
+        }
+
+        Node::Var(name) => quote! { ::rast_jit_vm::ast::Node::Var(#name) },
+
+        Node::ExtractTuple { expr, idx } => {
+            let expr = boxed(expr);
+
+            quote! { ::rast_jit_vm::ast::Node::ExtractTuple { expr: #expr, idx: #idx } }
+        }
+
+        Node::ExtractArray { expr, idx } => {
+            let expr = boxed(expr);
+            let idx = boxed(idx);
+
+            quote! { ::rast_jit_vm::ast::Node::ExtractArray { expr: #expr, idx: #idx } }
+        }
+
+        Node::Cast { expr, ty } => {
+            let expr = boxed(expr);
+            let ty = self::ty(ty);
+
+            quote! { ::rast_jit_vm::ast::Node::Cast { expr: #expr, ty: #ty } }
+        }
+
+        Node::Declare {
+            name,
+            ty: decl_ty,
+            value,
+        } => {
+            let decl_ty = match decl_ty {
+                Some(decl_ty) => {
+                    let decl_ty = ty(decl_ty);
+
+                    quote! { ::core::option::Option::Some(#decl_ty) }
+                }
+
+                None => quote! { ::core::option::Option::None },
+            };
+
+            let value = boxed(value);
+
+            quote! {
+                ::rast_jit_vm::ast::Node::Declare { name: #name, ty: #decl_ty, value: #value }
+            }
+        }
+
+        Node::DeclareUninit { name, ty: decl_ty } => {
+            let decl_ty = match decl_ty {
+                Some(decl_ty) => {
+                    let decl_ty = ty(decl_ty);
+
+                    quote! { ::core::option::Option::Some(#decl_ty) }
+                }
+
+                None => quote! { ::core::option::Option::None },
+            };
+
+            quote! { ::rast_jit_vm::ast::Node::DeclareUninit { name: #name, ty: #decl_ty } }
+        }
+
+        Node::Destructure { pat, value } => {
+            let pat = pattern(pat);
+            let value = boxed(value);
+
+            quote! { ::rast_jit_vm::ast::Node::Destructure { pat: #pat, value: #value } }
+        }
+
+        Node::Assign { name, value } => {
+            let value = boxed(value);
+
+            quote! { ::rast_jit_vm::ast::Node::Assign { name: #name, value: #value } }
+        }
+
+        Node::While { cond, body } => {
+            let cond = boxed(cond);
+            let body = boxed(body);
+
+            quote! { ::rast_jit_vm::ast::Node::While { cond: #cond, body: #body } }
+        }
+
+        Node::Try {
+            body,
+            name,
+            handler,
+        } => {
+            let body = boxed(body);
+            let handler = boxed(handler);
+
+            quote! {
+                ::rast_jit_vm::ast::Node::Try { body: #body, name: #name, handler: #handler }
+            }
+        }
+
+        Node::Print(nodes) => {
+            let nodes = nodes.iter().map(self::node);
+
+            quote! { ::rast_jit_vm::ast::Node::Print(::std::vec![#(#nodes),*]) }
+        }
+
+        Node::Println(nodes) => {
+            let nodes = nodes.iter().map(self::node);
+
+            quote! { ::rast_jit_vm::ast::Node::Println(::std::vec![#(#nodes),*]) }
+        }
+
+        Node::Format { fmt, args } => {
+            let fmt = fmt.as_ref();
+            let args = args.iter().map(self::node);
+
+            quote! {
+                ::rast_jit_vm::ast::Node::Format {
+                    fmt: ::std::borrow::Cow::Borrowed(#fmt),
+                    args: ::std::vec![#(#args),*],
+                }
+            }
+        }
+
+        Node::Block(nodes) => {
+            let nodes = nodes.iter().map(self::node);
+
+            quote! { ::rast_jit_vm::ast::Node::Block(::std::vec![#(#nodes),*]) }
+        }
+
+        _ => unreachable!(),
+    }
+}
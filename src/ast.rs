@@ -1,24 +1,82 @@
+mod binary;
+mod binop;
+mod diagnostic;
+mod dot;
+mod format;
 mod from_value;
 mod into_value;
-
-pub use self::{from_value::*, into_value::*};
-
-#[derive(Clone, Debug)]
+mod lint;
+mod optimize;
+mod parse;
+mod pretty;
+mod sexpr;
+mod span;
+mod typecheck;
+mod visit;
+
+pub use self::{
+    binary::*, diagnostic::*, dot::*, from_value::*, into_value::*, lint::*, optimize::*, parse::*,
+    sexpr::*, span::*, typecheck::*, visit::*,
+};
+pub(crate) use self::{binop::*, format::*};
+use std::borrow::Cow;
+use std::fmt;
+
+/// # Serialization
+///
+/// With the `serde` feature enabled, all AST types implement `Serialize` and
+/// `Deserialize`; the shape follows serde's defaults and is kept stable:
+///
+/// - structs (`Program`) are maps with field names as keys,
+/// - unit variants (`Type::Int`, `Pattern::Ignore`, ...) are strings with the
+///   variant's name (`"Int"`),
+/// - other variants are single-key maps from the variant's name to its
+///   payload - e.g. `{"Var": "x"}`, `{"Tuple": ["Int", "Str"]}` or
+///   `{"Add": {"lhs": ..., "rhs": ...}}`.
+///
+/// So `fn(input: int) -> int { input * 2 }` becomes (in JSON, with spans
+/// removed - see [`Program::without_spans()`]):
+///
+/// ```text
+/// {
+///   "input": "Int",
+///   "output": "Int",
+///   "body": {
+///     "Block": [
+///       {
+///         "Mul": {
+///           "lhs": { "Var": "input" },
+///           "rhs": { "Const": { "Int": 2 } }
+///         }
+///       }
+///     ]
+///   }
+/// }
+/// ```
+///
+/// Deserialization borrows identifiers and strings from the input, which
+/// means that identifiers cannot contain escape sequences (strings can, in
+/// which case they get allocated). Non-finite floats are not representable in
+/// JSON.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Program<'s> {
     pub input: Type,
     pub output: Type,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub body: Node<'s>,
 }
 
 pub type Ident<'s> = &'s str;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Node<'s> {
     /// E.g. `123`
-    Const(Value<'s>),
+    Const(#[cfg_attr(feature = "serde", serde(borrow))] Value<'s>),
 
     /// `name`
-    Var(Ident<'s>),
+    Var(#[cfg_attr(feature = "serde", serde(borrow))] Ident<'s>),
 
     /// `expr.idx`
     ExtractTuple { expr: Box<Self>, idx: usize },
@@ -68,23 +126,112 @@ pub enum Node<'s> {
     /// `lhs || rhs`
     Or { lhs: Box<Self>, rhs: Box<Self> },
 
-    /// `var name = value;`
-    Declare { name: Ident<'s>, value: Box<Self> },
+    /// `var name = value;` or `var name: ty = value;`
+    ///
+    /// Without `ty`, variable's type is inferred from `value`; with it, `value`
+    /// must be of that type.
+    Declare {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        name: Ident<'s>,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        ty: Option<Type>,
+        value: Box<Self>,
+    },
+
+    /// `var name;` or `var name: ty;`
+    ///
+    /// Declares a variable without initializing it - it has to be assigned to
+    /// before it's read. Without `ty`, variable's type is inferred from the
+    /// values assigned to it (see [`typecheck()`]).
+    DeclareUninit {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        name: Ident<'s>,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        ty: Option<Type>,
+    },
+
+    /// `var (name, name, ...) = value;`
+    Destructure {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        pat: Pattern<'s>,
+        value: Box<Self>,
+    },
 
     /// `name = value`
-    Assign { name: Ident<'s>, value: Box<Self> },
+    Assign {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        name: Ident<'s>,
+        value: Box<Self>,
+    },
 
     /// `while cond { body }`
     While { cond: Box<Self>, body: Box<Self> },
 
+    /// `try { body } catch name { handler }`
+    ///
+    /// If `body` fails at runtime (e.g. divides by zero), variables declared
+    /// inside it are discarded, the error message gets bound to `name` (as a
+    /// `str`) and `handler` is run instead; both branches must have the same
+    /// type. `name` is visible only inside `handler`.
+    Try {
+        body: Box<Self>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        name: Ident<'s>,
+        handler: Box<Self>,
+    },
+
     /// `print(node, node, ...)`
     Print(Vec<Self>),
 
+    /// `println(node, node, ...)`
+    ///
+    /// Same as [`Node::Print`], but prints a newline at the end.
+    Println(Vec<Self>),
+
+    /// `format("fmt", node, node, ...)`
+    ///
+    /// Returns a `str`; each `{}` in `fmt` gets replaced with the next
+    /// argument, with Rust-like specs (`{:[[fill]align][0][width][.precision]}`)
+    /// being supported as well.
+    Format {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        fmt: Cow<'s, str>,
+        args: Vec<Self>,
+    },
+
     /// `{ node node ... }`
     Block(Vec<Self>),
+
+    /// `node`, along with its location in the source code
+    ///
+    /// [`parse()`] wraps every node it creates, so that errors can point at
+    /// the culprit; nodes built by hand usually don't have spans. This node
+    /// doesn't change what the program does.
+    Spanned { span: Span, node: Box<Self> },
+}
+
+/// Left-hand side of [`Node::Destructure`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern<'s> {
+    /// `name`
+    Var(#[cfg_attr(feature = "serde", serde(borrow))] Ident<'s>),
+
+    /// `_`
+    Ignore,
+
+    /// `(pat, pat, ...)`
+    Tuple(Vec<Self>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     Unit,
     Bool,
@@ -96,36 +243,25 @@ pub enum Type {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value<'s> {
     Unit,
     Bool(bool),
     Char(char),
     Int(i64),
     Float(f32),
-    Str(&'s str),
+
+    /// Strings are usually borrowed from the program, but can be also created
+    /// at runtime (e.g. by [`Node::Format`])
+    Str(#[cfg_attr(feature = "serde", serde(borrow))] Cow<'s, str>),
+
     Tuple(Vec<Self>),
 }
 
 impl<'s> Value<'s> {
+    /// Prints the value to stdout; see the [`fmt::Display`] impl.
     pub fn print(&self) {
-        match self {
-            Value::Unit => print!("()"),
-            Value::Bool(value) => print!("{}", value),
-            Value::Char(value) => print!("{}", value),
-            Value::Int(value) => print!("{}", value),
-            Value::Float(value) => print!("{}", value),
-            Value::Str(value) => print!("{}", value),
-
-            Value::Tuple(values) => {
-                print!("(");
-
-                for value in values {
-                    value.print();
-                }
-
-                print!(")");
-            }
-        }
+        print!("{}", self);
     }
 
     pub fn ty(&self) -> Type {
@@ -157,3 +293,49 @@ impl<'s> Value<'s> {
         }
     }
 }
+
+impl fmt::Display for Value<'_> {
+    /// Formats the value the way `print()` outputs it.
+    ///
+    /// Precision (`{:.3}`) is respected by floats, also when they are nested
+    /// inside tuples.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Char(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+
+            Value::Float(value) => match f.precision() {
+                Some(precision) => write!(f, "{:.*}", precision, value),
+
+                // Contrary to `{}`, `{:?}` always prints the decimal point,
+                // so that `1.0` doesn't look like an integer
+                None => write!(f, "{:?}", value),
+            },
+
+            Value::Str(value) => write!(f, "{}", value),
+
+            Value::Tuple(values) => {
+                write!(f, "(")?;
+
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    match f.precision() {
+                        Some(precision) => write!(f, "{:.*}", precision, value)?,
+                        None => write!(f, "{}", value)?,
+                    }
+                }
+
+                if values.len() == 1 {
+                    write!(f, ",")?;
+                }
+
+                write!(f, ")")
+            }
+        }
+    }
+}
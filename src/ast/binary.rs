@@ -0,0 +1,714 @@
+//! Compact binary encoding of programs.
+//!
+//! Layout (version 3):
+//!
+//! ```text
+//! program = "RAST" version:u8 type type node
+//! type    = tag:u8 [len:varint type*]
+//! option  = 0 | 1 payload
+//! value   = tag:u8 [payload]
+//! pattern = tag:u8 [str | len:varint pattern*]
+//! node    = tag:u8 [payload]
+//! str     = len:varint utf8-bytes
+//! span    = start:varint end:varint line:varint column:varint
+//! ```
+//!
+//! ... where `varint` is an unsigned LEB128 number (at most 10 bytes); ints
+//! are zigzag-encoded varints, floats are 4 little-endian bytes, chars are
+//! varints and bools are single bytes (`0` or `1`). Tags are indices of the
+//! enum variants, in the order they are declared in (e.g. `Node::Var` is `1`),
+//! except for `Node::DeclareUninit`, which is `28` (it's been added later, so
+//! it got the next free tag); variant fields are encoded in the order they
+//! are declared in.
+//!
+//! Versions 1 and 2 are still accepted by the decoder - they are the same,
+//! except that they don't have `Node::DeclareUninit` and that in version 1
+//! `Node::Declare` doesn't have the type annotation.
+
+use crate::ast::*;
+use std::borrow::Cow;
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"RAST";
+const VERSION: u8 = 3;
+
+/// Maximum nesting of nodes, types, values and patterns the decoder accepts;
+/// that's to avoid overflowing the stack on malicious input.
+const MAX_DEPTH: usize = 256;
+
+/// Encodes program into the binary format; see [`decode()`].
+pub fn encode(prog: &Program) -> Vec<u8> {
+    let mut enc = Encoder { out: Vec::new() };
+
+    enc.out.extend_from_slice(MAGIC);
+    enc.out.push(VERSION);
+    enc.ty(&prog.input);
+    enc.ty(&prog.output);
+    enc.node(&prog.body);
+    enc.out
+}
+
+/// Decodes program encoded with [`encode()`].
+///
+/// Identifiers and strings are borrowed from `bytes`. Malformed input (e.g.
+/// truncated, nested too deeply or containing invalid UTF-8) is rejected with
+/// an error; the decoded program is not type-checked, though.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = parse("fn(input: int) -> int { input * 2 }").unwrap();
+/// let bytes = encode(&prog);
+///
+/// assert_eq!(prog, decode(&bytes).unwrap());
+/// ```
+pub fn decode(bytes: &[u8]) -> Result<Program<'_>, DecodeError> {
+    let mut dec = Decoder {
+        bytes,
+        pos: 0,
+        depth: 0,
+        version: VERSION,
+    };
+
+    if bytes.get(..MAGIC.len()) != Some(MAGIC) {
+        return Err(DecodeError::new(0, "missing magic header"));
+    }
+
+    dec.pos = MAGIC.len();
+
+    dec.version = dec.u8()?;
+
+    if !(1..=VERSION).contains(&dec.version) {
+        return Err(DecodeError::new(
+            MAGIC.len(),
+            format!(
+                "unsupported version {} (expected 1 to {})",
+                dec.version, VERSION
+            ),
+        ));
+    }
+
+    let input = dec.ty()?;
+    let output = dec.ty()?;
+    let body = dec.node()?;
+
+    if dec.pos != bytes.len() {
+        return Err(DecodeError::new(dec.pos, "unexpected trailing bytes"));
+    }
+
+    Ok(Program {
+        input,
+        output,
+        body,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    pub message: String,
+
+    /// Byte offset at which the error was detected
+    pub offset: usize,
+}
+
+impl DecodeError {
+    pub(crate) fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+struct Encoder {
+    out: Vec<u8>,
+}
+
+impl Encoder {
+    fn varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+
+            value >>= 7;
+
+            if value == 0 {
+                self.out.push(byte);
+                return;
+            }
+
+            self.out.push(byte | 0x80);
+        }
+    }
+
+    fn len(&mut self, len: usize) {
+        self.varint(len as u64);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.out.extend_from_slice(value.as_bytes());
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        match ty {
+            Type::Unit => self.out.push(0),
+            Type::Bool => self.out.push(1),
+            Type::Char => self.out.push(2),
+            Type::Int => self.out.push(3),
+            Type::Float => self.out.push(4),
+            Type::Str => self.out.push(5),
+
+            Type::Tuple(tys) => {
+                self.out.push(6);
+                self.len(tys.len());
+
+                for ty in tys {
+                    self.ty(ty);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Unit => self.out.push(0),
+
+            Value::Bool(value) => {
+                self.out.push(1);
+                self.out.push(*value as u8);
+            }
+
+            Value::Char(value) => {
+                self.out.push(2);
+                self.varint(*value as u64);
+            }
+
+            Value::Int(value) => {
+                self.out.push(3);
+                self.varint(((value << 1) ^ (value >> 63)) as u64);
+            }
+
+            Value::Float(value) => {
+                self.out.push(4);
+                self.out.extend_from_slice(&value.to_le_bytes());
+            }
+
+            Value::Str(value) => {
+                self.out.push(5);
+                self.str(value);
+            }
+
+            Value::Tuple(values) => {
+                self.out.push(6);
+                self.len(values.len());
+
+                for value in values {
+                    self.value(value);
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pat: &Pattern) {
+        match pat {
+            Pattern::Var(name) => {
+                self.out.push(0);
+                self.str(name);
+            }
+
+            Pattern::Ignore => self.out.push(1),
+
+            Pattern::Tuple(pats) => {
+                self.out.push(2);
+                self.len(pats.len());
+
+                for pat in pats {
+                    self.pattern(pat);
+                }
+            }
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[Node]) {
+        self.len(nodes.len());
+
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        self.out.push(node_tag(node));
+
+        if let Some((_, lhs, rhs)) = BinOp::of(node) {
+            self.node(lhs);
+            self.node(rhs);
+            return;
+        }
+
+        match node {
+            Node::Const(value) => self.value(value),
+            Node::Var(name) => self.str(name),
+
+            Node::ExtractTuple { expr, idx } => {
+                self.node(expr);
+                self.len(*idx);
+            }
+
+            Node::ExtractArray { expr, idx } => {
+                self.node(expr);
+                self.node(idx);
+            }
+
+            Node::Cast { expr, ty } => {
+                self.node(expr);
+                self.ty(ty);
+            }
+
+            Node::Declare { name, ty, value } => {
+                self.str(name);
+
+                match ty {
+                    Some(ty) => {
+                        self.out.push(1);
+                        self.ty(ty);
+                    }
+
+                    None => self.out.push(0),
+                }
+
+                self.node(value);
+            }
+
+            Node::DeclareUninit { name, ty } => {
+                self.str(name);
+
+                match ty {
+                    Some(ty) => {
+                        self.out.push(1);
+                        self.ty(ty);
+                    }
+
+                    None => self.out.push(0),
+                }
+            }
+
+            Node::Assign { name, value } => {
+                self.str(name);
+                self.node(value);
+            }
+
+            Node::Destructure { pat, value } => {
+                self.pattern(pat);
+                self.node(value);
+            }
+
+            Node::While { cond, body } => {
+                self.node(cond);
+                self.node(body);
+            }
+
+            Node::Try {
+                body,
+                name,
+                handler,
+            } => {
+                self.node(body);
+                self.str(name);
+                self.node(handler);
+            }
+
+            Node::Print(nodes) | Node::Println(nodes) | Node::Block(nodes) => {
+                self.nodes(nodes);
+            }
+
+            Node::Format { fmt, args } => {
+                self.str(fmt);
+                self.nodes(args);
+            }
+
+            // Nested spans are not supported by the decoder, so only the
+            // outermost one is kept
+            Node::Spanned { span, node } => {
+                self.len(span.start);
+                self.len(span.end);
+                self.len(span.line);
+                self.len(span.column);
+                self.node(node.unspanned());
+            }
+
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn node_tag(node: &Node) -> u8 {
+    match node {
+        Node::Const(_) => 0,
+        Node::Var(_) => 1,
+        Node::ExtractTuple { .. } => 2,
+        Node::ExtractArray { .. } => 3,
+        Node::Cast { .. } => 4,
+        Node::Add { .. } => 5,
+        Node::Sub { .. } => 6,
+        Node::Mul { .. } => 7,
+        Node::Div { .. } => 8,
+        Node::Mod { .. } => 9,
+        Node::Eq { .. } => 10,
+        Node::Neq { .. } => 11,
+        Node::Gt { .. } => 12,
+        Node::GtEq { .. } => 13,
+        Node::Lt { .. } => 14,
+        Node::LtEq { .. } => 15,
+        Node::And { .. } => 16,
+        Node::Or { .. } => 17,
+        Node::Declare { .. } => 18,
+        Node::Destructure { .. } => 19,
+        Node::Assign { .. } => 20,
+        Node::While { .. } => 21,
+        Node::Try { .. } => 22,
+        Node::Print(_) => 23,
+        Node::Println(_) => 24,
+        Node::Format { .. } => 25,
+        Node::Block(_) => 26,
+        Node::Spanned { .. } => 27,
+        Node::DeclareUninit { .. } => 28,
+    }
+}
+
+struct Decoder<'s> {
+    bytes: &'s [u8],
+    pos: usize,
+    depth: usize,
+
+    /// Version of the format being decoded
+    version: u8,
+}
+
+impl<'s> Decoder<'s> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> DecodeError {
+        DecodeError::new(offset, message)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| self.error(self.pos, "unexpected end of input"))?;
+
+        self.pos += 1;
+
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'s [u8], DecodeError> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or_else(|| self.error(self.pos, "unexpected end of input"))?;
+
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let start = self.pos;
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            let bits = (byte & 0x7f) as u64;
+
+            // The 10th byte can carry just a single bit
+            if shift == 63 && bits > 1 {
+                break;
+            }
+
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(self.error(start, "varint is too large"))
+    }
+
+    fn usize(&mut self) -> Result<usize, DecodeError> {
+        let start = self.pos;
+        let value = self.varint()?;
+
+        usize::try_from(value).map_err(|_| self.error(start, "number is too large"))
+    }
+
+    /// Reads length of a sequence whose each element takes at least one
+    /// byte; this makes it impossible to preallocate too much memory.
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let start = self.pos;
+        let len = self.usize()?;
+
+        if len > self.bytes.len() - self.pos {
+            return Err(self.error(start, "length exceeds the input"));
+        }
+
+        Ok(len)
+    }
+
+    fn str(&mut self) -> Result<&'s str, DecodeError> {
+        let len = self.len()?;
+        let start = self.pos;
+        let bytes = self.bytes(len)?;
+
+        std::str::from_utf8(bytes).map_err(|_| self.error(start, "invalid UTF-8"))
+    }
+
+    fn tag(&mut self, max: u8, what: &str) -> Result<u8, DecodeError> {
+        let tag = self.u8()?;
+
+        if tag > max {
+            return Err(self.error(self.pos - 1, format!("invalid {} tag: {}", what, tag)));
+        }
+
+        Ok(tag)
+    }
+
+    fn nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(self.pos, "nesting is too deep"));
+        }
+
+        self.depth += 1;
+
+        let result = f(self);
+
+        self.depth -= 1;
+
+        result
+    }
+
+    fn seq<T>(
+        &mut self,
+        f: impl Fn(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Vec<T>, DecodeError> {
+        let len = self.len()?;
+
+        (0..len).map(|_| f(self)).collect()
+    }
+
+    fn option<T>(
+        &mut self,
+        f: impl Fn(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Option<T>, DecodeError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => f(self).map(Some),
+            _ => Err(self.error(self.pos - 1, "invalid option")),
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type, DecodeError> {
+        self.nested(|this| {
+            Ok(match this.tag(6, "type")? {
+                0 => Type::Unit,
+                1 => Type::Bool,
+                2 => Type::Char,
+                3 => Type::Int,
+                4 => Type::Float,
+                5 => Type::Str,
+                _ => Type::Tuple(this.seq(Self::ty)?),
+            })
+        })
+    }
+
+    fn value(&mut self) -> Result<Value<'s>, DecodeError> {
+        self.nested(|this| {
+            Ok(match this.tag(6, "value")? {
+                0 => Value::Unit,
+
+                1 => match this.u8()? {
+                    0 => Value::Bool(false),
+                    1 => Value::Bool(true),
+                    _ => return Err(this.error(this.pos - 1, "invalid bool")),
+                },
+
+                2 => {
+                    let start = this.pos;
+
+                    u32::try_from(this.varint()?)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .map(Value::Char)
+                        .ok_or_else(|| this.error(start, "invalid char"))?
+                }
+
+                3 => {
+                    let value = this.varint()?;
+
+                    Value::Int(((value >> 1) as i64) ^ -((value & 1) as i64))
+                }
+
+                4 => Value::Float(f32::from_le_bytes(this.bytes(4)?.try_into().unwrap())),
+                5 => Value::Str(Cow::Borrowed(this.str()?)),
+                _ => Value::Tuple(this.seq(Self::value)?),
+            })
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern<'s>, DecodeError> {
+        self.nested(|this| {
+            Ok(match this.tag(2, "pattern")? {
+                0 => Pattern::Var(this.str()?),
+                1 => Pattern::Ignore,
+                _ => Pattern::Tuple(this.seq(Self::pattern)?),
+            })
+        })
+    }
+
+    fn boxed(&mut self) -> Result<Box<Node<'s>>, DecodeError> {
+        self.node().map(Box::new)
+    }
+
+    fn node(&mut self) -> Result<Node<'s>, DecodeError> {
+        self.nested(|this| {
+            let tag = this.node_tag()?;
+
+            // Spans don't count towards the nesting limit (so that parsed
+            // programs can be as deep as the ones built by hand), but they
+            // can't be nested either
+            if tag == 27 {
+                let span = Span {
+                    start: this.usize()?,
+                    end: this.usize()?,
+                    line: this.usize()?,
+                    column: this.usize()?,
+                };
+
+                let tag = this.node_tag()?;
+
+                if tag == 27 {
+                    return Err(this.error(this.pos - 1, "invalid node tag: 27"));
+                }
+
+                return Ok(Node::Spanned {
+                    span,
+                    node: Box::new(this.node_of(tag)?),
+                });
+            }
+
+            this.node_of(tag)
+        })
+    }
+
+    fn node_tag(&mut self) -> Result<u8, DecodeError> {
+        let max = if self.version >= 3 { 28 } else { 27 };
+
+        self.tag(max, "node")
+    }
+
+    /// Decodes node's payload, given its (already read) tag.
+    fn node_of(&mut self, tag: u8) -> Result<Node<'s>, DecodeError> {
+        Ok(match tag {
+            0 => Node::Const(self.value()?),
+            1 => Node::Var(self.str()?),
+
+            2 => Node::ExtractTuple {
+                expr: self.boxed()?,
+                idx: self.usize()?,
+            },
+
+            3 => Node::ExtractArray {
+                expr: self.boxed()?,
+                idx: self.boxed()?,
+            },
+
+            4 => Node::Cast {
+                expr: self.boxed()?,
+                ty: self.ty()?,
+            },
+
+            5..=17 => {
+                let op = match tag {
+                    5 => BinOp::Add,
+                    6 => BinOp::Sub,
+                    7 => BinOp::Mul,
+                    8 => BinOp::Div,
+                    9 => BinOp::Mod,
+                    10 => BinOp::Eq,
+                    11 => BinOp::Neq,
+                    12 => BinOp::Gt,
+                    13 => BinOp::GtEq,
+                    14 => BinOp::Lt,
+                    15 => BinOp::LtEq,
+                    16 => BinOp::And,
+                    _ => BinOp::Or,
+                };
+
+                let lhs = self.node()?;
+                let rhs = self.node()?;
+
+                op.build(lhs, rhs)
+            }
+
+            18 => Node::Declare {
+                name: self.str()?,
+                ty: if self.version >= 2 {
+                    self.option(Self::ty)?
+                } else {
+                    None
+                },
+                value: self.boxed()?,
+            },
+
+            19 => Node::Destructure {
+                pat: self.pattern()?,
+                value: self.boxed()?,
+            },
+
+            20 => Node::Assign {
+                name: self.str()?,
+                value: self.boxed()?,
+            },
+
+            21 => Node::While {
+                cond: self.boxed()?,
+                body: self.boxed()?,
+            },
+
+            22 => Node::Try {
+                body: self.boxed()?,
+                name: self.str()?,
+                handler: self.boxed()?,
+            },
+
+            23 => Node::Print(self.seq(Self::node)?),
+            24 => Node::Println(self.seq(Self::node)?),
+
+            25 => Node::Format {
+                fmt: Cow::Borrowed(self.str()?),
+                args: self.seq(Self::node)?,
+            },
+
+            26 => Node::Block(self.seq(Self::node)?),
+
+            _ => Node::DeclareUninit {
+                name: self.str()?,
+                ty: self.option(Self::ty)?,
+            },
+        })
+    }
+}
@@ -0,0 +1,124 @@
+use crate::ast::*;
+
+/// Binary operator, as understood by the parser and the pretty-printer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BinOp {
+    Or,
+    And,
+    Eq,
+    Neq,
+    Gt,
+    GtEq,
+    Lt,
+    LtEq,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl BinOp {
+    /// Precedence of the comparison operators, which are non-associative.
+    pub const CMP_PREC: u8 = 3;
+
+    /// Precedence of the tightest-binding binary operator.
+    pub const MAX_PREC: u8 = 5;
+
+    /// Returns operator's binding power; the higher, the tighter.
+    pub fn prec(self) -> u8 {
+        match self {
+            BinOp::Or => 1,
+            BinOp::And => 2,
+            BinOp::Eq | BinOp::Neq | BinOp::Gt | BinOp::GtEq | BinOp::Lt | BinOp::LtEq => {
+                Self::CMP_PREC
+            }
+            BinOp::Add | BinOp::Sub => 4,
+            BinOp::Mul | BinOp::Div | BinOp::Mod => Self::MAX_PREC,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Or => "||",
+            BinOp::And => "&&",
+            BinOp::Eq => "==",
+            BinOp::Neq => "!=",
+            BinOp::Gt => ">",
+            BinOp::GtEq => ">=",
+            BinOp::Lt => "<",
+            BinOp::LtEq => "<=",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+        }
+    }
+
+    /// Inverse of [`Self::symbol()`].
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        let op = match symbol {
+            "||" => BinOp::Or,
+            "&&" => BinOp::And,
+            "==" => BinOp::Eq,
+            "!=" => BinOp::Neq,
+            ">" => BinOp::Gt,
+            ">=" => BinOp::GtEq,
+            "<" => BinOp::Lt,
+            "<=" => BinOp::LtEq,
+            "+" => BinOp::Add,
+            "-" => BinOp::Sub,
+            "*" => BinOp::Mul,
+            "/" => BinOp::Div,
+            "%" => BinOp::Mod,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    /// If `node` is a binary operation, returns its operator and operands.
+    pub fn of<'a, 's>(node: &'a Node<'s>) -> Option<(Self, &'a Node<'s>, &'a Node<'s>)> {
+        let (op, lhs, rhs) = match node {
+            Node::Or { lhs, rhs } => (BinOp::Or, lhs, rhs),
+            Node::And { lhs, rhs } => (BinOp::And, lhs, rhs),
+            Node::Eq { lhs, rhs } => (BinOp::Eq, lhs, rhs),
+            Node::Neq { lhs, rhs } => (BinOp::Neq, lhs, rhs),
+            Node::Gt { lhs, rhs } => (BinOp::Gt, lhs, rhs),
+            Node::GtEq { lhs, rhs } => (BinOp::GtEq, lhs, rhs),
+            Node::Lt { lhs, rhs } => (BinOp::Lt, lhs, rhs),
+            Node::LtEq { lhs, rhs } => (BinOp::LtEq, lhs, rhs),
+            Node::Add { lhs, rhs } => (BinOp::Add, lhs, rhs),
+            Node::Sub { lhs, rhs } => (BinOp::Sub, lhs, rhs),
+            Node::Mul { lhs, rhs } => (BinOp::Mul, lhs, rhs),
+            Node::Div { lhs, rhs } => (BinOp::Div, lhs, rhs),
+            Node::Mod { lhs, rhs } => (BinOp::Mod, lhs, rhs),
+            _ => return None,
+        };
+
+        Some((op, lhs, rhs))
+    }
+
+    /// Creates a binary operation.
+    pub fn build<'s>(self, lhs: Node<'s>, rhs: Node<'s>) -> Node<'s> {
+        let lhs = Box::new(lhs);
+        let rhs = Box::new(rhs);
+
+        match self {
+            BinOp::Or => Node::Or { lhs, rhs },
+            BinOp::And => Node::And { lhs, rhs },
+            BinOp::Eq => Node::Eq { lhs, rhs },
+            BinOp::Neq => Node::Neq { lhs, rhs },
+            BinOp::Gt => Node::Gt { lhs, rhs },
+            BinOp::GtEq => Node::GtEq { lhs, rhs },
+            BinOp::Lt => Node::Lt { lhs, rhs },
+            BinOp::LtEq => Node::LtEq { lhs, rhs },
+            BinOp::Add => Node::Add { lhs, rhs },
+            BinOp::Sub => Node::Sub { lhs, rhs },
+            BinOp::Mul => Node::Mul { lhs, rhs },
+            BinOp::Div => Node::Div { lhs, rhs },
+            BinOp::Mod => Node::Mod { lhs, rhs },
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::ast::*;
+use std::fmt;
+
+/// Renders type errors rustc-style, i.e. with their codes and the lines of
+/// `src` they point at; `src` must be the source code the program has been
+/// parsed from.
+///
+/// Errors without spans (e.g. coming from programs built by hand) are
+/// rendered without snippets.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let src = "fn(input: int) -> int {\n    var x = y;\n    input = x;\n    x\n}";
+/// let errs = typecheck(&parse(src).unwrap()).unwrap_err();
+///
+/// assert_eq!(
+///     "\
+/// error[E0001]: Unknown variable: y
+///  --> 2:13
+///   |
+/// 2 |     var x = y;
+///   |             ^
+///
+/// error[E0003]: `input` variable is read-only
+///  --> 3:5
+///   |
+/// 3 |     input = x;
+///   |     ^^^^^^^^^
+/// ",
+///     render_errors(src, &errs)
+/// );
+/// ```
+pub fn render_errors(src: &str, errs: &[TypeError]) -> String {
+    let items = errs
+        .iter()
+        .map(|err| {
+            let header = format!("error[{}]: {}", err.kind.code(), err.kind);
+
+            (header, err.span)
+        })
+        .collect();
+
+    Diagnostics { src, items }.to_string()
+}
+
+/// Renders lints the same way [`render_errors()`] renders type errors, with
+/// lints' names in place of the codes; lints set to [`Level::Deny`] are
+/// rendered as errors.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let src = "fn(input: int) {\n    while false { }\n}";
+/// let lints = lint(&parse(src).unwrap(), &LintLevels::default());
+///
+/// assert_eq!(
+///     "\
+/// warning[constant_condition]: Loop's condition is always `false`, so its body never runs
+///  --> 2:5
+///   |
+/// 2 |     while false { }
+///   |     ^^^^^^^^^^^^^^^
+/// ",
+///     render_lints(src, &lints)
+/// );
+/// ```
+pub fn render_lints(src: &str, lints: &[Lint]) -> String {
+    let items = lints
+        .iter()
+        .map(|lint| {
+            let level = match lint.level {
+                Level::Deny => "error",
+                _ => "warning",
+            };
+
+            let header = format!("{}[{}]: {}", level, lint.kind.name(), lint.message);
+
+            (header, lint.span)
+        })
+        .collect();
+
+    Diagnostics { src, items }.to_string()
+}
+
+struct Diagnostics<'a> {
+    src: &'a str,
+
+    /// Headers (e.g. `error[E0001]: Unknown variable: y`) along with spans
+    /// to show snippets for
+    items: Vec<(String, Option<Span>)>,
+}
+
+impl fmt::Display for Diagnostics<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (header, span)) in self.items.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "{}", header)?;
+
+            if let Some(span) = span {
+                self.snippet(f, *span)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Diagnostics<'_> {
+    fn snippet(&self, f: &mut fmt::Formatter<'_>, span: Span) -> fmt::Result {
+        let line_start = self.src[..span.start].rfind('\n').map_or(0, |idx| idx + 1);
+
+        let line_end = self.src[span.start..]
+            .find('\n')
+            .map_or(self.src.len(), |idx| span.start + idx);
+
+        let line = self.src[line_start..line_end].trim_end_matches('\r');
+
+        // Tabs are kept as they are, so that the markers line up with the
+        // code no matter how wide the terminal renders them
+        let indent: String = self.src[line_start..span.start]
+            .chars()
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        // Multi-line nodes get underlined up to the end of their first line
+        let markers = self.src[span.start..span.end.clamp(span.start, line_end)]
+            .trim_end_matches('\r')
+            .chars()
+            .count()
+            .max(1);
+
+        let gutter = " ".repeat(span.line.to_string().len());
+
+        writeln!(f, "{}--> {}", gutter, span)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", span.line, line)?;
+        writeln!(f, "{} | {}{}", gutter, indent, "^".repeat(markers))
+    }
+}
@@ -0,0 +1,214 @@
+use crate::ast::*;
+use std::fmt::Write;
+
+/// Renders program as a [Graphviz](https://graphviz.org) graph, with one
+/// graph node per AST node.
+///
+/// Edges are labelled with the field they come from (`lhs`, `cond`, ...);
+/// nodes that have spans (see [`Node::Spanned`]) get their location printed
+/// below the label. See also `vm::to_dot()`, which additionally includes
+/// types inferred by the compiler.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = rast! { fn(input: int) -> int { input * 2 } };
+///
+/// assert_eq!(
+///     r#"digraph program {
+///     node [shape=box, fontname="monospace"];
+///     n0 [label="fn(input: int) -> int", shape=oval];
+///     n1 [label="{ }"];
+///     n0 -> n1 [label="body"];
+///     n2 [label="*"];
+///     n1 -> n2;
+///     n3 [label="input"];
+///     n2 -> n3 [label="lhs"];
+///     n4 [label="2"];
+///     n2 -> n4 [label="rhs"];
+/// }
+/// "#,
+///     to_dot(&prog)
+/// );
+/// ```
+pub fn to_dot(prog: &Program) -> String {
+    to_dot_with_types(prog, None)
+}
+
+/// Renders program as a Graphviz graph; `types` (if present) contain types of
+/// all nodes, in the order [`Visitor`] visits them.
+pub(crate) fn to_dot_with_types(prog: &Program, types: Option<&[Type]>) -> String {
+    let mut dot = Dot {
+        out: String::new(),
+        types,
+        next_id: 0,
+        next_node: 0,
+    };
+
+    dot.line("digraph program {");
+    dot.line("    node [shape=box, fontname=\"monospace\"];");
+
+    let mut signature = format!("fn(input: {})", prog.input);
+
+    if prog.output != Type::Unit {
+        let _ = write!(signature, " -> {}", prog.output);
+    }
+
+    let root = dot.vertex(&signature, ", shape=oval");
+
+    dot.node(&prog.body, root, Some("body"), None);
+    dot.line("}");
+    dot.out
+}
+
+struct Dot<'a> {
+    out: String,
+    types: Option<&'a [Type]>,
+
+    /// Id of the next graph node
+    next_id: usize,
+
+    /// Index of the next AST node, in the order `Visitor` visits them (i.e.
+    /// including the `Node::Spanned` wrappers)
+    next_node: usize,
+}
+
+impl Dot<'_> {
+    fn node(&mut self, node: &Node, parent: usize, edge: Option<&str>, span: Option<Span>) {
+        let idx = self.next_node;
+
+        self.next_node += 1;
+
+        // Spans are not nodes on their own, they just annotate their child
+        if let Node::Spanned { span, node } = node {
+            return self.node(node, parent, edge, Some(*span));
+        }
+
+        let mut label = label(node);
+
+        if let Some(ty) = self.types.and_then(|types| types.get(idx)) {
+            let _ = write!(label, "\n: {}", ty);
+        }
+
+        if let Some(span) = span {
+            let _ = write!(label, "\n@ {}", span);
+        }
+
+        let id = self.vertex(&label, "");
+
+        match edge {
+            Some(edge) => self.line(&format!("    n{} -> n{} [label=\"{}\"];", parent, id, edge)),
+            None => self.line(&format!("    n{} -> n{};", parent, id)),
+        }
+
+        if let Some((_, lhs, rhs)) = BinOp::of(node) {
+            self.node(lhs, id, Some("lhs"), None);
+            self.node(rhs, id, Some("rhs"), None);
+            return;
+        }
+
+        match node {
+            Node::Const(_) | Node::Var(_) | Node::DeclareUninit { .. } => (),
+
+            Node::ExtractTuple { expr, .. } | Node::Cast { expr, .. } => {
+                self.node(expr, id, Some("expr"), None);
+            }
+
+            Node::ExtractArray { expr, idx } => {
+                self.node(expr, id, Some("expr"), None);
+                self.node(idx, id, Some("idx"), None);
+            }
+
+            Node::Declare { value, .. }
+            | Node::Destructure { value, .. }
+            | Node::Assign { value, .. } => {
+                self.node(value, id, Some("value"), None);
+            }
+
+            Node::While { cond, body } => {
+                self.node(cond, id, Some("cond"), None);
+                self.node(body, id, Some("body"), None);
+            }
+
+            Node::Try { body, handler, .. } => {
+                self.node(body, id, Some("body"), None);
+                self.node(handler, id, Some("handler"), None);
+            }
+
+            Node::Print(nodes)
+            | Node::Println(nodes)
+            | Node::Format { args: nodes, .. }
+            | Node::Block(nodes) => {
+                for node in nodes {
+                    self.node(node, id, None, None);
+                }
+            }
+
+            _ => unreachable!(),
+        }
+    }
+
+    /// Adds a graph node and returns its id.
+    fn vertex(&mut self, label: &str, attrs: &str) -> usize {
+        let id = self.next_id;
+
+        self.next_id += 1;
+        self.line(&format!(
+            "    n{} [label=\"{}\"{}];",
+            id,
+            escape(label),
+            attrs
+        ));
+
+        id
+    }
+
+    fn line(&mut self, line: &str) {
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+}
+
+fn label(node: &Node) -> String {
+    if let Some((op, _, _)) = BinOp::of(node) {
+        return op.symbol().into();
+    }
+
+    match node {
+        Node::Const(_) | Node::Var(_) | Node::DeclareUninit { .. } => node.to_string(),
+        Node::ExtractTuple { idx, .. } => format!(".{}", idx),
+        Node::ExtractArray { .. } => "[]".into(),
+        Node::Cast { ty, .. } => format!("as {}", ty),
+        Node::Declare { name, ty: None, .. } => format!("var {}", name),
+        Node::Declare {
+            name, ty: Some(ty), ..
+        } => format!("var {}: {}", name, ty),
+        Node::Destructure { pat, .. } => format!("var {}", pat),
+        Node::Assign { name, .. } => format!("{} =", name),
+        Node::While { .. } => "while".into(),
+        Node::Try { name, .. } => format!("try / catch {}", name),
+        Node::Print(_) => "print".into(),
+        Node::Println(_) => "println".into(),
+        Node::Format { fmt, .. } => format!("format({})", Node::Const(Value::Str(fmt.clone()))),
+        Node::Block(_) => "{ }".into(),
+        _ => unreachable!(),
+    }
+}
+
+/// Escapes label for use inside a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+
+    for ch in label.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            ch => out.push(ch),
+        }
+    }
+
+    out
+}
@@ -0,0 +1,328 @@
+use crate::ast::*;
+
+/// Parsed format string, as used by [`Node::Format`].
+///
+/// Supports a subset of Rust's syntax - each `{}` is replaced with the next
+/// argument, and arguments can be given an optional spec:
+///
+/// ```text
+/// {:[[fill]align][0][width][.precision]}
+/// ```
+///
+/// ... where `align` is one of `<`, `^` or `>`; `{{` and `}}` print literal
+/// braces.
+///
+/// Width and precision cannot exceed `65535` (just like in Rust).
+///
+/// Precision affects only floats (including the ones nested in tuples); the
+/// `0` flag affects only numbers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Format {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Lit(String),
+    Arg(Spec),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    zero: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Format {
+    pub fn parse(fmt: &str) -> Result<Self, String> {
+        let mut pieces = Vec::new();
+        let mut lit = String::new();
+        let mut chars = fmt.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    lit.push('{');
+                }
+
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    lit.push('}');
+                }
+
+                '{' => {
+                    let mut spec = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(ch) => spec.push(ch),
+                            None => return Err("unterminated `{`".into()),
+                        }
+                    }
+
+                    if !lit.is_empty() {
+                        pieces.push(Piece::Lit(std::mem::take(&mut lit)));
+                    }
+
+                    pieces.push(Piece::Arg(Spec::parse(&spec)?));
+                }
+
+                '}' => {
+                    return Err("unmatched `}` (use `}}` to print a brace)".into());
+                }
+
+                ch => {
+                    lit.push(ch);
+                }
+            }
+        }
+
+        if !lit.is_empty() {
+            pieces.push(Piece::Lit(lit));
+        }
+
+        Ok(Self { pieces })
+    }
+
+    /// Returns how many arguments this format string expects.
+    pub fn args(&self) -> usize {
+        self.pieces
+            .iter()
+            .filter(|piece| matches!(piece, Piece::Arg(_)))
+            .count()
+    }
+
+    /// Renders given arguments.
+    ///
+    /// If `args` contains a different number of values than [`Self::args()`],
+    /// panics.
+    pub fn render(&self, args: &[Value]) -> String {
+        assert_eq!(
+            self.args(),
+            args.len(),
+            "Format string expects {} argument(s), got {}",
+            self.args(),
+            args.len()
+        );
+
+        let mut out = String::new();
+        let mut args = args.iter();
+
+        for piece in &self.pieces {
+            match piece {
+                Piece::Lit(lit) => out.push_str(lit),
+                Piece::Arg(spec) => spec.render(&mut out, args.next().unwrap()),
+            }
+        }
+
+        out
+    }
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut this = Self::default();
+
+        let spec = match spec.strip_prefix(':') {
+            Some(spec) => spec,
+            None if spec.is_empty() => return Ok(this),
+            None => return Err(format!("invalid argument `{{{}}}`", spec)),
+        };
+
+        let mut chars: Vec<char> = spec.chars().collect();
+        let mut idx = 0;
+
+        // [[fill]align]
+        if let Some(align) = chars.get(1).copied().and_then(Align::parse) {
+            this.fill = Some(chars[0]);
+            this.align = Some(align);
+            idx = 2;
+        } else if let Some(align) = chars.first().copied().and_then(Align::parse) {
+            this.align = Some(align);
+            idx = 1;
+        }
+
+        // [0]
+        if chars.get(idx) == Some(&'0') {
+            this.zero = true;
+            idx += 1;
+        }
+
+        // [width]
+        this.width = Self::parse_number(&chars, &mut idx)?;
+
+        // [.precision]
+        if chars.get(idx) == Some(&'.') {
+            idx += 1;
+
+            this.precision = Some(
+                Self::parse_number(&chars, &mut idx)?
+                    .ok_or_else(|| format!("missing precision in `{{:{}}}`", spec))?,
+            );
+        }
+
+        if idx != chars.len() {
+            chars.drain(..idx);
+
+            return Err(format!(
+                "unexpected `{}` in `{{:{}}}`",
+                chars.into_iter().collect::<String>(),
+                spec
+            ));
+        }
+
+        Ok(this)
+    }
+
+    fn parse_number(chars: &[char], idx: &mut usize) -> Result<Option<usize>, String> {
+        let digits: String = chars[*idx..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_digit())
+            .collect();
+
+        if digits.is_empty() {
+            return Ok(None);
+        }
+
+        *idx += digits.len();
+
+        // Capped the same way as in Rust, so that e.g. `{:99999999999}` fails
+        // here instead of trying to allocate that much padding later
+        digits
+            .parse::<u16>()
+            .map(|number| Some(number as usize))
+            .map_err(|_| format!("number `{}` is too large", digits))
+    }
+
+    fn render(&self, out: &mut String, value: &Value) {
+        let rendered = match self.precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        };
+
+        let width = self.width.unwrap_or(0);
+        let len = rendered.chars().count();
+
+        if len >= width {
+            out.push_str(&rendered);
+            return;
+        }
+
+        let padding = width - len;
+        let is_number = matches!(value, Value::Int(_) | Value::Float(_));
+
+        if self.zero && is_number {
+            let (sign, digits) = match rendered.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", rendered.as_str()),
+            };
+
+            out.push_str(sign);
+            out.extend(std::iter::repeat('0').take(padding));
+            out.push_str(digits);
+
+            return;
+        }
+
+        let fill = self.fill.unwrap_or(' ');
+
+        let align = self
+            .align
+            .unwrap_or(if is_number { Align::Right } else { Align::Left });
+
+        let (before, after) = match align {
+            Align::Left => (0, padding),
+            Align::Center => (padding / 2, padding - padding / 2),
+            Align::Right => (padding, 0),
+        };
+
+        out.extend(std::iter::repeat(fill).take(before));
+        out.push_str(&rendered);
+        out.extend(std::iter::repeat(fill).take(after));
+    }
+}
+
+impl Align {
+    fn parse(ch: char) -> Option<Self> {
+        match ch {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(fmt: &str, args: &[Value]) -> String {
+        Format::parse(fmt).unwrap().render(args)
+    }
+
+    #[test]
+    fn test_render() {
+        let cases = vec![
+            ("Hello!", vec![], "Hello!"),
+            ("{{}}", vec![], "{}"),
+            ("{}", vec![Value::Int(123)], "123"),
+            ("{} x {}", vec![Value::Int(1), Value::Float(2.0)], "1 x 2.0"),
+            ("{:.3}", vec![Value::Float(1.23456)], "1.235"),
+            ("[{:5}]", vec![Value::Int(42)], "[   42]"),
+            ("[{:5}]", vec![Value::Str("ab".into())], "[ab   ]"),
+            ("[{:<5}]", vec![Value::Int(42)], "[42   ]"),
+            ("[{:^6}]", vec![Value::Char('x')], "[  x   ]"),
+            ("[{:*>4}]", vec![Value::Bool(true)], "[true]"),
+            ("[{:*>6}]", vec![Value::Bool(true)], "[**true]"),
+            ("[{:05}]", vec![Value::Int(-42)], "[-0042]"),
+            ("[{:08.2}]", vec![Value::Float(1.23456)], "[00001.23]"),
+            ("{:.2}", vec![Value::Str("Hello".into())], "Hello"),
+            (
+                "{:.1}",
+                vec![Value::Tuple(vec![Value::Float(1.25), Value::Int(2)])],
+                "(1.2, 2)",
+            ),
+            ("{}", vec![Value::Tuple(vec![Value::Unit])], "((),)"),
+        ];
+
+        for (fmt, args, expected) in cases {
+            assert_eq!(expected, render(fmt, &args), "fmt = {:?}", fmt);
+        }
+
+        assert_eq!(format!("{:65535}", 1), render("{:65535}", &[Value::Int(1)]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = vec![
+            ("{", "unterminated `{`"),
+            ("}", "unmatched `}` (use `}}` to print a brace)"),
+            ("{0}", "invalid argument `{0}`"),
+            ("{:.}", "missing precision in `{:.}`"),
+            ("{:5x}", "unexpected `x` in `{:5x}`"),
+            ("{:65536}", "number `65536` is too large"),
+            ("{:.65536}", "number `65536` is too large"),
+            (
+                "{:18446744073709551615}",
+                "number `18446744073709551615` is too large",
+            ),
+        ];
+
+        for (fmt, expected) in cases {
+            assert_eq!(Err(expected.to_string()), Format::parse(fmt));
+        }
+    }
+}
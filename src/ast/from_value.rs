@@ -1,4 +1,5 @@
 use crate::ast::*;
+use std::borrow::Cow;
 
 pub trait FromValue<'s> {
     fn from_value(value: Value<'s>) -> Self;
@@ -64,5 +65,34 @@ from_value!([
     Int(value) = i32 => value as _,
     Float(value) = f32 => value,
     Float(value) = f64 => value as _,
-    Str(value) = &'s str => value,
+    Str(value) = Cow<'s, str> => value,
+    Str(value) = String => value.into_owned(),
 ]);
+
+impl<'s> FromValue<'s> for &'s str {
+    /// Extracts string borrowed from the program.
+    ///
+    /// If the string has been created at runtime (e.g. through
+    /// [`Node::Format`]), panics - use `String` or `Cow<str>` instead.
+    fn from_value(value: Value<'s>) -> Self {
+        match value {
+            Value::Str(Cow::Borrowed(value)) => value,
+
+            Value::Str(Cow::Owned(value)) => panic!(
+                "Got runtime-created string `{:?}`, which cannot be borrowed as \
+                 `&str` - please use `String` instead",
+                value,
+            ),
+
+            value => panic!(
+                "Got `{:?}`, but expected value of type `{:?}`",
+                value,
+                Type::Str,
+            ),
+        }
+    }
+
+    fn ty() -> Option<Type> {
+        Some(Type::Str)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::ast::*;
+use std::borrow::Cow;
 
 pub trait IntoValue<'s> {
     fn into_value(self) -> Value<'s>;
@@ -55,6 +56,16 @@ impl<'s> IntoValue<'s> for i64 {
     }
 }
 
+impl<'s> IntoValue<'s> for i32 {
+    fn into_value(self) -> Value<'s> {
+        Value::Int(self as _)
+    }
+
+    fn ty() -> Option<Type> {
+        Some(Type::Int)
+    }
+}
+
 impl<'s> IntoValue<'s> for f32 {
     fn into_value(self) -> Value<'s> {
         Value::Float(self)
@@ -65,9 +76,29 @@ impl<'s> IntoValue<'s> for f32 {
     }
 }
 
+impl<'s> IntoValue<'s> for f64 {
+    fn into_value(self) -> Value<'s> {
+        Value::Float(self as _)
+    }
+
+    fn ty() -> Option<Type> {
+        Some(Type::Float)
+    }
+}
+
 impl<'s> IntoValue<'s> for &'s str {
     fn into_value(self) -> Value<'s> {
-        Value::Str(self)
+        Value::Str(Cow::Borrowed(self))
+    }
+
+    fn ty() -> Option<Type> {
+        Some(Type::Str)
+    }
+}
+
+impl<'s> IntoValue<'s> for String {
+    fn into_value(self) -> Value<'s> {
+        Value::Str(Cow::Owned(self))
     }
 
     fn ty() -> Option<Type> {
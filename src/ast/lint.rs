@@ -0,0 +1,485 @@
+use crate::ast::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+/// Looks for code that's valid, but most likely not what the author meant -
+/// e.g. variables that are never read or loops that never run.
+///
+/// The program doesn't have to be well-typed; lints are returned in the
+/// order of the nodes they point at, with the ones set to [`Level::Allow`]
+/// skipped. If the source code is at hand, [`render_lints()`] can print them
+/// along with the offending lines.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = parse("fn(input: int) -> int {\n  var x = 1;\n  input\n}").unwrap();
+/// let lints = lint(&prog, &LintLevels::default());
+///
+/// assert_eq!(LintKind::UnusedVariable, lints[0].kind);
+/// assert_eq!("2:3: Variable `x` is never read", lints[0].to_string());
+///
+/// let levels = LintLevels::default().set(LintKind::UnusedVariable, Level::Allow);
+///
+/// assert!(lint(&prog, &levels).is_empty());
+/// ```
+pub fn lint(prog: &Program, levels: &LintLevels) -> Vec<Lint> {
+    struct Nodes<'s> {
+        reads: BTreeSet<Ident<'s>>,
+        ids: HashMap<*const Node<'s>, usize>,
+    }
+
+    impl<'s> Visitor<'s> for Nodes<'s> {
+        fn visit_node(&mut self, node: &Node<'s>) {
+            if let Node::Var(name) = node {
+                self.reads.insert(name);
+            }
+
+            let id = self.ids.len();
+
+            self.ids.insert(node, id);
+            walk_node(self, node);
+        }
+    }
+
+    let mut nodes = Nodes {
+        reads: Default::default(),
+        ids: Default::default(),
+    };
+
+    nodes.visit_program(prog);
+
+    let mut linter = Linter {
+        levels,
+        reads: nodes.reads,
+        ids: nodes.ids,
+        lints: Vec::new(),
+        report: true,
+        catch_live: Default::default(),
+        span: None,
+    };
+
+    linter.node(&prog.body, Default::default());
+
+    let mut lints = linter.lints;
+
+    lints.sort_by_key(|(id, _)| *id);
+    lints.into_iter().map(|(_, lint)| lint).collect()
+}
+
+/// Lint reported by [`lint()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    pub kind: LintKind,
+
+    /// Either [`Level::Warn`] or [`Level::Deny`]
+    pub level: Level,
+
+    pub message: String,
+
+    /// Span of the offending node, if the program has been parsed from
+    /// source code
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = self.span {
+            write!(f, "{}: ", span)?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintKind {
+    /// `var x = ...`, where `x` is never read; names starting with `_` are
+    /// not reported
+    UnusedVariable,
+
+    /// `x = ...`, where the value gets overwritten (or the program finishes)
+    /// before it's read
+    UnusedAssignment,
+
+    /// `while true { ... }` or `while false { ... }`
+    ConstantCondition,
+
+    /// Code after a loop that never finishes
+    UnreachableCode,
+
+    /// `var input = ...` (or `catch input`, etc.)
+    ShadowedInput,
+}
+
+impl LintKind {
+    pub const ALL: [Self; 5] = [
+        LintKind::UnusedVariable,
+        LintKind::UnusedAssignment,
+        LintKind::ConstantCondition,
+        LintKind::UnreachableCode,
+        LintKind::ShadowedInput,
+    ];
+
+    /// Returns lint's name (e.g. `unused_variable`), printed by
+    /// [`render_lints()`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintKind::UnusedVariable => "unused_variable",
+            LintKind::UnusedAssignment => "unused_assignment",
+            LintKind::ConstantCondition => "constant_condition",
+            LintKind::UnreachableCode => "unreachable_code",
+            LintKind::ShadowedInput => "shadowed_input",
+        }
+    }
+}
+
+/// Severity of a lint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Lint is not reported
+    Allow,
+
+    /// Lint is reported as a warning
+    Warn,
+
+    /// Lint is reported as an error
+    Deny,
+}
+
+/// Severities of lints, for [`lint()`]; all of them are [`Level::Warn`] by
+/// default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintLevels {
+    levels: BTreeMap<LintKind, Level>,
+}
+
+impl LintLevels {
+    /// Sets severity of all lints.
+    pub fn all(level: Level) -> Self {
+        LintKind::ALL
+            .into_iter()
+            .fold(Self::default(), |this, kind| this.set(kind, level))
+    }
+
+    pub fn set(mut self, kind: LintKind, level: Level) -> Self {
+        self.levels.insert(kind, level);
+        self
+    }
+
+    pub fn get(&self, kind: LintKind) -> Level {
+        self.levels.get(&kind).copied().unwrap_or(Level::Warn)
+    }
+}
+
+/// Variables whose current values might be read later.
+type Live<'s> = BTreeSet<Ident<'s>>;
+
+struct Linter<'a, 's> {
+    levels: &'a LintLevels,
+
+    /// Names of all the variables that are read somewhere in the program
+    reads: BTreeSet<Ident<'s>>,
+
+    /// Maps nodes into their indices (in pre-order), used to sort lints
+    ids: HashMap<*const Node<'s>, usize>,
+
+    lints: Vec<(usize, Lint)>,
+
+    /// Whether lints should be recorded; loops are analyzed a couple of
+    /// times (until their liveness reaches a fixed point), but reported only
+    /// once
+    report: bool,
+
+    /// Variables live at the entry of enclosing `catch` handlers - since
+    /// `try` body can fail at any point, they stay live throughout it
+    catch_live: Live<'s>,
+
+    /// Span of the innermost node being analyzed (if known)
+    span: Option<Span>,
+}
+
+impl<'s> Linter<'_, 's> {
+    /// Analyzes the node backwards, given variables live right after it;
+    /// returns variables live right before it.
+    fn node(&mut self, node: &Node<'s>, mut live: Live<'s>) -> Live<'s> {
+        if let Some((_, lhs, rhs)) = BinOp::of(node) {
+            let live = self.node(rhs, live);
+
+            return self.node(lhs, live);
+        }
+
+        match node {
+            Node::Const(_) => live,
+
+            Node::Var(name) => {
+                live.insert(*name);
+                live
+            }
+
+            Node::ExtractTuple { expr, .. } | Node::Cast { expr, .. } => self.node(expr, live),
+
+            Node::ExtractArray { expr, idx } => {
+                let live = self.node(idx, live);
+
+                self.node(expr, live)
+            }
+
+            Node::Declare { name, value, .. } => {
+                self.bind(node, name);
+                live.remove(*name);
+
+                self.node(value, live)
+            }
+
+            Node::DeclareUninit { name, .. } => {
+                self.bind(node, name);
+                live.remove(*name);
+                live
+            }
+
+            Node::Destructure { pat, value } => {
+                for name in pattern_vars(pat) {
+                    self.bind(node, name);
+                    live.remove(name);
+                }
+
+                self.node(value, live)
+            }
+
+            Node::Assign { name, value } => {
+                let is_dead = self.reads.contains(name)
+                    && !live.contains(name)
+                    && !self.catch_live.contains(name);
+
+                if is_dead {
+                    self.lint(
+                        node,
+                        LintKind::UnusedAssignment,
+                        format!("Value assigned to `{}` is never read", name),
+                    );
+                }
+
+                live.remove(*name);
+
+                self.node(value, live)
+            }
+
+            Node::While { cond, body } => self.node_while(node, cond, body, live),
+
+            Node::Try {
+                body,
+                name,
+                handler,
+            } => {
+                let mut handler_live = self.node(handler, live.clone());
+
+                self.bind(node, name);
+                handler_live.remove(*name);
+
+                let catch_live = self.catch_live.clone();
+
+                self.catch_live.extend(handler_live.iter().copied());
+
+                let mut live = self.node(body, live);
+
+                self.catch_live = catch_live;
+
+                // `body` can fail before doing anything
+                live.extend(handler_live);
+                live
+            }
+
+            Node::Print(nodes) | Node::Println(nodes) | Node::Format { args: nodes, .. } => nodes
+                .iter()
+                .rev()
+                .fold(live, |live, node| self.node(node, live)),
+
+            Node::Block(nodes) => {
+                let reachable = match nodes.iter().position(diverges) {
+                    Some(idx) => {
+                        if let Some(unreachable) = nodes.get(idx + 1) {
+                            self.lint_at(
+                                unreachable,
+                                unreachable.span().or(self.span),
+                                LintKind::UnreachableCode,
+                                "Unreachable code: it follows a loop that never finishes".into(),
+                            );
+                        }
+
+                        // Nothing runs after the diverging node
+                        live.clear();
+
+                        &nodes[..=idx]
+                    }
+
+                    None => &nodes[..],
+                };
+
+                reachable
+                    .iter()
+                    .rev()
+                    .fold(live, |live, node| self.node(node, live))
+            }
+
+            Node::Spanned { span, node } => {
+                let parent_span = self.span.replace(*span);
+                let live = self.node(node, live);
+
+                self.span = parent_span;
+
+                live
+            }
+
+            _ => unreachable!(),
+        }
+    }
+
+    fn node_while(
+        &mut self,
+        node: &Node<'s>,
+        cond: &Node<'s>,
+        body: &Node<'s>,
+        live: Live<'s>,
+    ) -> Live<'s> {
+        let constant = const_bool(cond);
+
+        if let Some(value) = constant {
+            let message = if value {
+                "Loop's condition is always `true`, so it never finishes"
+            } else {
+                "Loop's condition is always `false`, so its body never runs"
+            };
+
+            self.lint(node, LintKind::ConstantCondition, message.into());
+        }
+
+        // Variables live after the loop finishes
+        let exit = if constant == Some(true) {
+            Live::new()
+        } else {
+            live
+        };
+
+        // Variables live before the condition are the ones live after the
+        // loop plus the ones live before the body, which in turn depend on
+        // the ones live before the condition - so let's iterate until that
+        // settles (it has to, since the sets only grow)
+        let report = std::mem::replace(&mut self.report, false);
+        let mut entry = self.node(cond, exit.clone());
+
+        loop {
+            let mut live = self.node(body, entry.clone());
+
+            live.extend(exit.iter().copied());
+
+            let next = self.node(cond, live);
+
+            if next == entry {
+                break;
+            }
+
+            entry = next;
+        }
+
+        self.report = report;
+
+        let mut live = self.node(body, entry);
+
+        live.extend(exit);
+
+        self.node(cond, live)
+    }
+
+    /// Checks variable declared by `node`.
+    fn bind(&mut self, node: &Node<'s>, name: Ident<'s>) {
+        if name == "input" {
+            self.lint(
+                node,
+                LintKind::ShadowedInput,
+                "Variable `input` shadows program's input".into(),
+            );
+        } else if !name.starts_with('_') && !self.reads.contains(name) {
+            self.lint(
+                node,
+                LintKind::UnusedVariable,
+                format!("Variable `{}` is never read", name),
+            );
+        }
+    }
+
+    /// Records a lint at the current node.
+    fn lint(&mut self, node: &Node<'s>, kind: LintKind, message: String) {
+        self.lint_at(node, self.span, kind, message);
+    }
+
+    fn lint_at(&mut self, node: &Node<'s>, span: Option<Span>, kind: LintKind, message: String) {
+        let level = self.levels.get(kind);
+
+        if !self.report || level == Level::Allow {
+            return;
+        }
+
+        let id = self.ids[&(node as *const _)];
+
+        self.lints.push((
+            id,
+            Lint {
+                kind,
+                level,
+                message,
+                span,
+            },
+        ));
+    }
+}
+
+/// Returns whether running given node never finishes (i.e. it always enters
+/// an infinite loop).
+fn diverges(node: &Node) -> bool {
+    if let Some((_, lhs, rhs)) = BinOp::of(node) {
+        return diverges(lhs) || diverges(rhs);
+    }
+
+    match node {
+        Node::Const(_) | Node::Var(_) | Node::DeclareUninit { .. } => false,
+
+        Node::ExtractTuple { expr, .. } | Node::Cast { expr, .. } => diverges(expr),
+        Node::ExtractArray { expr, idx } => diverges(expr) || diverges(idx),
+
+        Node::Declare { value, .. }
+        | Node::Destructure { value, .. }
+        | Node::Assign { value, .. } => diverges(value),
+
+        Node::While { cond, .. } => const_bool(cond) == Some(true) || diverges(cond),
+
+        // `body` can bail out through a runtime error
+        Node::Try { body, handler, .. } => diverges(body) && diverges(handler),
+
+        Node::Print(nodes)
+        | Node::Println(nodes)
+        | Node::Format { args: nodes, .. }
+        | Node::Block(nodes) => nodes.iter().any(diverges),
+
+        Node::Spanned { node, .. } => diverges(node),
+
+        _ => unreachable!(),
+    }
+}
+
+fn const_bool(node: &Node) -> Option<bool> {
+    match node.unspanned() {
+        Node::Const(Value::Bool(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+fn pattern_vars<'s>(pat: &Pattern<'s>) -> Vec<Ident<'s>> {
+    match pat {
+        Pattern::Var(name) => vec![*name],
+        Pattern::Ignore => Vec::new(),
+        Pattern::Tuple(pats) => pats.iter().flat_map(pattern_vars).collect(),
+    }
+}
@@ -0,0 +1,186 @@
+use crate::ast::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Simplifies the program without changing what it does:
+///
+/// - operations on constants get evaluated (`2 * 3` becomes `6`, `1 as float`
+///   becomes `1.0`, `(1, 2).0` becomes `1` etc.),
+/// - variables that are never assigned to after being declared with a
+///   constant value get replaced with that value.
+///
+/// Operations that would fail at runtime (`1 / 0`, `"abc"[5]`) or overflow
+/// are left as they are, so that the program reports them the same way as
+/// before - only when (and if) it reaches them.
+///
+/// Ill-typed programs are returned unchanged, so that type errors point at
+/// the original code. Spans are preserved, but node indices (e.g. in
+/// [`crate::vm::RuntimeError`]) refer to the optimized program.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = parse("fn(input: int) -> int { var x = 2 * 3; input * x }").unwrap();
+/// let prog = optimize(prog).without_spans();
+///
+/// assert_eq!(
+///     rast! { fn(input: int) -> int { var x = 6; input * 6 } },
+///     prog
+/// );
+/// ```
+pub fn optimize(prog: Program<'_>) -> Program<'_> {
+    #[derive(Default)]
+    struct Assigns<'s>(BTreeSet<Ident<'s>>);
+
+    impl<'s> Visitor<'s> for Assigns<'s> {
+        fn visit_node(&mut self, node: &Node<'s>) {
+            if let Node::Assign { name, .. } = node {
+                self.0.insert(name);
+            }
+
+            walk_node(self, node);
+        }
+    }
+
+    if typecheck(&prog).is_err() {
+        return prog;
+    }
+
+    let mut assigns = Assigns::default();
+
+    assigns.visit_program(&prog);
+
+    let mut optimizer = Optimizer {
+        assigns: assigns.0,
+        consts: Default::default(),
+    };
+
+    optimizer.fold_program(prog)
+}
+
+struct Optimizer<'s> {
+    /// Variables that are assigned to somewhere in the program
+    assigns: BTreeSet<Ident<'s>>,
+
+    /// Values of constant variables declared so far
+    consts: BTreeMap<Ident<'s>, Value<'s>>,
+}
+
+impl<'s> Fold<'s> for Optimizer<'s> {
+    fn fold_node(&mut self, node: Node<'s>) -> Node<'s> {
+        // Children come first, so that e.g. `1 + 2 + 3` gets folded bottom-up
+        let node = walk_fold_node(self, node);
+
+        match self.eval(&node) {
+            Some(value) => Node::Const(value),
+            None => node,
+        }
+    }
+}
+
+impl<'s> Optimizer<'s> {
+    /// Returns node's value, if it's known at compile time and computing it
+    /// cannot fail.
+    fn eval(&mut self, node: &Node<'s>) -> Option<Value<'s>> {
+        if let Some((op, lhs, rhs)) = BinOp::of(node) {
+            return binop(op, constant(lhs)?, constant(rhs)?);
+        }
+
+        match node {
+            // Since the program is well-typed, the variable must've been
+            // declared by the time it's read - and because it's never
+            // assigned to, it still has its initial value
+            Node::Var(name) => self.consts.get(name).cloned(),
+
+            Node::ExtractTuple { expr, idx } => match constant(expr)? {
+                Value::Tuple(values) => values.get(*idx).cloned(),
+                _ => None,
+            },
+
+            Node::ExtractArray { expr, idx } => match (constant(expr)?, constant(idx)?) {
+                (Value::Str(expr), Value::Int(idx)) => {
+                    let char = expr.chars().nth(usize::try_from(*idx).ok()?)?;
+
+                    Some(Value::Char(char))
+                }
+
+                _ => None,
+            },
+
+            Node::Cast { expr, ty } => match (constant(expr)?, ty) {
+                (Value::Int(value), Type::Float) => Some(Value::Float(*value as _)),
+                (Value::Float(value), Type::Int) => Some(Value::Int(*value as _)),
+                _ => None,
+            },
+
+            Node::Declare { name, value, .. } => {
+                if let Some(value) = constant(value) {
+                    if !self.assigns.contains(name) {
+                        self.consts.insert(name, value.clone());
+                    }
+                }
+
+                None
+            }
+
+            _ => None,
+        }
+    }
+}
+
+/// Returns node's value, if it's a constant.
+fn constant<'a, 's>(node: &'a Node<'s>) -> Option<&'a Value<'s>> {
+    match node.unspanned() {
+        Node::Const(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn binop<'s>(op: BinOp, lhs: &Value<'s>, rhs: &Value<'s>) -> Option<Value<'s>> {
+    let value = match (lhs, rhs) {
+        (Value::Bool(lhs), Value::Bool(rhs)) => Value::Bool(match op {
+            BinOp::Eq => lhs == rhs,
+            BinOp::Neq => lhs != rhs,
+            BinOp::And => *lhs && *rhs,
+            BinOp::Or => *lhs || *rhs,
+            _ => return None,
+        }),
+
+        // Checked, so that division by zero and overflows are left for the
+        // program to run into
+        (Value::Int(lhs), Value::Int(rhs)) => match op {
+            BinOp::Add => Value::Int(lhs.checked_add(*rhs)?),
+            BinOp::Sub => Value::Int(lhs.checked_sub(*rhs)?),
+            BinOp::Mul => Value::Int(lhs.checked_mul(*rhs)?),
+            BinOp::Div => Value::Int(lhs.checked_div(*rhs)?),
+            BinOp::Mod => Value::Int(lhs.checked_rem(*rhs)?),
+            BinOp::Eq => Value::Bool(lhs == rhs),
+            BinOp::Neq => Value::Bool(lhs != rhs),
+            BinOp::Gt => Value::Bool(lhs > rhs),
+            BinOp::GtEq => Value::Bool(lhs >= rhs),
+            BinOp::Lt => Value::Bool(lhs < rhs),
+            BinOp::LtEq => Value::Bool(lhs <= rhs),
+            _ => return None,
+        },
+
+        (Value::Float(lhs), Value::Float(rhs)) => match op {
+            BinOp::Add => Value::Float(lhs + rhs),
+            BinOp::Sub => Value::Float(lhs - rhs),
+            BinOp::Mul => Value::Float(lhs * rhs),
+            BinOp::Div => Value::Float(lhs / rhs),
+            BinOp::Mod => Value::Float(lhs % rhs),
+            BinOp::Eq => Value::Bool(lhs == rhs),
+            BinOp::Neq => Value::Bool(lhs != rhs),
+            BinOp::Gt => Value::Bool(lhs > rhs),
+            BinOp::GtEq => Value::Bool(lhs >= rhs),
+            BinOp::Lt => Value::Bool(lhs < rhs),
+            BinOp::LtEq => Value::Bool(lhs <= rhs),
+            _ => return None,
+        },
+
+        _ => return None,
+    };
+
+    Some(value)
+}
@@ -0,0 +1,866 @@
+mod lexer;
+
+pub(super) use self::lexer::lex_str;
+use self::lexer::*;
+use crate::ast::*;
+use std::fmt;
+use std::ops::Range;
+
+/// Parses program written in rast-jit-vm's textual syntax.
+///
+/// The syntax is Rust-like:
+///
+/// ```text
+/// program  = "fn" "(" "input" ":" type ")" [ "->" type ] expr
+///
+/// type     = "bool" | "char" | "int" | "float" | "str"
+///          | "()"                                  (* unit *)
+///          | "(" "," ")"                           (* empty tuple *)
+///          | "(" type "," [ type { "," type } [ "," ] ] ")"
+///
+/// expr     = "var" IDENT [ ":" type ] "=" expr     (* Declare *)
+///          | "var" IDENT [ ":" type ]              (* DeclareUninit *)
+///          | "var" pattern "=" expr                (* Destructure *)
+///          | IDENT "=" expr                        (* Assign *)
+///          | binary
+///
+/// pattern  = IDENT | "_" | "(" pattern ")"
+///          | "(" [ pattern "," [ pattern { "," pattern } [ "," ] ] ] ")"
+///
+/// binary   = cast { binop cast }                   (* see below *)
+/// cast     = postfix { "as" type }
+/// postfix  = primary { "." INT | "[" expr "]" }
+///
+/// primary  = literal | IDENT | "(" expr ")" | block
+///          | "while" expr block
+///          | "try" block "catch" IDENT block
+///          | "print" "(" [ args ] ")"
+///          | "println" "(" [ args ] ")"
+///          | "format" "(" STR { "," expr } [ "," ] ")"
+///
+/// block    = "{" { stmt ( ";" | <nothing, after while / try / block> ) } "}"
+/// stmt     = expr                                  (* see below *)
+/// args     = expr { "," expr } [ "," ]
+///
+/// literal  = [ "-" ] INT | [ "-" ] FLOAT | CHAR | STR | "true" | "false"
+///          | "()"                                  (* unit *)
+///          | "(" "," ")"                           (* empty tuple *)
+///          | "(" literal "," [ literal { "," literal } [ "," ] ] ")"
+/// ```
+///
+/// Binary operators, from the loosest to the tightest binding:
+///
+/// - `||`,
+/// - `&&`,
+/// - `==`, `!=`, `<`, `<=`, `>`, `>=` (non-associative, i.e. `a < b < c` is
+///   rejected),
+/// - `+`, `-`,
+/// - `*`, `/`, `%`.
+///
+/// All of them (except for comparisons) are left-associative.
+///
+/// Inside blocks, a statement that starts with `while`, `try` or `{` ends at
+/// its closing brace (that's the same rule Rust has), so it doesn't need a
+/// semicolon after it; `{ while cond {} - 1 }` is a block of two statements
+/// (`while cond {}` and `-1`). Semicolons after the last statement are
+/// optional and don't change the block's value.
+///
+/// Expressions, types and patterns can be nested at most 256 levels deep
+/// (e.g. `((((1))))` is nested 5 levels deep), so that malicious input
+/// cannot overflow the stack. Chains of operators, casts and indexing count
+/// towards that limit too, since they build nested nodes just the same -
+/// `1 + 2 + 3` is `(1 + 2) + 3`, and `x.0.1` is `(x.0).1`.
+///
+/// Identifiers and string literals (as long as they don't contain escape
+/// sequences) are borrowed from `src`; `//` and `/* */` comments are
+/// supported.
+///
+/// Every node gets wrapped in [`Node::Spanned`], pointing at the node's
+/// source code, so that compilation and runtime errors can report where they
+/// happened; use [`Program::without_spans()`] to get rid of them.
+///
+/// [`Program`]'s `Display` impl prints programs in this syntax, so that
+/// `parse(&prog.to_string())` gives back `prog` (modulo spans; see
+/// `ast/pretty.rs` for the few other exceptions).
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = parse("fn(input: int) -> int { input * 2 }").unwrap();
+///
+/// assert_eq!(Ok(30), vm::eval(&prog, 15));
+/// ```
+pub fn parse(src: &str) -> Result<Program<'_>, ParseError> {
+    Parser::run(src, Parser::program)
+}
+
+/// Parses a single expression (`expr` in the grammar above), e.g.
+/// `input * 2`.
+pub fn parse_node(src: &str) -> Result<Node<'_>, ParseError> {
+    Parser::run(src, Parser::expr)
+}
+
+/// Error returned by [`parse()`], [`parse_node()`] and [`parse_sexpr()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+
+    /// Byte offset into the source code
+    pub offset: usize,
+
+    /// 1-based line number
+    pub line: usize,
+
+    /// 1-based column number, in characters
+    pub column: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(src: &str, offset: usize, message: impl Into<String>) -> Self {
+        let before = &src[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let column = src[line_start..offset].chars().count() + 1;
+
+        Self {
+            message: message.into(),
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Maximum nesting of expressions, types and patterns the parser accepts;
+/// see [`parse()`].
+const MAX_DEPTH: usize = 256;
+
+pub(crate) const KEYWORDS: &[&str] = &["as", "catch", "false", "fn", "true", "try", "var", "while"];
+
+struct Parser<'s> {
+    src: &'s str,
+    tokens: Vec<(Token<'s>, Range<usize>)>,
+
+    /// Index of the current token
+    pos: usize,
+
+    /// Index of the most recently consumed token
+    prev: usize,
+
+    /// Byte offsets at which consecutive lines start
+    lines: Vec<usize>,
+
+    /// How deeply nested the currently parsed expression, type or pattern
+    /// is
+    depth: usize,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+impl<'s> Parser<'s> {
+    fn run<T>(src: &'s str, f: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        let mut this = Self {
+            src,
+            tokens: lex(src)?,
+            pos: 0,
+            prev: 0,
+            lines: std::iter::once(0)
+                .chain(src.match_indices('\n').map(|(idx, _)| idx + 1))
+                .collect(),
+            depth: 0,
+        };
+
+        let result = f(&mut this)?;
+
+        this.expect(Token::Eof)?;
+
+        Ok(result)
+    }
+
+    fn program(&mut self) -> ParseResult<Program<'s>> {
+        self.expect_keyword("fn")?;
+        self.expect(Token::LParen)?;
+        self.expect_keyword("input")?;
+        self.expect(Token::Colon)?;
+
+        let input = self.ty()?;
+
+        self.expect(Token::RParen)?;
+
+        let output = if self.eat(&Token::Arrow) {
+            self.ty()?
+        } else {
+            Type::Unit
+        };
+
+        let body = self.expr()?;
+
+        Ok(Program {
+            input,
+            output,
+            body,
+        })
+    }
+
+    fn ty(&mut self) -> ParseResult<Type> {
+        self.nested(Self::ty_unnested)
+    }
+
+    fn ty_unnested(&mut self) -> ParseResult<Type> {
+        match self.bump() {
+            Token::Ident("bool") => Ok(Type::Bool),
+            Token::Ident("char") => Ok(Type::Char),
+            Token::Ident("int") => Ok(Type::Int),
+            Token::Ident("float") => Ok(Type::Float),
+            Token::Ident("str") => Ok(Type::Str),
+
+            Token::LParen => {
+                if self.eat(&Token::RParen) {
+                    return Ok(Type::Unit);
+                }
+
+                if self.eat(&Token::Comma) {
+                    self.expect(Token::RParen)?;
+
+                    return Ok(Type::Tuple(Vec::new()));
+                }
+
+                let mut tys = vec![self.ty()?];
+
+                self.expect(Token::Comma)?;
+
+                while !self.eat(&Token::RParen) {
+                    tys.push(self.ty()?);
+
+                    if !self.eat(&Token::Comma) {
+                        self.expect(Token::RParen)?;
+                        break;
+                    }
+                }
+
+                Ok(Type::Tuple(tys))
+            }
+
+            token => Err(self.error_prev(format!("expected type, found {}", token.describe()))),
+        }
+    }
+
+    fn expr(&mut self) -> ParseResult<Node<'s>> {
+        self.nested(Self::expr_unnested)
+    }
+
+    fn expr_unnested(&mut self) -> ParseResult<Node<'s>> {
+        let start = self.offset();
+
+        let node = match self.peek() {
+            Token::Ident("var") => {
+                self.bump();
+
+                // `var name ...` is a regular declaration, everything else
+                // (including `var (name) = ...`) is a pattern
+                let is_declare = matches!(self.peek(), Token::Ident(name) if *name != "_");
+
+                if is_declare {
+                    let name = self.ident()?;
+
+                    let ty = if self.eat(&Token::Colon) {
+                        Some(self.ty()?)
+                    } else {
+                        None
+                    };
+
+                    if self.eat(&Token::Assign) {
+                        Node::Declare {
+                            name,
+                            ty,
+                            value: Box::new(self.expr()?),
+                        }
+                    } else {
+                        Node::DeclareUninit { name, ty }
+                    }
+                } else {
+                    let pat = self.pattern()?;
+
+                    self.expect(Token::Assign)?;
+
+                    Node::Destructure {
+                        pat,
+                        value: Box::new(self.expr()?),
+                    }
+                }
+            }
+
+            Token::Ident(name)
+                if !KEYWORDS.contains(name) && self.peek_nth(1) == &Token::Assign =>
+            {
+                let name = self.ident()?;
+
+                self.expect(Token::Assign)?;
+
+                Node::Assign {
+                    name,
+                    value: Box::new(self.expr()?),
+                }
+            }
+
+            _ => return self.binary(0),
+        };
+
+        Ok(self.spanned(start, node))
+    }
+
+    fn pattern(&mut self) -> ParseResult<Pattern<'s>> {
+        self.nested(Self::pattern_unnested)
+    }
+
+    fn pattern_unnested(&mut self) -> ParseResult<Pattern<'s>> {
+        match self.peek() {
+            Token::Ident("_") => {
+                self.bump();
+
+                Ok(Pattern::Ignore)
+            }
+
+            Token::LParen => {
+                self.bump();
+
+                if self.eat(&Token::RParen) {
+                    return Ok(Pattern::Tuple(Vec::new()));
+                }
+
+                let pat = self.pattern()?;
+
+                if self.eat(&Token::RParen) {
+                    return Ok(pat);
+                }
+
+                self.expect(Token::Comma)?;
+
+                let mut pats = vec![pat];
+
+                while !self.eat(&Token::RParen) {
+                    pats.push(self.pattern()?);
+
+                    if !self.eat(&Token::Comma) {
+                        self.expect(Token::RParen)?;
+                        break;
+                    }
+                }
+
+                Ok(Pattern::Tuple(pats))
+            }
+
+            _ => Ok(Pattern::Var(self.ident()?)),
+        }
+    }
+
+    fn binary(&mut self, min_prec: u8) -> ParseResult<Node<'s>> {
+        self.folding(|this| this.binary_unfolded(min_prec))
+    }
+
+    fn binary_unfolded(&mut self, min_prec: u8) -> ParseResult<Node<'s>> {
+        let start = self.offset();
+        let mut lhs = self.cast()?;
+        let mut prev_prec = None;
+
+        while let Some(op) = binop(self.peek()) {
+            let prec = op.prec();
+
+            if prec < min_prec {
+                break;
+            }
+
+            if prec == BinOp::CMP_PREC && prev_prec == Some(BinOp::CMP_PREC) {
+                return Err(self.error_here("comparison operators cannot be chained"));
+            }
+
+            self.fold()?;
+            self.bump();
+
+            let rhs = self.binary(prec + 1)?;
+
+            lhs = self.spanned(start, op.build(lhs, rhs));
+            prev_prec = Some(prec);
+        }
+
+        Ok(lhs)
+    }
+
+    fn cast(&mut self) -> ParseResult<Node<'s>> {
+        self.folding(Self::cast_unfolded)
+    }
+
+    fn cast_unfolded(&mut self) -> ParseResult<Node<'s>> {
+        let start = self.offset();
+        let mut expr = self.postfix()?;
+
+        while *self.peek() == Token::Ident("as") {
+            self.fold()?;
+            self.bump();
+
+            let node = Node::Cast {
+                expr: Box::new(expr),
+                ty: self.ty()?,
+            };
+
+            expr = self.spanned(start, node);
+        }
+
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> ParseResult<Node<'s>> {
+        self.folding(Self::postfix_unfolded)
+    }
+
+    fn postfix_unfolded(&mut self) -> ParseResult<Node<'s>> {
+        let start = self.offset();
+        let mut expr = self.primary()?;
+
+        loop {
+            if matches!(self.peek(), Token::Dot | Token::LBracket) {
+                self.fold()?;
+            }
+
+            if self.eat(&Token::Dot) {
+                let idx = match self.bump() {
+                    Token::Int(idx) => idx
+                        .parse()
+                        .map_err(|_| self.error_prev("tuple index is too large"))?,
+
+                    token => {
+                        return Err(self.error_prev(format!(
+                            "expected tuple index, found {}",
+                            token.describe()
+                        )));
+                    }
+                };
+
+                let node = Node::ExtractTuple {
+                    expr: Box::new(expr),
+                    idx,
+                };
+
+                expr = self.spanned(start, node);
+            } else if self.eat(&Token::LBracket) {
+                let idx = self.expr()?;
+
+                self.expect(Token::RBracket)?;
+
+                let node = Node::ExtractArray {
+                    expr: Box::new(expr),
+                    idx: Box::new(idx),
+                };
+
+                expr = self.spanned(start, node);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> ParseResult<Node<'s>> {
+        let start = self.offset();
+        let node = self.primary_unspanned()?;
+
+        Ok(self.spanned(start, node))
+    }
+
+    fn primary_unspanned(&mut self) -> ParseResult<Node<'s>> {
+        match self.peek().clone() {
+            Token::Int(_) | Token::Float(_) | Token::Minus | Token::Char(_) | Token::Str(_) => {
+                Ok(Node::Const(self.literal()?))
+            }
+
+            Token::Ident("true" | "false") => Ok(Node::Const(self.literal()?)),
+
+            Token::Ident("while") => {
+                self.bump();
+
+                let cond = self.expr()?;
+                let body = self.block()?;
+
+                Ok(Node::While {
+                    cond: Box::new(cond),
+                    body: Box::new(body),
+                })
+            }
+
+            Token::Ident("try") => {
+                self.bump();
+
+                let body = self.block()?;
+
+                self.expect_keyword("catch")?;
+
+                let name = self.ident()?;
+                let handler = self.block()?;
+
+                Ok(Node::Try {
+                    body: Box::new(body),
+                    name,
+                    handler: Box::new(handler),
+                })
+            }
+
+            Token::Ident(name @ ("print" | "println" | "format"))
+                if self.peek_nth(1) == &Token::LParen =>
+            {
+                self.bump();
+                self.bump();
+
+                let fmt = if name == "format" {
+                    match self.bump() {
+                        Token::Str(fmt) => {
+                            if !self.eat(&Token::Comma) && self.peek() != &Token::RParen {
+                                return Err(self.unexpected("`,` or `)`"));
+                            }
+
+                            Some(fmt)
+                        }
+
+                        token => {
+                            return Err(self.error_prev(format!(
+                                "expected format string, found {}",
+                                token.describe()
+                            )));
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                let args = self.args()?;
+
+                Ok(match fmt {
+                    Some(fmt) => Node::Format { fmt, args },
+                    None if name == "print" => Node::Print(args),
+                    None => Node::Println(args),
+                })
+            }
+
+            Token::Ident(_) => Ok(Node::Var(self.ident()?)),
+            Token::LBrace => self.block(),
+
+            Token::LParen => {
+                self.bump();
+
+                if self.eat(&Token::RParen) {
+                    return Ok(Node::Const(Value::Unit));
+                }
+
+                if self.eat(&Token::Comma) {
+                    self.expect(Token::RParen)?;
+
+                    return Ok(Node::Const(Value::Tuple(Vec::new())));
+                }
+
+                let offset = self.offset();
+                let expr = self.expr()?;
+
+                if self.eat(&Token::RParen) {
+                    return Ok(expr);
+                }
+
+                self.expect(Token::Comma)?;
+
+                let mut values = vec![(offset, expr)];
+
+                while !self.eat(&Token::RParen) {
+                    values.push((self.offset(), self.expr()?));
+
+                    if !self.eat(&Token::Comma) {
+                        self.expect(Token::RParen)?;
+                        break;
+                    }
+                }
+
+                let values = values
+                    .into_iter()
+                    .map(|(offset, value)| match value.without_spans() {
+                        Node::Const(value) => Ok(value),
+
+                        _ => Err(ParseError::new(
+                            self.src,
+                            offset,
+                            "tuple literals can contain only constants",
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Ok(Node::Const(Value::Tuple(values)))
+            }
+
+            _ => Err(self.unexpected("expression")),
+        }
+    }
+
+    fn literal(&mut self) -> ParseResult<Value<'s>> {
+        let negative = self.eat(&Token::Minus);
+
+        let value = match self.bump() {
+            Token::Int(value) => {
+                let value = if negative {
+                    format!("-{}", value).parse()
+                } else {
+                    value.parse()
+                };
+
+                Value::Int(value.map_err(|_| self.error_prev("integer literal is too large"))?)
+            }
+
+            Token::Float(value) => {
+                let value: f32 = value
+                    .parse()
+                    .map_err(|_| self.error_prev("invalid float literal"))?;
+
+                Value::Float(if negative { -value } else { value })
+            }
+
+            _ if negative => {
+                return Err(self.error_prev(
+                    "expected number after `-` (there's no unary minus operator; use `0 - expr`)",
+                ));
+            }
+
+            Token::Char(value) => Value::Char(value),
+            Token::Str(value) => Value::Str(value),
+            Token::Ident("true") => Value::Bool(true),
+            Token::Ident("false") => Value::Bool(false),
+
+            token => {
+                return Err(
+                    self.error_prev(format!("expected literal, found {}", token.describe()))
+                );
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn block(&mut self) -> ParseResult<Node<'s>> {
+        self.nested(Self::block_unnested)
+    }
+
+    fn block_unnested(&mut self) -> ParseResult<Node<'s>> {
+        let start = self.offset();
+
+        self.expect(Token::LBrace)?;
+
+        let mut nodes = Vec::new();
+
+        while !self.eat(&Token::RBrace) {
+            let is_block_like = matches!(
+                self.peek(),
+                Token::LBrace | Token::Ident("while") | Token::Ident("try")
+            );
+
+            let node = if is_block_like {
+                self.primary()?
+            } else {
+                self.expr()?
+            };
+
+            nodes.push(node);
+
+            if !self.eat(&Token::Semi) && !is_block_like && self.peek() != &Token::RBrace {
+                return Err(self.unexpected("`;` or `}`"));
+            }
+        }
+
+        Ok(self.spanned(start, Node::Block(nodes)))
+    }
+
+    /// Parses comma-separated expressions up to (and including) the closing
+    /// parenthesis.
+    fn args(&mut self) -> ParseResult<Vec<Node<'s>>> {
+        let mut args = Vec::new();
+
+        while !self.eat(&Token::RParen) {
+            args.push(self.expr()?);
+
+            if !self.eat(&Token::Comma) {
+                self.expect(Token::RParen)?;
+                break;
+            }
+        }
+
+        Ok(args)
+    }
+
+    fn ident(&mut self) -> ParseResult<Ident<'s>> {
+        match self.bump() {
+            Token::Ident(name) if !KEYWORDS.contains(&name) && name != "_" => Ok(name),
+            token => {
+                Err(self.error_prev(format!("expected identifier, found {}", token.describe())))
+            }
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ParseResult<()> {
+        match self.bump() {
+            Token::Ident(name) if name == keyword => Ok(()),
+
+            token => Err(self.error_prev(format!(
+                "expected `{}`, found {}",
+                keyword,
+                token.describe()
+            ))),
+        }
+    }
+
+    fn expect(&mut self, token: Token<'s>) -> ParseResult<()> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&token.describe()))
+        }
+    }
+
+    fn eat(&mut self, token: &Token<'s>) -> bool {
+        if self.peek() == token {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> &Token<'s> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token<'s> {
+        self.tokens
+            .get(self.pos + n)
+            .map_or(&Token::Eof, |(token, _)| token)
+    }
+
+    fn bump(&mut self) -> Token<'s> {
+        let token = self.peek().clone();
+
+        self.prev = self.pos;
+
+        // The last token is always `Eof`, which we never move past
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+
+        token
+    }
+
+    /// Wraps `node` into [`Node::Spanned`], spanning from `start` up to the
+    /// end of the most recently consumed token.
+    ///
+    /// Nodes that already have a span (e.g. parenthesized expressions) are
+    /// left as they are.
+    fn spanned(&self, start: usize, node: Node<'s>) -> Node<'s> {
+        if let Node::Spanned { .. } = node {
+            return node;
+        }
+
+        let end = self.tokens[self.prev].1.end;
+        let line = self
+            .lines
+            .partition_point(|&line_start| line_start <= start);
+        let line_start = self.lines[line - 1];
+        let column = self.src[line_start..start].chars().count() + 1;
+
+        Node::Spanned {
+            span: Span {
+                start,
+                end,
+                line,
+                column,
+            },
+            node: Box::new(node),
+        }
+    }
+
+    /// Calls `f`, failing if that'd nest too deep; see [`MAX_DEPTH`].
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        self.folding(|this| {
+            this.fold()?;
+            f(this)
+        })
+    }
+
+    /// Calls `f`, which can [`Self::fold()`] nodes built so far into new
+    /// ones (e.g. `a + b` into `(a + b) + c`); each such fold nests the
+    /// tree one level deeper, so it counts towards [`MAX_DEPTH`] until `f`
+    /// returns.
+    fn folding<T>(&mut self, f: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        let depth = self.depth;
+        let result = f(self);
+
+        self.depth = depth;
+
+        result
+    }
+
+    /// Accounts for one more level of nesting within [`Self::folding()`],
+    /// failing if that'd nest too deep.
+    fn fold(&mut self) -> ParseResult<()> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error_here("nesting is too deep"));
+        }
+
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    /// Returns byte offset of the current token.
+    fn offset(&self) -> usize {
+        self.tokens[self.pos].1.start
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        self.error_here(format!(
+            "expected {}, found {}",
+            expected,
+            self.peek().describe()
+        ))
+    }
+
+    /// Returns error pointing at the current token.
+    fn error_here(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.src, self.offset(), message)
+    }
+
+    /// Returns error pointing at the most recently consumed token.
+    fn error_prev(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.src, self.tokens[self.prev].1.start, message)
+    }
+}
+
+fn binop(token: &Token) -> Option<BinOp> {
+    let op = match token {
+        Token::OrOr => BinOp::Or,
+        Token::AndAnd => BinOp::And,
+        Token::EqEq => BinOp::Eq,
+        Token::Neq => BinOp::Neq,
+        Token::Gt => BinOp::Gt,
+        Token::GtEq => BinOp::GtEq,
+        Token::Lt => BinOp::Lt,
+        Token::LtEq => BinOp::LtEq,
+        Token::Plus => BinOp::Add,
+        Token::Minus => BinOp::Sub,
+        Token::Star => BinOp::Mul,
+        Token::Slash => BinOp::Div,
+        Token::Percent => BinOp::Mod,
+        _ => return None,
+    };
+
+    Some(op)
+}
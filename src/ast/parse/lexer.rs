@@ -0,0 +1,441 @@
+use super::ParseError;
+use std::borrow::Cow;
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token<'s> {
+    Ident(&'s str),
+    Int(&'s str),
+    Float(&'s str),
+    Char(char),
+    Str(Cow<'s, str>),
+
+    /// `(`
+    LParen,
+    /// `)`
+    RParen,
+    /// `{`
+    LBrace,
+    /// `}`
+    RBrace,
+    /// `[`
+    LBracket,
+    /// `]`
+    RBracket,
+    /// `,`
+    Comma,
+    /// `;`
+    Semi,
+    /// `:`
+    Colon,
+    /// `.`
+    Dot,
+    /// `->`
+    Arrow,
+
+    /// `=`
+    Assign,
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Star,
+    /// `/`
+    Slash,
+    /// `%`
+    Percent,
+    /// `==`
+    EqEq,
+    /// `!=`
+    Neq,
+    /// `>`
+    Gt,
+    /// `>=`
+    GtEq,
+    /// `<`
+    Lt,
+    /// `<=`
+    LtEq,
+    /// `&&`
+    AndAnd,
+    /// `||`
+    OrOr,
+
+    Eof,
+}
+
+impl Token<'_> {
+    pub fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("`{}`", name),
+            Token::Int(value) | Token::Float(value) => format!("`{}`", value),
+            Token::Char(value) => format!("{:?}", value),
+            Token::Str(value) => format!("{:?}", value),
+            Token::LParen => "`(`".into(),
+            Token::RParen => "`)`".into(),
+            Token::LBrace => "`{`".into(),
+            Token::RBrace => "`}`".into(),
+            Token::LBracket => "`[`".into(),
+            Token::RBracket => "`]`".into(),
+            Token::Comma => "`,`".into(),
+            Token::Semi => "`;`".into(),
+            Token::Colon => "`:`".into(),
+            Token::Dot => "`.`".into(),
+            Token::Arrow => "`->`".into(),
+            Token::Assign => "`=`".into(),
+            Token::Plus => "`+`".into(),
+            Token::Minus => "`-`".into(),
+            Token::Star => "`*`".into(),
+            Token::Slash => "`/`".into(),
+            Token::Percent => "`%`".into(),
+            Token::EqEq => "`==`".into(),
+            Token::Neq => "`!=`".into(),
+            Token::Gt => "`>`".into(),
+            Token::GtEq => "`>=`".into(),
+            Token::Lt => "`<`".into(),
+            Token::LtEq => "`<=`".into(),
+            Token::AndAnd => "`&&`".into(),
+            Token::OrOr => "`||`".into(),
+            Token::Eof => "end of input".into(),
+        }
+    }
+}
+
+/// Splits source code into tokens (each accompanied by its byte range).
+pub fn lex(src: &str) -> Result<Vec<(Token<'_>, Range<usize>)>, ParseError> {
+    let mut lexer = Lexer { src, pos: 0 };
+    let mut tokens = Vec::new();
+
+    loop {
+        lexer.skip_whitespace_and_comments()?;
+
+        let start = lexer.pos;
+
+        // After `.`, numbers are always tuple indices - that's so that
+        // `input.0.1` gets lexed as `input` `.` `0` `.` `1` instead of
+        // `input` `.` `0.1`
+        let after_dot = matches!(tokens.last(), Some((Token::Dot, _)));
+        let token = lexer.next(after_dot)?;
+        let is_eof = token == Token::Eof;
+
+        tokens.push((token, start..lexer.pos));
+
+        if is_eof {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// Lexes a string literal starting at `offset` (which must point at the
+/// opening quote); returns its contents and the offset right past the closing
+/// quote.
+pub fn lex_str(src: &str, offset: usize) -> Result<(Cow<'_, str>, usize), ParseError> {
+    let mut lexer = Lexer { src, pos: offset };
+
+    match lexer.string()? {
+        Token::Str(value) => Ok((value, lexer.pos)),
+        _ => unreachable!(),
+    }
+}
+
+struct Lexer<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> Lexer<'s> {
+    fn next(&mut self, after_dot: bool) -> Result<Token<'s>, ParseError> {
+        let start = self.pos;
+
+        let ch = match self.peek() {
+            Some(ch) => ch,
+            None => return Ok(Token::Eof),
+        };
+
+        if ch.is_ascii_alphabetic() || ch == '_' {
+            self.eat_while(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+            return Ok(Token::Ident(&self.src[start..self.pos]));
+        }
+
+        if ch.is_ascii_digit() {
+            return Ok(self.number(after_dot));
+        }
+
+        if ch == '"' {
+            return self.string();
+        }
+
+        if ch == '\'' {
+            return self.char();
+        }
+
+        self.bump();
+
+        let token = match ch {
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            ';' => Token::Semi,
+            ':' => Token::Colon,
+            '.' => Token::Dot,
+            '+' => Token::Plus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '-' if self.eat('>') => Token::Arrow,
+            '-' => Token::Minus,
+            '=' if self.eat('=') => Token::EqEq,
+            '=' => Token::Assign,
+            '!' if self.eat('=') => Token::Neq,
+            '>' if self.eat('=') => Token::GtEq,
+            '>' => Token::Gt,
+            '<' if self.eat('=') => Token::LtEq,
+            '<' => Token::Lt,
+            '&' if self.eat('&') => Token::AndAnd,
+            '|' if self.eat('|') => Token::OrOr,
+
+            ch => {
+                return Err(ParseError::new(
+                    self.src,
+                    start,
+                    format!("unexpected character {:?}", ch),
+                ));
+            }
+        };
+
+        Ok(token)
+    }
+
+    fn number(&mut self, after_dot: bool) -> Token<'s> {
+        let start = self.pos;
+
+        self.eat_while(|ch| ch.is_ascii_digit());
+
+        if after_dot {
+            return Token::Int(&self.src[start..self.pos]);
+        }
+
+        let mut is_float = false;
+
+        // Fractional part; requires a digit after the dot, so that `1.foo`
+        // doesn't get treated as a float
+        if self.peek() == Some('.') && self.peek_nth(1).map_or(false, |ch| ch.is_ascii_digit()) {
+            is_float = true;
+
+            self.bump();
+            self.eat_while(|ch| ch.is_ascii_digit());
+        }
+
+        // Exponent
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let digits_at = match self.peek_nth(1) {
+                Some('+' | '-') => 2,
+                _ => 1,
+            };
+
+            if self
+                .peek_nth(digits_at)
+                .map_or(false, |ch| ch.is_ascii_digit())
+            {
+                is_float = true;
+
+                for _ in 0..digits_at {
+                    self.bump();
+                }
+
+                self.eat_while(|ch| ch.is_ascii_digit());
+            }
+        }
+
+        let number = &self.src[start..self.pos];
+
+        if is_float {
+            Token::Float(number)
+        } else {
+            Token::Int(number)
+        }
+    }
+
+    fn string(&mut self) -> Result<Token<'s>, ParseError> {
+        let start = self.pos;
+
+        self.bump();
+
+        let content_start = self.pos;
+        let mut owned: Option<String> = None;
+
+        loop {
+            let ch_pos = self.pos;
+
+            match self.bump() {
+                Some('"') => break,
+
+                Some('\\') => {
+                    let ch = self.escape(ch_pos)?;
+
+                    owned
+                        .get_or_insert_with(|| self.src[content_start..ch_pos].to_owned())
+                        .push(ch);
+                }
+
+                Some(ch) => {
+                    if let Some(owned) = &mut owned {
+                        owned.push(ch);
+                    }
+                }
+
+                None => {
+                    return Err(ParseError::new(
+                        self.src,
+                        start,
+                        "unterminated string literal",
+                    ));
+                }
+            }
+        }
+
+        Ok(Token::Str(match owned {
+            Some(owned) => Cow::Owned(owned),
+            None => Cow::Borrowed(&self.src[content_start..self.pos - 1]),
+        }))
+    }
+
+    fn char(&mut self) -> Result<Token<'s>, ParseError> {
+        let start = self.pos;
+
+        self.bump();
+
+        let ch_pos = self.pos;
+
+        let ch = match self.bump() {
+            Some('\\') => self.escape(ch_pos)?,
+            Some('\'') => return Err(ParseError::new(self.src, start, "empty char literal")),
+            Some(ch) => ch,
+            None => {
+                return Err(ParseError::new(
+                    self.src,
+                    start,
+                    "unterminated char literal",
+                ))
+            }
+        };
+
+        if !self.eat('\'') {
+            return Err(ParseError::new(
+                self.src,
+                start,
+                "unterminated char literal",
+            ));
+        }
+
+        Ok(Token::Char(ch))
+    }
+
+    /// Parses an escape sequence; assumes the backslash has been already
+    /// consumed.
+    fn escape(&mut self, start: usize) -> Result<char, ParseError> {
+        let ch = match self.bump() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+
+            Some('u') => {
+                if !self.eat('{') {
+                    return Err(ParseError::new(self.src, start, "expected `{` after `\\u`"));
+                }
+
+                let digits_start = self.pos;
+
+                self.eat_while(|ch| ch.is_ascii_hexdigit());
+
+                let digits = &self.src[digits_start..self.pos];
+
+                if !self.eat('}') {
+                    return Err(ParseError::new(
+                        self.src,
+                        start,
+                        "expected `}` after unicode escape",
+                    ));
+                }
+
+                u32::from_str_radix(digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| ParseError::new(self.src, start, "invalid unicode escape"))?
+            }
+
+            _ => {
+                return Err(ParseError::new(self.src, start, "unknown escape sequence"));
+            }
+        };
+
+        Ok(ch)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.eat_while(char::is_whitespace);
+
+            if self.src[self.pos..].starts_with("//") {
+                self.eat_while(|ch| ch != '\n');
+            } else if self.src[self.pos..].starts_with("/*") {
+                let start = self.pos;
+
+                match self.src[self.pos + 2..].find("*/") {
+                    Some(len) => self.pos += len + 4,
+
+                    None => {
+                        return Err(ParseError::new(
+                            self.src,
+                            start,
+                            "unterminated block comment",
+                        ));
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+
+        self.pos += ch.len_utf8();
+
+        Some(ch)
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().map_or(false, &f) {
+            self.bump();
+        }
+    }
+}
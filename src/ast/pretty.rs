@@ -0,0 +1,422 @@
+//! Pretty-printer that renders the AST in the syntax understood by
+//! [`parse()`].
+//!
+//! Printing is precise enough for the output to be parsed back into the same
+//! AST, with three exceptions:
+//!
+//! - bodies of `while` and `try` are always printed as blocks, so a body that
+//!   isn't a [`Node::Block`] gets parsed back wrapped in a single-element
+//!   block (which doesn't change what the program does),
+//! - non-finite float constants (`inf`, `NaN`) don't have a literal syntax,
+//! - variables named after keywords (e.g. `while`) can't be referred to.
+//!
+//! Spans ([`Node::Spanned`]) are not printed.
+
+use crate::ast::*;
+use std::fmt;
+
+const ASSIGN_PREC: u8 = 0;
+const CAST_PREC: u8 = BinOp::MAX_PREC + 1;
+const POSTFIX_PREC: u8 = CAST_PREC + 1;
+const PRIMARY_PREC: u8 = POSTFIX_PREC + 1;
+
+impl fmt::Display for Program<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fn(input: {})", self.input)?;
+
+        if self.output != Type::Unit {
+            write!(f, " -> {}", self.output)?;
+        }
+
+        write!(f, " ")?;
+
+        Printer { f, indent: 0 }.expr(&self.body, ASSIGN_PREC)
+    }
+}
+
+impl fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Printer { f, indent: 0 }.expr(self, ASSIGN_PREC)
+    }
+}
+
+impl fmt::Display for Pattern<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Var(name) => write!(f, "{}", name),
+            Pattern::Ignore => write!(f, "_"),
+
+            Pattern::Tuple(pats) => {
+                write!(f, "(")?;
+
+                for (idx, pat) in pats.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", pat)?;
+                }
+
+                if pats.len() == 1 {
+                    write!(f, ",")?;
+                }
+
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Unit => write!(f, "()"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Str => write!(f, "str"),
+            Type::Tuple(tys) if tys.is_empty() => write!(f, "(,)"),
+
+            Type::Tuple(tys) => {
+                write!(f, "(")?;
+
+                for (idx, ty) in tys.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", ty)?;
+                }
+
+                if tys.len() == 1 {
+                    write!(f, ",")?;
+                }
+
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+struct Printer<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    indent: usize,
+}
+
+impl Printer<'_, '_> {
+    /// Prints `node`, wrapping it in parentheses if it binds looser than
+    /// `min_prec`.
+    fn expr(&mut self, node: &Node, min_prec: u8) -> fmt::Result {
+        let node = node.unspanned();
+
+        if prec(node) < min_prec {
+            write!(self.f, "(")?;
+            self.expr_inner(node)?;
+            write!(self.f, ")")
+        } else {
+            self.expr_inner(node)
+        }
+    }
+
+    fn expr_inner(&mut self, node: &Node) -> fmt::Result {
+        if let Some((op, lhs, rhs)) = BinOp::of(node) {
+            let prec = op.prec();
+
+            // Operators are left-associative (except for comparisons, which
+            // are non-associative), so the lhs needs parentheses only when
+            // it binds looser
+            let lhs_prec = if prec == BinOp::CMP_PREC {
+                prec + 1
+            } else {
+                prec
+            };
+
+            self.expr(lhs, lhs_prec)?;
+            write!(self.f, " {} ", op.symbol())?;
+
+            return self.expr(rhs, prec + 1);
+        }
+
+        match node {
+            Node::Const(value) => self.literal(value),
+            Node::Var(name) => write!(self.f, "{}", name),
+
+            Node::ExtractTuple { expr, idx } => {
+                self.postfix_operand(expr)?;
+                write!(self.f, ".{}", idx)
+            }
+
+            Node::ExtractArray { expr, idx } => {
+                self.postfix_operand(expr)?;
+                write!(self.f, "[")?;
+                self.expr(idx, ASSIGN_PREC)?;
+                write!(self.f, "]")
+            }
+
+            Node::Cast { expr, ty } => {
+                self.expr(expr, CAST_PREC)?;
+                write!(self.f, " as {}", ty)
+            }
+
+            Node::Declare { name, ty, value } => {
+                write!(self.f, "var {}", name)?;
+
+                if let Some(ty) = ty {
+                    write!(self.f, ": {}", ty)?;
+                }
+
+                write!(self.f, " = ")?;
+                self.expr(value, ASSIGN_PREC)
+            }
+
+            Node::DeclareUninit { name, ty } => {
+                write!(self.f, "var {}", name)?;
+
+                if let Some(ty) = ty {
+                    write!(self.f, ": {}", ty)?;
+                }
+
+                Ok(())
+            }
+
+            Node::Destructure { pat, value } => {
+                // `var name = ...` would get parsed as `Node::Declare`
+                if let Pattern::Var(name) = pat {
+                    write!(self.f, "var ({}) = ", name)?;
+                } else {
+                    write!(self.f, "var {} = ", pat)?;
+                }
+
+                self.expr(value, ASSIGN_PREC)
+            }
+
+            Node::Assign { name, value } => {
+                write!(self.f, "{} = ", name)?;
+                self.expr(value, ASSIGN_PREC)
+            }
+
+            Node::While { cond, body } => {
+                write!(self.f, "while ")?;
+                self.expr(cond, ASSIGN_PREC)?;
+                write!(self.f, " ")?;
+                self.block_of(body)
+            }
+
+            Node::Try {
+                body,
+                name,
+                handler,
+            } => {
+                write!(self.f, "try ")?;
+                self.block_of(body)?;
+                write!(self.f, " catch {} ", name)?;
+                self.block_of(handler)
+            }
+
+            Node::Print(args) => {
+                write!(self.f, "print(")?;
+                self.args(args)?;
+                write!(self.f, ")")
+            }
+
+            Node::Println(args) => {
+                write!(self.f, "println(")?;
+                self.args(args)?;
+                write!(self.f, ")")
+            }
+
+            Node::Format { fmt, args } => {
+                write!(self.f, "format(")?;
+                self.literal(&Value::Str(fmt.clone()))?;
+
+                for arg in args {
+                    write!(self.f, ", ")?;
+                    self.expr(arg, ASSIGN_PREC)?;
+                }
+
+                write!(self.f, ")")
+            }
+
+            Node::Block(nodes) => self.block(nodes),
+
+            _ => unreachable!(),
+        }
+    }
+
+    fn postfix_operand(&mut self, node: &Node) -> fmt::Result {
+        // `1.0` would be lexed as a float, so numbers need parentheses
+        if let Node::Const(Value::Int(_) | Value::Float(_)) = node.unspanned() {
+            write!(self.f, "(")?;
+            self.expr(node, ASSIGN_PREC)?;
+            write!(self.f, ")")
+        } else {
+            self.expr(node, POSTFIX_PREC)
+        }
+    }
+
+    fn args(&mut self, args: &[Node]) -> fmt::Result {
+        for (idx, arg) in args.iter().enumerate() {
+            if idx > 0 {
+                write!(self.f, ", ")?;
+            }
+
+            self.expr(arg, ASSIGN_PREC)?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints `node` as a block, wrapping it in one if it's not a block
+    /// already.
+    fn block_of(&mut self, node: &Node) -> fmt::Result {
+        match node.unspanned() {
+            Node::Block(nodes) => self.block(nodes),
+            _ => self.block(std::slice::from_ref(node)),
+        }
+    }
+
+    fn block(&mut self, nodes: &[Node]) -> fmt::Result {
+        if nodes.is_empty() {
+            return write!(self.f, "{{}}");
+        }
+
+        writeln!(self.f, "{{")?;
+
+        self.indent += 1;
+
+        for (idx, node) in nodes.iter().enumerate() {
+            let is_last = idx + 1 == nodes.len();
+
+            self.write_indent()?;
+
+            if is_block_like(node) {
+                self.expr(node, ASSIGN_PREC)?;
+            } else {
+                // A statement such as `{ a } + b` would get parsed as two
+                // separate statements (`{ a }` and `+b`), so it has to be
+                // wrapped in parentheses
+                if starts_with_block_like(node) {
+                    write!(self.f, "(")?;
+                    self.expr(node, ASSIGN_PREC)?;
+                    write!(self.f, ")")?;
+                } else {
+                    self.expr(node, ASSIGN_PREC)?;
+                }
+
+                if !is_last {
+                    write!(self.f, ";")?;
+                }
+            }
+
+            writeln!(self.f)?;
+        }
+
+        self.indent -= 1;
+        self.write_indent()?;
+
+        write!(self.f, "}}")
+    }
+
+    fn literal(&mut self, value: &Value) -> fmt::Result {
+        match value {
+            Value::Unit => write!(self.f, "()"),
+            Value::Bool(value) => write!(self.f, "{}", value),
+            Value::Int(value) => write!(self.f, "{}", value),
+
+            // `{:?}` always includes the decimal point (or the exponent), so
+            // the number gets parsed back as a float
+            Value::Float(value) => write!(self.f, "{:?}", value),
+
+            Value::Char(value) => {
+                write!(self.f, "'")?;
+                write_escaped(self.f, *value, '\'')?;
+                write!(self.f, "'")
+            }
+
+            Value::Str(value) => {
+                write!(self.f, "\"")?;
+
+                for ch in value.chars() {
+                    write_escaped(self.f, ch, '"')?;
+                }
+
+                write!(self.f, "\"")
+            }
+
+            Value::Tuple(values) if values.is_empty() => write!(self.f, "(,)"),
+
+            Value::Tuple(values) => {
+                write!(self.f, "(")?;
+
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        write!(self.f, ", ")?;
+                    }
+
+                    self.literal(value)?;
+                }
+
+                if values.len() == 1 {
+                    write!(self.f, ",")?;
+                }
+
+                write!(self.f, ")")
+            }
+        }
+    }
+
+    fn write_indent(&mut self) -> fmt::Result {
+        write!(self.f, "{:1$}", "", self.indent * 4)
+    }
+}
+
+fn prec(node: &Node) -> u8 {
+    match node.unspanned() {
+        Node::Declare { .. }
+        | Node::DeclareUninit { .. }
+        | Node::Destructure { .. }
+        | Node::Assign { .. } => ASSIGN_PREC,
+        Node::Cast { .. } => CAST_PREC,
+        Node::ExtractTuple { .. } | Node::ExtractArray { .. } => POSTFIX_PREC,
+        node => BinOp::of(node).map_or(PRIMARY_PREC, |(op, _, _)| op.prec()),
+    }
+}
+
+fn is_block_like(node: &Node) -> bool {
+    matches!(
+        node.unspanned(),
+        Node::While { .. } | Node::Try { .. } | Node::Block(_)
+    )
+}
+
+/// Returns whether `node`, when printed, starts with a block-like expression.
+fn starts_with_block_like(node: &Node) -> bool {
+    let node = node.unspanned();
+
+    if let Some((_, lhs, _)) = BinOp::of(node) {
+        return starts_with_block_like(lhs);
+    }
+
+    match node {
+        Node::Cast { expr, .. }
+        | Node::ExtractTuple { expr, .. }
+        | Node::ExtractArray { expr, .. } => starts_with_block_like(expr),
+
+        node => is_block_like(node),
+    }
+}
+
+/// Writes `ch` the way it'd appear inside a literal delimited by `quote`.
+pub(super) fn write_escaped(f: &mut fmt::Formatter<'_>, ch: char, quote: char) -> fmt::Result {
+    match ch {
+        '\n' => write!(f, "\\n"),
+        '\r' => write!(f, "\\r"),
+        '\t' => write!(f, "\\t"),
+        '\0' => write!(f, "\\0"),
+        '\\' => write!(f, "\\\\"),
+        ch if ch == quote => write!(f, "\\{}", ch),
+        ch if ch.is_control() => write!(f, "\\u{{{:x}}}", ch as u32),
+        ch => write!(f, "{}", ch),
+    }
+}
@@ -0,0 +1,879 @@
+use super::parse::lex_str;
+use super::pretty::write_escaped;
+use crate::ast::*;
+use std::fmt;
+
+/// Renders program as an s-expression; see [`parse_sexpr()`] for the syntax.
+///
+/// # Panics
+///
+/// Panics if any of the program's identifiers contains `"` or `\` - those
+/// cannot be written in a way that [`parse_sexpr()`] would read back (it
+/// borrows identifiers from the source, so they cannot contain escape
+/// sequences).
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = rast! { fn(input: int) -> int { input * 2 } };
+///
+/// assert_eq!(
+///     "(program int int (block\n  (* (var input) (const 2))))",
+///     to_sexpr(&prog)
+/// );
+/// ```
+pub fn to_sexpr(prog: &Program) -> String {
+    struct Sexpr<'a, 's>(&'a Program<'s>);
+
+    impl fmt::Display for Sexpr<'_, '_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            Writer { f, indent: 0 }.program(self.0)
+        }
+    }
+
+    Sexpr(prog).to_string()
+}
+
+/// Parses program written as an s-expression, e.g. the one produced by
+/// [`to_sexpr()`].
+///
+/// The syntax is:
+///
+/// ```text
+/// program = "(" "program" type type node ")"      (* input, output, body *)
+///
+/// type    = "unit" | "bool" | "char" | "int" | "float" | "str"
+///         | "(" "tuple" { type } ")"
+///
+/// value   = "unit" | "true" | "false" | INT | FLOAT | STR
+///         | "(" "char" STR ")"                     (* single character *)
+///         | "(" "tuple" { value } ")"
+///
+/// pattern = NAME | "_" | "(" "tuple" { pattern } ")"
+///
+/// node    = "(" "const" value ")"
+///         | "(" "var" NAME ")"
+///         | "(" "extract-tuple" node INT ")"
+///         | "(" "extract-array" node node ")"
+///         | "(" "cast" node type ")"
+///         | "(" binop node node ")"                (* e.g. `(+ lhs rhs)` *)
+///         | "(" "declare" NAME [ type ] node ")"
+///         | "(" "declare-uninit" NAME [ type ] ")"
+///         | "(" "destructure" pattern node ")"
+///         | "(" "assign" NAME node ")"
+///         | "(" "while" node node ")"              (* cond, body *)
+///         | "(" "try" node NAME node ")"           (* body, name, handler *)
+///         | "(" "print" { node } ")"
+///         | "(" "println" { node } ")"
+///         | "(" "format" STR { node } ")"
+///         | "(" "block" { node } ")"
+///         | "(" "span" INT INT INT INT node ")"    (* start, end, line, column *)
+///
+/// binop   = "||" | "&&" | "==" | "!=" | ">" | ">=" | "<" | "<="
+///         | "+" | "-" | "*" | "/" | "%"
+/// ```
+///
+/// Atoms (`NAME`, `INT`, `FLOAT`, keywords) are runs of characters other
+/// than whitespace, parentheses, `"` and `;`. `INT` is an optional `-`
+/// followed by digits, `FLOAT` is anything else Rust's `f32::from_str()`
+/// accepts (so `1.5`, `1e30`, `inf` and `NaN` all work). `STR` is a string
+/// literal with the same escape sequences as in [`parse()`]; names that
+/// aren't valid atoms (e.g. `_` or ones containing spaces) can be written as
+/// strings, too, as long as they don't contain escape sequences. `;` starts a
+/// comment that runs until the end of the line.
+///
+/// Lists can be nested at most 256 levels deep, so that malicious input
+/// cannot overflow the stack.
+///
+/// Contrary to [`parse()`], nodes are not wrapped in [`Node::Spanned`]
+/// (unless the input says so), and almost every AST is representable - so
+/// `parse_sexpr(&to_sexpr(&prog))` gives back `prog`, as long as `prog` isn't
+/// nested too deep and [`to_sexpr()`] doesn't panic.
+pub fn parse_sexpr(src: &str) -> Result<Program<'_>, ParseError> {
+    let reader = Reader { src };
+    let sexpr = reader.read()?;
+
+    reader.program(&sexpr)
+}
+
+struct Writer<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    indent: usize,
+}
+
+impl Writer<'_, '_> {
+    fn program(&mut self, prog: &Program) -> fmt::Result {
+        write!(self.f, "(program ")?;
+        self.ty(&prog.input)?;
+        write!(self.f, " ")?;
+        self.ty(&prog.output)?;
+        write!(self.f, " ")?;
+        self.node(&prog.body)?;
+        write!(self.f, ")")
+    }
+
+    fn node(&mut self, node: &Node) -> fmt::Result {
+        if let Some((op, lhs, rhs)) = BinOp::of(node) {
+            write!(self.f, "({} ", op.symbol())?;
+            self.node(lhs)?;
+            write!(self.f, " ")?;
+            self.node(rhs)?;
+            return write!(self.f, ")");
+        }
+
+        match node {
+            Node::Const(value) => {
+                write!(self.f, "(const ")?;
+                self.value(value)?;
+            }
+
+            Node::Var(name) => {
+                write!(self.f, "(var ")?;
+                self.name(name)?;
+            }
+
+            Node::ExtractTuple { expr, idx } => {
+                write!(self.f, "(extract-tuple ")?;
+                self.node(expr)?;
+                write!(self.f, " {}", idx)?;
+            }
+
+            Node::ExtractArray { expr, idx } => {
+                write!(self.f, "(extract-array ")?;
+                self.node(expr)?;
+                write!(self.f, " ")?;
+                self.node(idx)?;
+            }
+
+            Node::Cast { expr, ty } => {
+                write!(self.f, "(cast ")?;
+                self.node(expr)?;
+                write!(self.f, " ")?;
+                self.ty(ty)?;
+            }
+
+            Node::Declare { name, ty, value } => {
+                write!(self.f, "(declare ")?;
+                self.name(name)?;
+                write!(self.f, " ")?;
+
+                if let Some(ty) = ty {
+                    self.ty(ty)?;
+                    write!(self.f, " ")?;
+                }
+
+                self.node(value)?;
+            }
+
+            Node::DeclareUninit { name, ty } => {
+                write!(self.f, "(declare-uninit ")?;
+                self.name(name)?;
+
+                if let Some(ty) = ty {
+                    write!(self.f, " ")?;
+                    self.ty(ty)?;
+                }
+            }
+
+            Node::Assign { name, value } => {
+                write!(self.f, "(assign ")?;
+                self.name(name)?;
+                write!(self.f, " ")?;
+                self.node(value)?;
+            }
+
+            Node::Destructure { pat, value } => {
+                write!(self.f, "(destructure ")?;
+                self.pattern(pat)?;
+                write!(self.f, " ")?;
+                self.node(value)?;
+            }
+
+            Node::While { cond, body } => {
+                write!(self.f, "(while ")?;
+                self.node(cond)?;
+                write!(self.f, " ")?;
+                self.node(body)?;
+            }
+
+            Node::Try {
+                body,
+                name,
+                handler,
+            } => {
+                write!(self.f, "(try ")?;
+                self.node(body)?;
+                write!(self.f, " ")?;
+                self.name(name)?;
+                write!(self.f, " ")?;
+                self.node(handler)?;
+            }
+
+            Node::Print(args) | Node::Println(args) => {
+                let head = if let Node::Print(_) = node {
+                    "print"
+                } else {
+                    "println"
+                };
+
+                write!(self.f, "({}", head)?;
+
+                for arg in args {
+                    write!(self.f, " ")?;
+                    self.node(arg)?;
+                }
+            }
+
+            Node::Format { fmt, args } => {
+                write!(self.f, "(format ")?;
+                self.str(fmt)?;
+
+                for arg in args {
+                    write!(self.f, " ")?;
+                    self.node(arg)?;
+                }
+            }
+
+            // Blocks are the only nodes that span multiple lines, one line per
+            // statement
+            Node::Block(nodes) => {
+                write!(self.f, "(block")?;
+
+                self.indent += 1;
+
+                for node in nodes {
+                    writeln!(self.f)?;
+                    write!(self.f, "{:1$}", "", self.indent * 2)?;
+                    self.node(node)?;
+                }
+
+                self.indent -= 1;
+            }
+
+            Node::Spanned { span, node } => {
+                write!(
+                    self.f,
+                    "(span {} {} {} {} ",
+                    span.start, span.end, span.line, span.column
+                )?;
+
+                self.node(node)?;
+            }
+
+            _ => unreachable!(),
+        }
+
+        write!(self.f, ")")
+    }
+
+    fn pattern(&mut self, pat: &Pattern) -> fmt::Result {
+        match pat {
+            Pattern::Var(name) => self.name(name),
+            Pattern::Ignore => write!(self.f, "_"),
+
+            Pattern::Tuple(pats) => {
+                write!(self.f, "(tuple")?;
+
+                for pat in pats {
+                    write!(self.f, " ")?;
+                    self.pattern(pat)?;
+                }
+
+                write!(self.f, ")")
+            }
+        }
+    }
+
+    fn ty(&mut self, ty: &Type) -> fmt::Result {
+        match ty {
+            Type::Unit => write!(self.f, "unit"),
+            Type::Bool => write!(self.f, "bool"),
+            Type::Char => write!(self.f, "char"),
+            Type::Int => write!(self.f, "int"),
+            Type::Float => write!(self.f, "float"),
+            Type::Str => write!(self.f, "str"),
+
+            Type::Tuple(tys) => {
+                write!(self.f, "(tuple")?;
+
+                for ty in tys {
+                    write!(self.f, " ")?;
+                    self.ty(ty)?;
+                }
+
+                write!(self.f, ")")
+            }
+        }
+    }
+
+    fn value(&mut self, value: &Value) -> fmt::Result {
+        match value {
+            Value::Unit => write!(self.f, "unit"),
+            Value::Bool(value) => write!(self.f, "{}", value),
+            Value::Int(value) => write!(self.f, "{}", value),
+
+            // `{:?}` always prints the decimal point (or the exponent), so
+            // that `1.0` doesn't get read back as an integer
+            Value::Float(value) => write!(self.f, "{:?}", value),
+
+            Value::Char(value) => {
+                write!(self.f, "(char \"")?;
+                write_escaped(self.f, *value, '"')?;
+                write!(self.f, "\")")
+            }
+
+            Value::Str(value) => self.str(value),
+
+            Value::Tuple(values) => {
+                write!(self.f, "(tuple")?;
+
+                for value in values {
+                    write!(self.f, " ")?;
+                    self.value(value)?;
+                }
+
+                write!(self.f, ")")
+            }
+        }
+    }
+
+    fn name(&mut self, name: &str) -> fmt::Result {
+        let is_atom = !name.is_empty()
+            && name != "_"
+            && !name
+                .chars()
+                .any(|ch| ch.is_whitespace() || is_delimiter(ch));
+
+        if is_atom {
+            return write!(self.f, "{}", name);
+        }
+
+        // Identifiers are borrowed from the source when reading, so they
+        // have to be written as-is, without escaping anything
+        assert!(
+            !name.contains(['"', '\\']),
+            "Identifier {:?} cannot be represented as an s-expression",
+            name
+        );
+
+        write!(self.f, "\"{}\"", name)
+    }
+
+    fn str(&mut self, value: &str) -> fmt::Result {
+        write!(self.f, "\"")?;
+
+        for ch in value.chars() {
+            write_escaped(self.f, ch, '"')?;
+        }
+
+        write!(self.f, "\"")
+    }
+}
+
+/// Maximum nesting of lists the reader accepts; see [`parse_sexpr()`].
+const MAX_DEPTH: usize = 256;
+
+/// Characters that end an atom.
+fn is_delimiter(ch: char) -> bool {
+    matches!(ch, '(' | ')' | '"' | ';')
+}
+
+/// Untyped s-expression, before it gets converted into the AST.
+struct Sexpr<'s> {
+    kind: SexprKind<'s>,
+
+    /// Byte offset of the expression's first character
+    offset: usize,
+}
+
+enum SexprKind<'s> {
+    Atom(&'s str),
+    Str(Cow<'s, str>),
+
+    /// `(item item ...)`; `end` is the byte offset of the closing paren
+    List {
+        items: Vec<Sexpr<'s>>,
+        end: usize,
+    },
+}
+
+impl Sexpr<'_> {
+    fn describe(&self) -> String {
+        match &self.kind {
+            SexprKind::Atom(atom) => format!("`{}`", atom),
+            SexprKind::Str(value) => format!("{:?}", value),
+            SexprKind::List { .. } => "list".into(),
+        }
+    }
+}
+
+type ReadResult<T> = Result<T, ParseError>;
+
+struct Reader<'s> {
+    src: &'s str,
+}
+
+impl<'s> Reader<'s> {
+    /// Splits the source code into a single top-level s-expression.
+    fn read(&self) -> ReadResult<Sexpr<'s>> {
+        let mut pos = 0;
+
+        // Lists that are still open, along with their items so far
+        let mut open: Vec<(usize, Vec<Sexpr<'s>>)> = Vec::new();
+        let mut result: Option<Sexpr<'s>> = None;
+
+        loop {
+            // Whitespace and comments
+            loop {
+                let rest = &self.src[pos..];
+                let trimmed = rest.trim_start();
+
+                pos += rest.len() - trimmed.len();
+
+                if trimmed.starts_with(';') {
+                    pos += trimmed.find('\n').unwrap_or(trimmed.len());
+                } else {
+                    break;
+                }
+            }
+
+            let start = pos;
+
+            let sexpr = match self.src[pos..].chars().next() {
+                None => break,
+
+                Some('(') => {
+                    if open.len() >= MAX_DEPTH {
+                        return Err(self.error(start, "nesting is too deep"));
+                    }
+
+                    pos += 1;
+                    open.push((start, Vec::new()));
+                    continue;
+                }
+
+                Some(')') => {
+                    let (offset, items) = open
+                        .pop()
+                        .ok_or_else(|| self.error(start, "unexpected `)`"))?;
+
+                    pos += 1;
+
+                    Sexpr {
+                        kind: SexprKind::List { items, end: start },
+                        offset,
+                    }
+                }
+
+                Some('"') => {
+                    let (value, end) = lex_str(self.src, start)?;
+
+                    pos = end;
+
+                    Sexpr {
+                        kind: SexprKind::Str(value),
+                        offset: start,
+                    }
+                }
+
+                Some(_) => {
+                    pos = self.src[pos..]
+                        .find(|ch: char| ch.is_whitespace() || is_delimiter(ch))
+                        .map_or(self.src.len(), |len| pos + len);
+
+                    Sexpr {
+                        kind: SexprKind::Atom(&self.src[start..pos]),
+                        offset: start,
+                    }
+                }
+            };
+
+            if let Some((_, items)) = open.last_mut() {
+                items.push(sexpr);
+            } else if result.is_none() {
+                result = Some(sexpr);
+            } else {
+                return Err(self.error(
+                    sexpr.offset,
+                    format!("expected end of input, found {}", sexpr.describe()),
+                ));
+            }
+        }
+
+        if let Some((offset, _)) = open.last() {
+            return Err(self.error(*offset, "unclosed `(`"));
+        }
+
+        result.ok_or_else(|| self.error(pos, "expected `(program ...)`, found end of input"))
+    }
+
+    fn program(&self, sexpr: &Sexpr<'s>) -> ReadResult<Program<'s>> {
+        let mut args = self.form(sexpr, "`(program ...)`")?;
+
+        args.keyword("program")?;
+
+        let input = self.ty(args.next("input type")?)?;
+        let output = self.ty(args.next("output type")?)?;
+        let body = self.node(args.next("program's body")?)?;
+
+        args.finish()?;
+
+        Ok(Program {
+            input,
+            output,
+            body,
+        })
+    }
+
+    fn node(&self, sexpr: &Sexpr<'s>) -> ReadResult<Node<'s>> {
+        let mut args = self.form(sexpr, "node")?;
+        let (head, head_offset) = args.head;
+
+        let node = if let Some(op) = BinOp::from_symbol(head) {
+            let lhs = self.node(args.next("left-hand side")?)?;
+            let rhs = self.node(args.next("right-hand side")?)?;
+
+            op.build(lhs, rhs)
+        } else {
+            match head {
+                "const" => Node::Const(self.value(args.next("value")?)?),
+                "var" => Node::Var(self.name(args.next("variable name")?)?),
+
+                "extract-tuple" => Node::ExtractTuple {
+                    expr: Box::new(self.node(args.next("node")?)?),
+                    idx: self.usize(args.next("tuple index")?, "tuple index")?,
+                },
+
+                "extract-array" => Node::ExtractArray {
+                    expr: Box::new(self.node(args.next("node")?)?),
+                    idx: Box::new(self.node(args.next("index")?)?),
+                },
+
+                "cast" => Node::Cast {
+                    expr: Box::new(self.node(args.next("node")?)?),
+                    ty: self.ty(args.next("type")?)?,
+                },
+
+                "declare" => {
+                    let name = self.name(args.next("variable name")?)?;
+
+                    // Type is optional, so it's there only if it's followed
+                    // by the value
+                    let ty = if args.items.len() > 1 {
+                        Some(self.ty(args.next("type")?)?)
+                    } else {
+                        None
+                    };
+
+                    Node::Declare {
+                        name,
+                        ty,
+                        value: Box::new(self.node(args.next("value")?)?),
+                    }
+                }
+
+                "declare-uninit" => Node::DeclareUninit {
+                    name: self.name(args.next("variable name")?)?,
+                    ty: if args.items.is_empty() {
+                        None
+                    } else {
+                        Some(self.ty(args.next("type")?)?)
+                    },
+                },
+
+                "destructure" => Node::Destructure {
+                    pat: self.pattern(args.next("pattern")?)?,
+                    value: Box::new(self.node(args.next("value")?)?),
+                },
+
+                "assign" => Node::Assign {
+                    name: self.name(args.next("variable name")?)?,
+                    value: Box::new(self.node(args.next("value")?)?),
+                },
+
+                "while" => Node::While {
+                    cond: Box::new(self.node(args.next("condition")?)?),
+                    body: Box::new(self.node(args.next("loop's body")?)?),
+                },
+
+                "try" => Node::Try {
+                    body: Box::new(self.node(args.next("node")?)?),
+                    name: self.name(args.next("error's name")?)?,
+                    handler: Box::new(self.node(args.next("handler")?)?),
+                },
+
+                "print" => Node::Print(self.nodes(args.rest())?),
+                "println" => Node::Println(self.nodes(args.rest())?),
+
+                "format" => Node::Format {
+                    fmt: self.str(args.next("format string")?, "format string")?,
+                    args: self.nodes(args.rest())?,
+                },
+
+                "block" => Node::Block(self.nodes(args.rest())?),
+
+                "span" => {
+                    let span = Span {
+                        start: self.usize(args.next("start offset")?, "start offset")?,
+                        end: self.usize(args.next("end offset")?, "end offset")?,
+                        line: self.usize(args.next("line number")?, "line number")?,
+                        column: self.usize(args.next("column number")?, "column number")?,
+                    };
+
+                    Node::Spanned {
+                        span,
+                        node: Box::new(self.node(args.next("node")?)?),
+                    }
+                }
+
+                _ => return Err(self.error(head_offset, format!("unknown node `{}`", head))),
+            }
+        };
+
+        args.finish()?;
+
+        Ok(node)
+    }
+
+    fn nodes(&self, sexprs: &[Sexpr<'s>]) -> ReadResult<Vec<Node<'s>>> {
+        sexprs.iter().map(|sexpr| self.node(sexpr)).collect()
+    }
+
+    fn pattern(&self, sexpr: &Sexpr<'s>) -> ReadResult<Pattern<'s>> {
+        if let SexprKind::Atom("_") = sexpr.kind {
+            return Ok(Pattern::Ignore);
+        }
+
+        if let SexprKind::List { .. } = sexpr.kind {
+            let mut args = self.form(sexpr, "pattern")?;
+
+            args.keyword("tuple")?;
+
+            return args
+                .rest()
+                .iter()
+                .map(|sexpr| self.pattern(sexpr))
+                .collect::<ReadResult<_>>()
+                .map(Pattern::Tuple);
+        }
+
+        self.name(sexpr).map(Pattern::Var)
+    }
+
+    fn ty(&self, sexpr: &Sexpr<'s>) -> ReadResult<Type> {
+        let ty = match sexpr.kind {
+            SexprKind::Atom("unit") => Type::Unit,
+            SexprKind::Atom("bool") => Type::Bool,
+            SexprKind::Atom("char") => Type::Char,
+            SexprKind::Atom("int") => Type::Int,
+            SexprKind::Atom("float") => Type::Float,
+            SexprKind::Atom("str") => Type::Str,
+
+            SexprKind::List { .. } => {
+                let mut args = self.form(sexpr, "type")?;
+
+                args.keyword("tuple")?;
+
+                return args
+                    .rest()
+                    .iter()
+                    .map(|sexpr| self.ty(sexpr))
+                    .collect::<ReadResult<_>>()
+                    .map(Type::Tuple);
+            }
+
+            _ => return Err(self.expected(sexpr, "type")),
+        };
+
+        Ok(ty)
+    }
+
+    fn value(&self, sexpr: &Sexpr<'s>) -> ReadResult<Value<'s>> {
+        match &sexpr.kind {
+            SexprKind::Atom("unit") => Ok(Value::Unit),
+            SexprKind::Atom("true") => Ok(Value::Bool(true)),
+            SexprKind::Atom("false") => Ok(Value::Bool(false)),
+            SexprKind::Atom(atom) => self.number(atom, sexpr.offset),
+            SexprKind::Str(value) => Ok(Value::Str(value.clone())),
+
+            SexprKind::List { .. } => {
+                let mut args = self.form(sexpr, "value")?;
+                let (head, head_offset) = args.head;
+
+                let value = match head {
+                    "char" => {
+                        let arg = args.next("character")?;
+                        let value = self.str(arg, "string")?;
+                        let mut chars = value.chars();
+
+                        match (chars.next(), chars.next()) {
+                            (Some(ch), None) => Value::Char(ch),
+                            _ => {
+                                return Err(
+                                    self.error(arg.offset, "expected a single-character string")
+                                )
+                            }
+                        }
+                    }
+
+                    "tuple" => Value::Tuple(
+                        args.rest()
+                            .iter()
+                            .map(|sexpr| self.value(sexpr))
+                            .collect::<ReadResult<_>>()?,
+                    ),
+
+                    _ => {
+                        return Err(self.error(
+                            head_offset,
+                            format!("expected `char` or `tuple`, found `{}`", head),
+                        ))
+                    }
+                };
+
+                args.finish()?;
+
+                Ok(value)
+            }
+        }
+    }
+
+    fn number(&self, atom: &str, offset: usize) -> ReadResult<Value<'s>> {
+        let digits = atom.strip_prefix('-').unwrap_or(atom);
+
+        // Checked separately, so that too large integers are reported instead
+        // of being silently read as floats
+        if !digits.is_empty() && digits.chars().all(|ch| ch.is_ascii_digit()) {
+            return atom
+                .parse()
+                .map(Value::Int)
+                .map_err(|_| self.error(offset, "integer literal is too large"));
+        }
+
+        atom.parse()
+            .map(Value::Float)
+            .map_err(|_| self.error(offset, format!("expected value, found `{}`", atom)))
+    }
+
+    fn usize(&self, sexpr: &Sexpr<'s>, what: &str) -> ReadResult<usize> {
+        match sexpr.kind {
+            SexprKind::Atom(atom) if atom.chars().all(|ch| ch.is_ascii_digit()) => atom
+                .parse()
+                .map_err(|_| self.error(sexpr.offset, "integer literal is too large")),
+
+            _ => Err(self.expected(sexpr, what)),
+        }
+    }
+
+    fn name(&self, sexpr: &Sexpr<'s>) -> ReadResult<Ident<'s>> {
+        match sexpr.kind {
+            SexprKind::Atom(name) | SexprKind::Str(Cow::Borrowed(name)) => Ok(name),
+
+            SexprKind::Str(Cow::Owned(_)) => {
+                Err(self.error(sexpr.offset, "identifiers cannot contain escape sequences"))
+            }
+
+            _ => Err(self.expected(sexpr, "identifier")),
+        }
+    }
+
+    fn str(&self, sexpr: &Sexpr<'s>, what: &str) -> ReadResult<Cow<'s, str>> {
+        match &sexpr.kind {
+            SexprKind::Str(value) => Ok(value.clone()),
+            _ => Err(self.expected(sexpr, what)),
+        }
+    }
+
+    /// Expects a list that starts with an atom (e.g. `(var x)`) and returns
+    /// its arguments.
+    fn form<'a>(&self, sexpr: &'a Sexpr<'s>, what: &str) -> ReadResult<Args<'a, 's>> {
+        if let SexprKind::List { items, end } = &sexpr.kind {
+            if let Some(Sexpr {
+                kind: SexprKind::Atom(head),
+                offset,
+            }) = items.first()
+            {
+                return Ok(Args {
+                    src: self.src,
+                    head: (head, *offset),
+                    items: &items[1..],
+                    end: *end,
+                });
+            }
+
+            if let Some(item) = items.first() {
+                return Err(self.expected(item, &format!("name of {}", what)));
+            }
+        }
+
+        Err(self.expected(sexpr, what))
+    }
+
+    fn expected(&self, sexpr: &Sexpr<'s>, what: &str) -> ParseError {
+        self.error(
+            sexpr.offset,
+            format!("expected {}, found {}", what, sexpr.describe()),
+        )
+    }
+
+    fn error(&self, offset: usize, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.src, offset, message)
+    }
+}
+
+/// Arguments of a list, e.g. `x` and `(const 1)` in `(assign x (const 1))`.
+struct Args<'a, 's> {
+    src: &'s str,
+
+    /// The list's first atom, along with its byte offset
+    head: (&'s str, usize),
+
+    items: &'a [Sexpr<'s>],
+
+    /// Byte offset of the list's closing paren
+    end: usize,
+}
+
+impl<'a, 's> Args<'a, 's> {
+    fn keyword(&self, keyword: &str) -> ReadResult<()> {
+        let (head, offset) = self.head;
+
+        if head == keyword {
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                self.src,
+                offset,
+                format!("expected `{}`, found `{}`", keyword, head),
+            ))
+        }
+    }
+
+    fn next(&mut self, what: &str) -> ReadResult<&'a Sexpr<'s>> {
+        let (item, items) = self.items.split_first().ok_or_else(|| {
+            ParseError::new(self.src, self.end, format!("expected {}, found `)`", what))
+        })?;
+
+        self.items = items;
+
+        Ok(item)
+    }
+
+    fn rest(&mut self) -> &'a [Sexpr<'s>] {
+        std::mem::take(&mut self.items)
+    }
+
+    fn finish(self) -> ReadResult<()> {
+        match self.items.first() {
+            Some(item) => Err(ParseError::new(
+                self.src,
+                item.offset,
+                format!("expected `)`, found {}", item.describe()),
+            )),
+
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::ast::*;
+use std::fmt;
+
+/// Location of a node in the source code it's been parsed from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// Byte offset of the node's first character
+    pub start: usize,
+
+    /// Byte offset right past the node's last character
+    pub end: usize,
+
+    /// 1-based line number of `start`
+    pub line: usize,
+
+    /// 1-based column number of `start`, in characters
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl<'s> Program<'s> {
+    /// Removes all [`Node::Spanned`] wrappers from the program; see
+    /// [`Node::without_spans()`].
+    pub fn without_spans(self) -> Self {
+        Self {
+            body: self.body.without_spans(),
+            ..self
+        }
+    }
+}
+
+impl<'s> Node<'s> {
+    /// Returns the node, looking through its [`Node::Spanned`] wrappers (if
+    /// any); children are left as they are.
+    pub fn unspanned(&self) -> &Self {
+        match self {
+            Node::Spanned { node, .. } => node.unspanned(),
+            node => node,
+        }
+    }
+
+    /// Returns node's span, if it's wrapped in [`Node::Spanned`].
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Node::Spanned { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// Removes all [`Node::Spanned`] wrappers from the node and its children.
+    ///
+    /// Useful for comparing parsed programs with the ones built by hand:
+    ///
+    /// ```
+    /// # use rast_jit_vm::prelude::*;
+    /// #
+    /// let node = parse_node("input").unwrap();
+    ///
+    /// assert_ne!(Node::Var("input"), node);
+    /// assert_eq!(Node::Var("input"), node.without_spans());
+    /// ```
+    pub fn without_spans(self) -> Self {
+        struct WithoutSpans;
+
+        impl<'s> Fold<'s> for WithoutSpans {
+            fn fold_node(&mut self, node: Node<'s>) -> Node<'s> {
+                match node {
+                    Node::Spanned { node, .. } => self.fold_node(*node),
+                    node => walk_fold_node(self, node),
+                }
+            }
+        }
+
+        WithoutSpans.fold_node(self)
+    }
+}
@@ -0,0 +1,782 @@
+use crate::ast::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Type-checks the program and records the type of every node.
+///
+/// This is the pass both engines run before doing anything else:
+/// `vm::compile()` relies on the recorded types to pick thunks, while
+/// `vm::eval()` uses it to reject ill-typed programs up front, instead of
+/// failing once (and if) it reaches the offending node.
+///
+/// Checking doesn't stop at the first error - all of them are returned, in
+/// the order they've been found; nodes whose children are ill-typed are not
+/// reported again. If the source code is at hand, [`render_errors()`] can
+/// print them along with the offending lines.
+///
+/// Variables must be initialized on every path leading to their uses - e.g. a
+/// variable declared inside a loop cannot be used after it, since the loop
+/// might not run at all. Variables declared without a value (`var x;`) get
+/// initialized by the first assignment.
+///
+/// Types are inferred bottom-up, with one exception: a variable declared
+/// without a value (and without a type annotation) gets its type from the
+/// values assigned to it later - the first assignment determines it, and the
+/// other ones are checked against it, like with any other variable. Since a
+/// variable must be assigned to before it's read, its type is always known by
+/// the time it's used; variables that are never assigned to are reported as
+/// ambiguous, pointing at their declarations.
+///
+/// That's not a constraint-based (Hindley-Milner style) inference, though:
+/// the checker walks the program once, in evaluation order, and it's the first
+/// assignment it reaches that decides - so for a variable assigned an `int` in
+/// a `try` body and a `str` in its `catch`, the latter is reported as a type
+/// mismatch (against `int`), rather than the variable's type as ambiguous.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = parse("fn(input: int) -> float {\n  input + 1.0\n}").unwrap();
+/// let errs = typecheck(&prog).unwrap_err();
+///
+/// assert_eq!("2:3: Unknown operation `Int` `Add` `Float`", errs[0].to_string());
+///
+/// let prog = rast! { fn(input: int) -> float { input as float } };
+/// let typed = typecheck(&prog).unwrap();
+///
+/// // The block, the cast and the variable
+/// assert_eq!(&[Type::Float, Type::Float, Type::Int], typed.types());
+/// ```
+pub fn typecheck<'a, 's>(prog: &'a Program<'s>) -> Result<TypedProgram<'a, 's>, Vec<TypeError>> {
+    let mut checker = Checker {
+        vars: BTreeMap::from_iter([("input", Some(prog.input.clone()))]),
+        init: BTreeMap::from_iter([("input", true)]),
+        pending: Vec::new(),
+        types: Vec::new(),
+        errors: Vec::new(),
+        span: None,
+    };
+
+    if let Some(ty) = checker.node(&prog.body) {
+        if ty != prog.output {
+            checker.span = prog.body.span();
+
+            checker.fail::<()>(TypeErrorKind::OutputMismatch {
+                declared: prog.output.clone(),
+                actual: ty,
+            });
+        }
+    }
+
+    for (name, span) in std::mem::take(&mut checker.pending) {
+        checker.span = span;
+
+        checker.fail::<()>(TypeErrorKind::AmbiguousType {
+            name: name.to_string(),
+        });
+    }
+
+    if checker.errors.is_empty() {
+        Ok(TypedProgram {
+            prog,
+            types: checker.types.into_iter().map(Option::unwrap).collect(),
+        })
+    } else {
+        Err(checker.errors)
+    }
+}
+
+/// Program that's passed [`typecheck()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedProgram<'a, 's> {
+    prog: &'a Program<'s>,
+    types: Vec<Type>,
+}
+
+impl<'a, 's> TypedProgram<'a, 's> {
+    pub fn program(&self) -> &'a Program<'s> {
+        self.prog
+    }
+
+    /// Returns types of all nodes, in the order [`Visitor`] visits them - so
+    /// the first one is the type of the program's body.
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
+    pub fn into_types(self) -> Vec<Type> {
+        self.types
+    }
+}
+
+/// Error returned by [`typecheck()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+
+    /// Span of the offending node, if the program has been parsed from
+    /// source code
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = self.span {
+            write!(f, "{}: ", span)?;
+        }
+
+        write!(f, "{}", self.kind)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeErrorKind {
+    /// `name` is used, but it hasn't been declared
+    UnknownVariable { name: String },
+
+    /// `var name = ...`, but `name` has been already declared
+    AlreadyDefined { name: String },
+
+    /// `name = ...`, where `name` cannot be assigned to (i.e. it's `input`)
+    ReadOnlyVariable { name: String },
+
+    /// `name` or `name = ...`, where `name` has been declared, but not on
+    /// every path leading here (e.g. only inside a loop's body)
+    UninitializedVariable { name: String },
+
+    /// `name = value`, where `value` doesn't match the variable's type
+    AssignMismatch {
+        name: String,
+        var: Type,
+        value: Type,
+    },
+
+    /// `var name: ty = value`, where `value` doesn't match the declared type
+    DeclareMismatch {
+        name: String,
+        var: Type,
+        value: Type,
+    },
+
+    /// `expr.idx` or `var (...) = expr`, where `expr` is not a tuple
+    NotATuple { ty: Type },
+
+    /// `expr.idx`, where `expr` doesn't have that many elements
+    InvalidSubscript { tuple: Vec<Type>, idx: usize },
+
+    /// `var (...) = expr`, where the pattern's and the tuple's lengths differ
+    PatternMismatch { tuple: Vec<Type>, expected: usize },
+
+    /// `expr[idx]`, where `expr` is not a `str` or `idx` is not an `int`
+    UnexpectedType { expected: Type, actual: Type },
+
+    /// `expr as ty`, where `expr` cannot be converted into `ty`
+    InvalidCast { from: Type, to: Type },
+
+    /// `lhs op rhs`, where `op` is not defined for given operands; `op` is
+    /// the name of the corresponding [`Node`] variant (e.g. `Add`)
+    UnknownOperation { lhs: Type, op: String, rhs: Type },
+
+    /// `while cond { ... }`, where `cond` is not a `bool`
+    InvalidCondition { ty: Type },
+
+    /// `try { body } catch ... { handler }`, where both blocks return
+    /// different types
+    TryMismatch { body: Type, handler: Type },
+
+    /// `format("fmt", ...)`, where `fmt` is malformed
+    InvalidFormat { fmt: String, message: String },
+
+    /// `format("fmt", ...)`, where `fmt` expects a different number of
+    /// arguments
+    FormatArgsMismatch { expected: usize, actual: usize },
+
+    /// Program's body returns a different type than the declared output
+    OutputMismatch { declared: Type, actual: Type },
+
+    /// `var name;`, where `name` is never assigned to, so its type cannot be
+    /// inferred
+    AmbiguousType { name: String },
+}
+
+impl TypeErrorKind {
+    /// Returns the error's code (e.g. `E0001`), printed by
+    /// [`render_errors()`]; codes are stable - new kinds get new codes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeErrorKind::UnknownVariable { .. } => "E0001",
+            TypeErrorKind::AlreadyDefined { .. } => "E0002",
+            TypeErrorKind::ReadOnlyVariable { .. } => "E0003",
+            TypeErrorKind::AssignMismatch { .. } => "E0004",
+            TypeErrorKind::NotATuple { .. } => "E0005",
+            TypeErrorKind::InvalidSubscript { .. } => "E0006",
+            TypeErrorKind::PatternMismatch { .. } => "E0007",
+            TypeErrorKind::UnexpectedType { .. } => "E0008",
+            TypeErrorKind::InvalidCast { .. } => "E0009",
+            TypeErrorKind::UnknownOperation { .. } => "E0010",
+            TypeErrorKind::InvalidCondition { .. } => "E0011",
+            TypeErrorKind::TryMismatch { .. } => "E0012",
+            TypeErrorKind::InvalidFormat { .. } => "E0013",
+            TypeErrorKind::FormatArgsMismatch { .. } => "E0014",
+            TypeErrorKind::OutputMismatch { .. } => "E0015",
+            TypeErrorKind::DeclareMismatch { .. } => "E0016",
+            TypeErrorKind::UninitializedVariable { .. } => "E0017",
+            TypeErrorKind::AmbiguousType { .. } => "E0018",
+        }
+    }
+}
+
+impl fmt::Display for TypeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeErrorKind::UnknownVariable { name } => write!(f, "Unknown variable: {}", name),
+
+            TypeErrorKind::AlreadyDefined { name } => {
+                write!(f, "Variable `{}` has been already defined", name)
+            }
+
+            TypeErrorKind::ReadOnlyVariable { name } => {
+                write!(f, "`{}` variable is read-only", name)
+            }
+
+            TypeErrorKind::UninitializedVariable { name } => {
+                write!(f, "Variable `{}` might be uninitialized here", name)
+            }
+
+            TypeErrorKind::AmbiguousType { name } => write!(
+                f,
+                "Type of variable `{}` cannot be inferred, since it's never assigned to \
+                 (consider annotating it, e.g. `var {}: int`)",
+                name, name
+            ),
+
+            TypeErrorKind::AssignMismatch { var, value, .. } => write!(
+                f,
+                "Type mismatch: Cannot assign `{:?}` to `{:?}`",
+                value, var
+            ),
+
+            TypeErrorKind::DeclareMismatch { name, var, value } => write!(
+                f,
+                "Type mismatch: Variable `{}` is declared as `{:?}`, but its value is `{:?}`",
+                name, var, value
+            ),
+
+            TypeErrorKind::NotATuple { ty } => {
+                write!(f, "Invalid type: `{:?}` is not a tuple", ty)
+            }
+
+            TypeErrorKind::InvalidSubscript { tuple, idx } => write!(
+                f,
+                "Invalid subscript: Tuple `{:?}` doesn't have element .{}",
+                tuple, idx
+            ),
+
+            TypeErrorKind::PatternMismatch { tuple, expected } => write!(
+                f,
+                "Invalid pattern: Tuple `{:?}` has {} element(s), but the \
+                 pattern expects {}",
+                tuple,
+                tuple.len(),
+                expected
+            ),
+
+            TypeErrorKind::UnexpectedType { expected, actual } => write!(
+                f,
+                "Invalid type: Expected `{}`, got `{:?}`",
+                expected, actual
+            ),
+
+            TypeErrorKind::InvalidCast { from, to } => {
+                write!(f, "Cannot cast `{:?}` to `{:?}`", from, to)
+            }
+
+            TypeErrorKind::UnknownOperation { lhs, op, rhs } => {
+                write!(f, "Unknown operation `{:?}` `{}` `{:?}`", lhs, op, rhs)
+            }
+
+            TypeErrorKind::InvalidCondition { .. } => write!(
+                f,
+                "Type mismatch: Loop's condition was expected to be `bool`"
+            ),
+
+            TypeErrorKind::TryMismatch { body, handler } => write!(
+                f,
+                "Type mismatch: `try` returns `{:?}`, but `catch` returns `{:?}`",
+                body, handler
+            ),
+
+            TypeErrorKind::InvalidFormat { fmt, message } => {
+                write!(f, "Invalid format string `{}`: {}", fmt, message)
+            }
+
+            TypeErrorKind::FormatArgsMismatch { expected, actual } => write!(
+                f,
+                "Invalid format: Format string expects {} argument(s), got {}",
+                expected, actual
+            ),
+
+            TypeErrorKind::OutputMismatch { declared, actual } => write!(
+                f,
+                "Output type mismatch: Program declared to return `{:?}`, but in \
+                 reality it returns `{:?}`",
+                declared, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+struct Checker<'s> {
+    /// Types of variables declared so far; `None` stands for variables whose
+    /// value is ill-typed
+    vars: BTreeMap<Ident<'s>, Option<Type>>,
+
+    /// Variables that are declared on every path leading to the node being
+    /// checked, along with whether they are initialized on all of them, too
+    init: BTreeMap<Ident<'s>, bool>,
+
+    /// Variables declared without a value and a type, whose types will be
+    /// known once they're assigned to; along with spans of the declarations
+    pending: Vec<(Ident<'s>, Option<Span>)>,
+
+    /// Types of the nodes checked so far, in pre-order
+    types: Vec<Option<Type>>,
+
+    errors: Vec<TypeError>,
+
+    /// Span of the innermost node being checked (if known)
+    span: Option<Span>,
+}
+
+impl<'s> Checker<'s> {
+    /// Returns node's type, or `None` if it (or any of its children) is
+    /// ill-typed.
+    fn node(&mut self, node: &Node<'s>) -> Option<Type> {
+        // Nodes are numbered in pre-order, but their types are known only
+        // after their children are checked
+        let idx = self.types.len();
+
+        self.types.push(None);
+
+        let ty = self.node_inner(node);
+
+        self.types[idx] = ty.clone();
+        ty
+    }
+
+    fn node_inner(&mut self, node: &Node<'s>) -> Option<Type> {
+        if let Some((op, lhs, rhs)) = BinOp::of(node) {
+            return self.binop(op, lhs, rhs);
+        }
+
+        match node {
+            Node::Const(value) => Some(value.ty()),
+
+            Node::Var(name) => self.var(name),
+
+            Node::ExtractTuple { expr, idx } => match self.node(expr)? {
+                Type::Tuple(tys) => match tys.get(*idx) {
+                    Some(ty) => Some(ty.clone()),
+
+                    None => self.fail(TypeErrorKind::InvalidSubscript {
+                        tuple: tys,
+                        idx: *idx,
+                    }),
+                },
+
+                ty => self.fail(TypeErrorKind::NotATuple { ty }),
+            },
+
+            // The result's type is known even if operands are ill-typed, so
+            // it's returned anyway, not to report errors for the parent node
+            Node::ExtractArray { expr, idx } => {
+                let expr_ty = self.node(expr);
+                let idx_ty = self.node(idx);
+
+                if let Some(ty) = expr_ty.filter(|ty| *ty != Type::Str) {
+                    self.fail::<()>(TypeErrorKind::UnexpectedType {
+                        expected: Type::Str,
+                        actual: ty,
+                    });
+                }
+
+                if let Some(ty) = idx_ty.filter(|ty| *ty != Type::Int) {
+                    self.fail::<()>(TypeErrorKind::UnexpectedType {
+                        expected: Type::Int,
+                        actual: ty,
+                    });
+                }
+
+                Some(Type::Char)
+            }
+
+            Node::Cast { expr, ty } => {
+                let source_ty = self.node(expr);
+                let is_valid = |source_ty: &Type| {
+                    matches!(
+                        (source_ty, ty),
+                        (Type::Int, Type::Float) | (Type::Float, Type::Int)
+                    )
+                };
+
+                if let Some(source_ty) = source_ty.filter(|source_ty| !is_valid(source_ty)) {
+                    self.fail::<()>(TypeErrorKind::InvalidCast {
+                        from: source_ty,
+                        to: ty.clone(),
+                    });
+                }
+
+                Some(ty.clone())
+            }
+
+            Node::Declare { name, ty, value } => {
+                let value_ty = self.node(value);
+
+                // Annotated variables get the declared type even if the value
+                // doesn't match (or is ill-typed), so that the mistake gets
+                // reported here and not at the variable's uses
+                let ty = match ty {
+                    Some(ty) => {
+                        if let Some(value_ty) = value_ty.filter(|value_ty| value_ty != ty) {
+                            self.fail::<()>(TypeErrorKind::DeclareMismatch {
+                                name: name.to_string(),
+                                var: ty.clone(),
+                                value: value_ty,
+                            });
+                        }
+
+                        Some(ty.clone())
+                    }
+
+                    None => value_ty,
+                };
+
+                self.declare(name, ty);
+
+                Some(Type::Unit)
+            }
+
+            Node::DeclareUninit { name, ty } => {
+                self.declare(name, ty.clone());
+                self.init.insert(name, false);
+
+                if ty.is_none() {
+                    self.pending.push((name, self.span));
+                }
+
+                Some(Type::Unit)
+            }
+
+            Node::Destructure { pat, value } => {
+                let ty = self.node(value);
+
+                self.pattern(pat, ty);
+
+                Some(Type::Unit)
+            }
+
+            Node::Assign { name, value } => {
+                let var_ty = if *name == "input" {
+                    self.fail(TypeErrorKind::ReadOnlyVariable {
+                        name: name.to_string(),
+                    })
+                } else {
+                    self.assignee(name)
+                };
+
+                let value_ty = self.node(value);
+
+                if let Some(is_init) = self.init.get_mut(name) {
+                    *is_init = true;
+                }
+
+                // The first assignment to a variable declared without a type
+                // determines that type
+                if let Some(idx) = self.pending.iter().position(|(pending, _)| pending == name) {
+                    self.pending.remove(idx);
+                    self.vars.insert(name, value_ty);
+
+                    return Some(Type::Unit);
+                }
+
+                if let (Some(var_ty), Some(value_ty)) = (var_ty, value_ty) {
+                    if value_ty != var_ty {
+                        self.fail::<()>(TypeErrorKind::AssignMismatch {
+                            name: name.to_string(),
+                            var: var_ty,
+                            value: value_ty,
+                        });
+                    }
+                }
+
+                Some(Type::Unit)
+            }
+
+            Node::While { cond, body } => {
+                let cond_ty = self.node(cond);
+
+                // The body might not run, so whatever it declares is not
+                // initialized after the loop
+                let init = self.init.clone();
+
+                self.node(body);
+                self.init = init;
+
+                if let Some(ty) = cond_ty.filter(|ty| *ty != Type::Bool) {
+                    self.fail::<()>(TypeErrorKind::InvalidCondition { ty });
+                }
+
+                Some(Type::Unit)
+            }
+
+            Node::Try {
+                body,
+                name,
+                handler,
+            } => {
+                // The body can fail at any point, so the handler sees only
+                // what had been initialized before; after the whole thing,
+                // only what both branches initialize is
+                let init = self.init.clone();
+                let body_ty = self.node(body);
+                let body_init = std::mem::replace(&mut self.init, init);
+                let shadows = self.vars.contains_key(name);
+
+                self.declare(name, Some(Type::Str));
+
+                let handler_ty = self.node(handler);
+
+                // The error is visible only inside the handler, so that
+                // consecutive handlers can use the same name for it
+                if !shadows {
+                    self.vars.remove(name);
+                    self.init.remove(name);
+                }
+
+                self.init = std::mem::take(&mut self.init)
+                    .into_iter()
+                    .filter_map(|(name, is_init)| Some((name, is_init && *body_init.get(name)?)))
+                    .collect();
+
+                match (body_ty, handler_ty) {
+                    (Some(body_ty), Some(handler_ty)) if body_ty != handler_ty => {
+                        self.fail(TypeErrorKind::TryMismatch {
+                            body: body_ty,
+                            handler: handler_ty,
+                        })
+                    }
+
+                    (Some(ty), _) | (None, Some(ty)) => Some(ty),
+                    (None, None) => None,
+                }
+            }
+
+            Node::Print(nodes) | Node::Println(nodes) => {
+                for node in nodes {
+                    self.node(node);
+                }
+
+                Some(Type::Unit)
+            }
+
+            Node::Format { fmt, args } => {
+                match Format::parse(fmt) {
+                    Ok(fmt) => {
+                        if fmt.args() != args.len() {
+                            self.fail::<()>(TypeErrorKind::FormatArgsMismatch {
+                                expected: fmt.args(),
+                                actual: args.len(),
+                            });
+                        }
+                    }
+
+                    Err(err) => {
+                        self.fail::<()>(TypeErrorKind::InvalidFormat {
+                            fmt: fmt.to_string(),
+                            message: err,
+                        });
+                    }
+                }
+
+                for arg in args {
+                    self.node(arg);
+                }
+
+                Some(Type::Str)
+            }
+
+            Node::Block(nodes) => {
+                let mut ty = Some(Type::Unit);
+
+                for node in nodes {
+                    ty = self.node(node);
+                }
+
+                ty
+            }
+
+            Node::Spanned { span, node } => {
+                let parent_span = self.span.replace(*span);
+                let ty = self.node(node);
+
+                self.span = parent_span;
+
+                ty
+            }
+
+            _ => unreachable!(),
+        }
+    }
+
+    fn binop(&mut self, op: BinOp, lhs: &Node<'s>, rhs: &Node<'s>) -> Option<Type> {
+        let lhs_ty = self.node(lhs);
+
+        // Both engines currently evaluate `rhs` unconditionally, but `&&` and
+        // `||` read as short-circuiting, so - to keep programs from depending
+        // on that - variables initialized only in `rhs` don't count as
+        // initialized afterwards
+        let rhs_ty = if matches!(op, BinOp::And | BinOp::Or) {
+            let init = self.init.clone();
+            let rhs_ty = self.node(rhs);
+
+            self.init = init;
+            rhs_ty
+        } else {
+            self.node(rhs)
+        };
+        let (lhs_ty, rhs_ty) = (lhs_ty?, rhs_ty?);
+
+        let is_cmp = matches!(
+            op,
+            BinOp::Eq | BinOp::Neq | BinOp::Gt | BinOp::GtEq | BinOp::Lt | BinOp::LtEq
+        );
+
+        let is_arith = matches!(
+            op,
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod
+        );
+
+        match (&lhs_ty, &rhs_ty) {
+            (Type::Bool, Type::Bool)
+                if matches!(op, BinOp::Eq | BinOp::Neq | BinOp::And | BinOp::Or) =>
+            {
+                Some(Type::Bool)
+            }
+
+            (Type::Int, Type::Int) | (Type::Float, Type::Float) if is_cmp => Some(Type::Bool),
+            (Type::Int, Type::Int) | (Type::Float, Type::Float) if is_arith => Some(lhs_ty),
+
+            _ => self.fail(TypeErrorKind::UnknownOperation {
+                lhs: lhs_ty,
+                op: format!("{:?}", op),
+                rhs: rhs_ty,
+            }),
+        }
+    }
+
+    fn pattern(&mut self, pat: &Pattern<'s>, ty: Option<Type>) {
+        match pat {
+            Pattern::Var(name) => self.declare(name, ty),
+            Pattern::Ignore => (),
+
+            Pattern::Tuple(pats) => {
+                let tys = match ty {
+                    Some(Type::Tuple(tys)) if tys.len() == pats.len() => {
+                        tys.into_iter().map(Some).collect()
+                    }
+
+                    Some(Type::Tuple(tys)) => {
+                        self.fail::<()>(TypeErrorKind::PatternMismatch {
+                            tuple: tys,
+                            expected: pats.len(),
+                        });
+
+                        vec![None; pats.len()]
+                    }
+
+                    Some(ty) => {
+                        self.fail::<()>(TypeErrorKind::NotATuple { ty });
+                        vec![None; pats.len()]
+                    }
+
+                    None => vec![None; pats.len()],
+                };
+
+                for (pat, ty) in pats.iter().zip(tys) {
+                    self.pattern(pat, ty);
+                }
+            }
+        }
+    }
+
+    fn var(&mut self, name: Ident<'s>) -> Option<Type> {
+        match self.vars.get(name) {
+            Some(ty) => {
+                let ty = ty.clone();
+
+                if self.init.get(name) != Some(&true) {
+                    self.fail::<()>(TypeErrorKind::UninitializedVariable {
+                        name: name.to_string(),
+                    });
+                }
+
+                ty
+            }
+
+            None => self.fail(TypeErrorKind::UnknownVariable {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Returns type of a variable that's being assigned to.
+    ///
+    /// Contrary to reading, assigning doesn't require the variable to be
+    /// initialized - but it still has to be declared on every path leading
+    /// here.
+    fn assignee(&mut self, name: Ident<'s>) -> Option<Type> {
+        match self.vars.get(name) {
+            Some(ty) => {
+                let ty = ty.clone();
+
+                if !self.init.contains_key(name) {
+                    self.fail::<()>(TypeErrorKind::UninitializedVariable {
+                        name: name.to_string(),
+                    });
+                }
+
+                ty
+            }
+
+            None => self.fail(TypeErrorKind::UnknownVariable {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    fn declare(&mut self, name: Ident<'s>, ty: Option<Type>) {
+        self.init.insert(name, true);
+        self.pending.retain(|(pending, _)| *pending != name);
+
+        if self.vars.insert(name, ty).is_some() {
+            self.fail::<()>(TypeErrorKind::AlreadyDefined {
+                name: name.to_string(),
+            });
+        }
+    }
+
+    /// Records an error at the current node; returns `None`, so that it can
+    /// be used in place of an unknown type.
+    fn fail<T>(&mut self, kind: TypeErrorKind) -> Option<T> {
+        self.errors.push(TypeError {
+            kind,
+            span: self.span,
+        });
+
+        None
+    }
+}
@@ -0,0 +1,562 @@
+//! Traversals over the AST.
+
+use crate::ast::*;
+
+/// Read-only traversal over the AST.
+///
+/// This trait (as well as [`VisitorMut`] and [`Fold`]) has a method per AST
+/// type (`visit_node()`, `visit_type()`, ...) whose default implementation
+/// calls the corresponding walk function (`walk_node()`, `walk_node_mut()`,
+/// `walk_fold_node()`, ...), which in turn visits all of the children. Passes
+/// override only the methods they care about and call the walk functions for
+/// everything else:
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// /// Collects names of all the variables that are read
+/// #[derive(Default)]
+/// struct Reads<'s>(Vec<&'s str>);
+///
+/// impl<'s> Visitor<'s> for Reads<'s> {
+///     fn visit_node(&mut self, node: &Node<'s>) {
+///         if let Node::Var(name) = node {
+///             self.0.push(name);
+///         }
+///
+///         walk_node(self, node);
+///     }
+/// }
+///
+/// let prog = parse("fn(input: int) -> int { var x = input; x * x }").unwrap();
+/// let mut reads = Reads::default();
+///
+/// reads.visit_program(&prog);
+///
+/// assert_eq!(vec!["input", "x", "x"], reads.0);
+/// ```
+///
+/// Children are visited in the order they appear in the source code. For
+/// nodes, that's also the order they get evaluated in - which is what
+/// numbering nodes in pre-order (as [`TypedProgram::types()`] and runtime
+/// errors do) relies on. Types and patterns are not evaluated, so they just
+/// keep their place from the source code - e.g. `var (a, b) = value` visits
+/// the pattern before `value`, even though it's bound only after `value` has
+/// been evaluated.
+pub trait Visitor<'s> {
+    fn visit_program(&mut self, prog: &Program<'s>) {
+        walk_program(self, prog);
+    }
+
+    fn visit_node(&mut self, node: &Node<'s>) {
+        walk_node(self, node);
+    }
+
+    fn visit_pattern(&mut self, pat: &Pattern<'s>) {
+        walk_pattern(self, pat);
+    }
+
+    fn visit_type(&mut self, ty: &Type) {
+        walk_type(self, ty);
+    }
+
+    fn visit_value(&mut self, value: &Value<'s>) {
+        walk_value(self, value);
+    }
+}
+
+pub fn walk_program<'s, V>(v: &mut V, prog: &Program<'s>)
+where
+    V: Visitor<'s> + ?Sized,
+{
+    v.visit_type(&prog.input);
+    v.visit_type(&prog.output);
+    v.visit_node(&prog.body);
+}
+
+pub fn walk_node<'s, V>(v: &mut V, node: &Node<'s>)
+where
+    V: Visitor<'s> + ?Sized,
+{
+    match node {
+        Node::Const(value) => v.visit_value(value),
+        Node::Var(_) => (),
+        Node::ExtractTuple { expr, .. } => v.visit_node(expr),
+
+        Node::ExtractArray { expr, idx } => {
+            v.visit_node(expr);
+            v.visit_node(idx);
+        }
+
+        Node::Cast { expr, ty } => {
+            v.visit_node(expr);
+            v.visit_type(ty);
+        }
+
+        Node::Add { lhs, rhs }
+        | Node::Sub { lhs, rhs }
+        | Node::Mul { lhs, rhs }
+        | Node::Div { lhs, rhs }
+        | Node::Mod { lhs, rhs }
+        | Node::Eq { lhs, rhs }
+        | Node::Neq { lhs, rhs }
+        | Node::Gt { lhs, rhs }
+        | Node::GtEq { lhs, rhs }
+        | Node::Lt { lhs, rhs }
+        | Node::LtEq { lhs, rhs }
+        | Node::And { lhs, rhs }
+        | Node::Or { lhs, rhs } => {
+            v.visit_node(lhs);
+            v.visit_node(rhs);
+        }
+
+        Node::Declare { ty, value, .. } => {
+            if let Some(ty) = ty {
+                v.visit_type(ty);
+            }
+
+            v.visit_node(value);
+        }
+
+        Node::DeclareUninit { ty, .. } => {
+            if let Some(ty) = ty {
+                v.visit_type(ty);
+            }
+        }
+
+        Node::Assign { value, .. } => v.visit_node(value),
+
+        Node::Destructure { pat, value } => {
+            v.visit_pattern(pat);
+            v.visit_node(value);
+        }
+
+        Node::While { cond, body } => {
+            v.visit_node(cond);
+            v.visit_node(body);
+        }
+
+        Node::Try { body, handler, .. } => {
+            v.visit_node(body);
+            v.visit_node(handler);
+        }
+
+        Node::Print(nodes)
+        | Node::Println(nodes)
+        | Node::Format { args: nodes, .. }
+        | Node::Block(nodes) => {
+            for node in nodes {
+                v.visit_node(node);
+            }
+        }
+
+        Node::Spanned { node, .. } => v.visit_node(node),
+    }
+}
+
+pub fn walk_pattern<'s, V>(v: &mut V, pat: &Pattern<'s>)
+where
+    V: Visitor<'s> + ?Sized,
+{
+    if let Pattern::Tuple(pats) = pat {
+        for pat in pats {
+            v.visit_pattern(pat);
+        }
+    }
+}
+
+pub fn walk_type<'s, V>(v: &mut V, ty: &Type)
+where
+    V: Visitor<'s> + ?Sized,
+{
+    if let Type::Tuple(tys) = ty {
+        for ty in tys {
+            v.visit_type(ty);
+        }
+    }
+}
+
+pub fn walk_value<'s, V>(v: &mut V, value: &Value<'s>)
+where
+    V: Visitor<'s> + ?Sized,
+{
+    if let Value::Tuple(values) = value {
+        for value in values {
+            v.visit_value(value);
+        }
+    }
+}
+
+/// In-place traversal over the AST; see [`Visitor`].
+pub trait VisitorMut<'s> {
+    fn visit_program_mut(&mut self, prog: &mut Program<'s>) {
+        walk_program_mut(self, prog);
+    }
+
+    fn visit_node_mut(&mut self, node: &mut Node<'s>) {
+        walk_node_mut(self, node);
+    }
+
+    fn visit_pattern_mut(&mut self, pat: &mut Pattern<'s>) {
+        walk_pattern_mut(self, pat);
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        walk_type_mut(self, ty);
+    }
+
+    fn visit_value_mut(&mut self, value: &mut Value<'s>) {
+        walk_value_mut(self, value);
+    }
+}
+
+pub fn walk_program_mut<'s, V>(v: &mut V, prog: &mut Program<'s>)
+where
+    V: VisitorMut<'s> + ?Sized,
+{
+    v.visit_type_mut(&mut prog.input);
+    v.visit_type_mut(&mut prog.output);
+    v.visit_node_mut(&mut prog.body);
+}
+
+pub fn walk_node_mut<'s, V>(v: &mut V, node: &mut Node<'s>)
+where
+    V: VisitorMut<'s> + ?Sized,
+{
+    match node {
+        Node::Const(value) => v.visit_value_mut(value),
+        Node::Var(_) => (),
+        Node::ExtractTuple { expr, .. } => v.visit_node_mut(expr),
+
+        Node::ExtractArray { expr, idx } => {
+            v.visit_node_mut(expr);
+            v.visit_node_mut(idx);
+        }
+
+        Node::Cast { expr, ty } => {
+            v.visit_node_mut(expr);
+            v.visit_type_mut(ty);
+        }
+
+        Node::Add { lhs, rhs }
+        | Node::Sub { lhs, rhs }
+        | Node::Mul { lhs, rhs }
+        | Node::Div { lhs, rhs }
+        | Node::Mod { lhs, rhs }
+        | Node::Eq { lhs, rhs }
+        | Node::Neq { lhs, rhs }
+        | Node::Gt { lhs, rhs }
+        | Node::GtEq { lhs, rhs }
+        | Node::Lt { lhs, rhs }
+        | Node::LtEq { lhs, rhs }
+        | Node::And { lhs, rhs }
+        | Node::Or { lhs, rhs } => {
+            v.visit_node_mut(lhs);
+            v.visit_node_mut(rhs);
+        }
+
+        Node::Declare { ty, value, .. } => {
+            if let Some(ty) = ty {
+                v.visit_type_mut(ty);
+            }
+
+            v.visit_node_mut(value);
+        }
+
+        Node::DeclareUninit { ty, .. } => {
+            if let Some(ty) = ty {
+                v.visit_type_mut(ty);
+            }
+        }
+
+        Node::Assign { value, .. } => v.visit_node_mut(value),
+
+        Node::Destructure { pat, value } => {
+            v.visit_pattern_mut(pat);
+            v.visit_node_mut(value);
+        }
+
+        Node::While { cond, body } => {
+            v.visit_node_mut(cond);
+            v.visit_node_mut(body);
+        }
+
+        Node::Try { body, handler, .. } => {
+            v.visit_node_mut(body);
+            v.visit_node_mut(handler);
+        }
+
+        Node::Print(nodes)
+        | Node::Println(nodes)
+        | Node::Format { args: nodes, .. }
+        | Node::Block(nodes) => {
+            for node in nodes {
+                v.visit_node_mut(node);
+            }
+        }
+
+        Node::Spanned { node, .. } => v.visit_node_mut(node),
+    }
+}
+
+pub fn walk_pattern_mut<'s, V>(v: &mut V, pat: &mut Pattern<'s>)
+where
+    V: VisitorMut<'s> + ?Sized,
+{
+    if let Pattern::Tuple(pats) = pat {
+        for pat in pats {
+            v.visit_pattern_mut(pat);
+        }
+    }
+}
+
+pub fn walk_type_mut<'s, V>(v: &mut V, ty: &mut Type)
+where
+    V: VisitorMut<'s> + ?Sized,
+{
+    if let Type::Tuple(tys) = ty {
+        for ty in tys {
+            v.visit_type_mut(ty);
+        }
+    }
+}
+
+pub fn walk_value_mut<'s, V>(v: &mut V, value: &mut Value<'s>)
+where
+    V: VisitorMut<'s> + ?Sized,
+{
+    if let Value::Tuple(values) = value {
+        for value in values {
+            v.visit_value_mut(value);
+        }
+    }
+}
+
+/// Traversal that rebuilds the AST, possibly changing nodes' shapes; see
+/// [`Visitor`].
+pub trait Fold<'s> {
+    fn fold_program(&mut self, prog: Program<'s>) -> Program<'s> {
+        walk_fold_program(self, prog)
+    }
+
+    fn fold_node(&mut self, node: Node<'s>) -> Node<'s> {
+        walk_fold_node(self, node)
+    }
+
+    fn fold_pattern(&mut self, pat: Pattern<'s>) -> Pattern<'s> {
+        walk_fold_pattern(self, pat)
+    }
+
+    fn fold_type(&mut self, ty: Type) -> Type {
+        walk_fold_type(self, ty)
+    }
+
+    fn fold_value(&mut self, value: Value<'s>) -> Value<'s> {
+        walk_fold_value(self, value)
+    }
+}
+
+pub fn walk_fold_program<'s, F>(f: &mut F, prog: Program<'s>) -> Program<'s>
+where
+    F: Fold<'s> + ?Sized,
+{
+    Program {
+        input: f.fold_type(prog.input),
+        output: f.fold_type(prog.output),
+        body: f.fold_node(prog.body),
+    }
+}
+
+pub fn walk_fold_node<'s, F>(f: &mut F, node: Node<'s>) -> Node<'s>
+where
+    F: Fold<'s> + ?Sized,
+{
+    match node {
+        Node::Const(value) => Node::Const(f.fold_value(value)),
+        Node::Var(name) => Node::Var(name),
+
+        Node::ExtractTuple { expr, idx } => Node::ExtractTuple {
+            expr: fold_boxed(f, *expr),
+            idx,
+        },
+
+        Node::ExtractArray { expr, idx } => Node::ExtractArray {
+            expr: fold_boxed(f, *expr),
+            idx: fold_boxed(f, *idx),
+        },
+
+        Node::Cast { expr, ty } => Node::Cast {
+            expr: fold_boxed(f, *expr),
+            ty: f.fold_type(ty),
+        },
+
+        Node::Add { lhs, rhs } => Node::Add {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Sub { lhs, rhs } => Node::Sub {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Mul { lhs, rhs } => Node::Mul {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Div { lhs, rhs } => Node::Div {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Mod { lhs, rhs } => Node::Mod {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Eq { lhs, rhs } => Node::Eq {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Neq { lhs, rhs } => Node::Neq {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Gt { lhs, rhs } => Node::Gt {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::GtEq { lhs, rhs } => Node::GtEq {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Lt { lhs, rhs } => Node::Lt {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::LtEq { lhs, rhs } => Node::LtEq {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::And { lhs, rhs } => Node::And {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Or { lhs, rhs } => Node::Or {
+            lhs: fold_boxed(f, *lhs),
+            rhs: fold_boxed(f, *rhs),
+        },
+
+        Node::Declare { name, ty, value } => Node::Declare {
+            name,
+            ty: ty.map(|ty| f.fold_type(ty)),
+            value: fold_boxed(f, *value),
+        },
+
+        Node::DeclareUninit { name, ty } => Node::DeclareUninit {
+            name,
+            ty: ty.map(|ty| f.fold_type(ty)),
+        },
+
+        Node::Destructure { pat, value } => Node::Destructure {
+            pat: f.fold_pattern(pat),
+            value: fold_boxed(f, *value),
+        },
+
+        Node::Assign { name, value } => Node::Assign {
+            name,
+            value: fold_boxed(f, *value),
+        },
+
+        Node::While { cond, body } => Node::While {
+            cond: fold_boxed(f, *cond),
+            body: fold_boxed(f, *body),
+        },
+
+        Node::Try {
+            body,
+            name,
+            handler,
+        } => Node::Try {
+            body: fold_boxed(f, *body),
+            name,
+            handler: fold_boxed(f, *handler),
+        },
+
+        Node::Print(nodes) => Node::Print(fold_nodes(f, nodes)),
+        Node::Println(nodes) => Node::Println(fold_nodes(f, nodes)),
+
+        Node::Format { fmt, args } => Node::Format {
+            fmt,
+            args: fold_nodes(f, args),
+        },
+
+        Node::Block(nodes) => Node::Block(fold_nodes(f, nodes)),
+
+        Node::Spanned { span, node } => Node::Spanned {
+            span,
+            node: fold_boxed(f, *node),
+        },
+    }
+}
+
+fn fold_boxed<'s, F>(f: &mut F, node: Node<'s>) -> Box<Node<'s>>
+where
+    F: Fold<'s> + ?Sized,
+{
+    Box::new(f.fold_node(node))
+}
+
+fn fold_nodes<'s, F>(f: &mut F, nodes: Vec<Node<'s>>) -> Vec<Node<'s>>
+where
+    F: Fold<'s> + ?Sized,
+{
+    nodes.into_iter().map(|node| f.fold_node(node)).collect()
+}
+
+pub fn walk_fold_pattern<'s, F>(f: &mut F, pat: Pattern<'s>) -> Pattern<'s>
+where
+    F: Fold<'s> + ?Sized,
+{
+    match pat {
+        Pattern::Tuple(pats) => {
+            Pattern::Tuple(pats.into_iter().map(|pat| f.fold_pattern(pat)).collect())
+        }
+
+        pat => pat,
+    }
+}
+
+pub fn walk_fold_type<'s, F>(f: &mut F, ty: Type) -> Type
+where
+    F: Fold<'s> + ?Sized,
+{
+    match ty {
+        Type::Tuple(tys) => Type::Tuple(tys.into_iter().map(|ty| f.fold_type(ty)).collect()),
+        ty => ty,
+    }
+}
+
+pub fn walk_fold_value<'s, F>(f: &mut F, value: Value<'s>) -> Value<'s>
+where
+    F: Fold<'s> + ?Sized,
+{
+    match value {
+        Value::Tuple(values) => Value::Tuple(
+            values
+                .into_iter()
+                .map(|value| f.fold_value(value))
+                .collect(),
+        ),
+
+        value => value,
+    }
+}
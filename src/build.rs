@@ -0,0 +1,280 @@
+//! Helpers for building ASTs by hand.
+//!
+//! Nodes are built through [`Expr`], which overloads the arithmetic operators
+//! and provides methods for everything else; literals (anything that
+//! implements [`IntoValue`]) convert into constants automatically.
+//!
+//! # Example
+//!
+//! ```
+//! # use rast_jit_vm::prelude::*;
+//! use rast_jit_vm::build::*;
+//!
+//! let prog = Program {
+//!     input: Type::Int,
+//!     output: Type::Int,
+//!     body: block![
+//!         declare("n", var("input")),
+//!         declare("acc", 1),
+//!         while_(var("n").gt(0), block![
+//!             assign("acc", var("acc") * var("n")),
+//!             assign("n", var("n") - 1),
+//!         ]),
+//!         var("acc"),
+//!     ]
+//!     .into(),
+//! };
+//!
+//! assert_eq!(Ok(120), vm::eval(&prog, 5));
+//! ```
+
+use crate::ast::*;
+use std::borrow::Cow;
+use std::ops;
+
+pub use crate::block;
+
+/// Wrapper for [`Node`] that makes it possible to build nodes with
+/// operators, e.g. `var("x") + 1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr<'s>(pub Node<'s>);
+
+impl<'s> Expr<'s> {
+    /// `self == rhs`
+    ///
+    /// (named with an underscore so that it doesn't shadow
+    /// [`PartialEq::eq()`], which compares the expressions themselves)
+    pub fn eq_(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::Eq, rhs)
+    }
+
+    /// `self != rhs`
+    pub fn neq(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::Neq, rhs)
+    }
+
+    /// `self > rhs`
+    pub fn gt(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::Gt, rhs)
+    }
+
+    /// `self >= rhs`
+    pub fn gt_eq(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::GtEq, rhs)
+    }
+
+    /// `self < rhs`
+    pub fn lt(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::Lt, rhs)
+    }
+
+    /// `self <= rhs`
+    pub fn lt_eq(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::LtEq, rhs)
+    }
+
+    /// `self && rhs`
+    pub fn and(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::And, rhs)
+    }
+
+    /// `self || rhs`
+    pub fn or(self, rhs: impl Into<Self>) -> Self {
+        self.binary(BinOp::Or, rhs)
+    }
+
+    /// `self as ty`
+    pub fn cast(self, ty: Type) -> Self {
+        Self(Node::Cast {
+            expr: Box::new(self.0),
+            ty,
+        })
+    }
+
+    /// `self.idx`
+    pub fn extract(self, idx: usize) -> Self {
+        Self(Node::ExtractTuple {
+            expr: Box::new(self.0),
+            idx,
+        })
+    }
+
+    /// `self[idx]`
+    pub fn index(self, idx: impl Into<Self>) -> Self {
+        Self(Node::ExtractArray {
+            expr: Box::new(self.0),
+            idx: Box::new(idx.into().0),
+        })
+    }
+
+    fn binary(self, op: BinOp, rhs: impl Into<Self>) -> Self {
+        Self(op.build(self.0, rhs.into().0))
+    }
+}
+
+impl<'s> From<Node<'s>> for Expr<'s> {
+    fn from(node: Node<'s>) -> Self {
+        Self(node)
+    }
+}
+
+impl<'s, T> From<T> for Expr<'s>
+where
+    T: IntoValue<'s>,
+{
+    fn from(value: T) -> Self {
+        Self(Node::Const(value.into_value()))
+    }
+}
+
+impl<'s> From<Expr<'s>> for Node<'s> {
+    fn from(expr: Expr<'s>) -> Self {
+        expr.0
+    }
+}
+
+impl<'s, T> From<T> for Node<'s>
+where
+    T: IntoValue<'s>,
+{
+    fn from(value: T) -> Self {
+        Node::Const(value.into_value())
+    }
+}
+
+macro_rules! ops {
+    ([ $( $trait:ident::$fn:ident => $op:ident ),+ $(,)? ]) => {
+        $(
+            impl<'s, R> ops::$trait<R> for Expr<'s>
+            where
+                R: Into<Expr<'s>>,
+            {
+                type Output = Self;
+
+                fn $fn(self, rhs: R) -> Self {
+                    self.binary(BinOp::$op, rhs)
+                }
+            }
+        )+
+    };
+}
+
+ops!([
+    Add::add => Add,
+    Sub::sub => Sub,
+    Mul::mul => Mul,
+    Div::div => Div,
+    Rem::rem => Mod,
+]);
+
+/// `name`
+pub fn var(name: Ident<'_>) -> Expr<'_> {
+    Expr(Node::Var(name))
+}
+
+/// Constant, e.g. `lit(123)` or `lit((1, "two"))`.
+pub fn lit<'s>(value: impl IntoValue<'s>) -> Expr<'s> {
+    Expr::from(value)
+}
+
+/// `var name = value`
+pub fn declare<'s>(name: Ident<'s>, value: impl Into<Expr<'s>>) -> Expr<'s> {
+    Expr(Node::Declare {
+        name,
+        ty: None,
+        value: Box::new(value.into().0),
+    })
+}
+
+/// `var name: ty = value`
+pub fn declare_typed<'s>(name: Ident<'s>, ty: Type, value: impl Into<Expr<'s>>) -> Expr<'s> {
+    Expr(Node::Declare {
+        name,
+        ty: Some(ty),
+        value: Box::new(value.into().0),
+    })
+}
+
+/// `var name`
+pub fn declare_uninit(name: Ident<'_>) -> Expr<'_> {
+    Expr(Node::DeclareUninit { name, ty: None })
+}
+
+/// `var name: ty`
+pub fn declare_uninit_typed(name: Ident<'_>, ty: Type) -> Expr<'_> {
+    Expr(Node::DeclareUninit { name, ty: Some(ty) })
+}
+
+/// `var pat = value`
+pub fn destructure<'s>(pat: Pattern<'s>, value: impl Into<Expr<'s>>) -> Expr<'s> {
+    Expr(Node::Destructure {
+        pat,
+        value: Box::new(value.into().0),
+    })
+}
+
+/// `name = value`
+pub fn assign<'s>(name: Ident<'s>, value: impl Into<Expr<'s>>) -> Expr<'s> {
+    Expr(Node::Assign {
+        name,
+        value: Box::new(value.into().0),
+    })
+}
+
+/// `while cond { body }`
+pub fn while_<'s>(cond: impl Into<Expr<'s>>, body: impl Into<Expr<'s>>) -> Expr<'s> {
+    Expr(Node::While {
+        cond: Box::new(cond.into().0),
+        body: Box::new(body.into().0),
+    })
+}
+
+/// `try { body } catch name { handler }`
+pub fn try_<'s>(
+    body: impl Into<Expr<'s>>,
+    name: Ident<'s>,
+    handler: impl Into<Expr<'s>>,
+) -> Expr<'s> {
+    Expr(Node::Try {
+        body: Box::new(body.into().0),
+        name,
+        handler: Box::new(handler.into().0),
+    })
+}
+
+/// `print(args...)`
+pub fn print<'s>(args: impl IntoIterator<Item = impl Into<Expr<'s>>>) -> Expr<'s> {
+    Expr(Node::Print(nodes(args)))
+}
+
+/// `println(args...)`
+pub fn println<'s>(args: impl IntoIterator<Item = impl Into<Expr<'s>>>) -> Expr<'s> {
+    Expr(Node::Println(nodes(args)))
+}
+
+/// `format(fmt, args...)`
+pub fn format<'s>(
+    fmt: impl Into<Cow<'s, str>>,
+    args: impl IntoIterator<Item = impl Into<Expr<'s>>>,
+) -> Expr<'s> {
+    Expr(Node::Format {
+        fmt: fmt.into(),
+        args: nodes(args),
+    })
+}
+
+fn nodes<'s>(nodes: impl IntoIterator<Item = impl Into<Expr<'s>>>) -> Vec<Node<'s>> {
+    nodes.into_iter().map(|node| node.into().0).collect()
+}
+
+/// `{ node node ... }`, e.g. `block![declare("x", 1), var("x") + 2]`.
+///
+/// Accepts anything that converts into [`Expr`](crate::build::Expr).
+#[macro_export]
+macro_rules! block {
+    ($( $node:expr ),* $(,)?) => {
+        $crate::build::Expr($crate::ast::Node::Block(vec![
+            $( $crate::ast::Node::from($crate::build::Expr::from($node)) ),*
+        ]))
+    };
+}
@@ -28,21 +28,25 @@ pub fn program() -> Program<'static> {
             // var x = 0
             Node::Declare {
                 name: "x",
+                ty: None,
                 value: Box::new(Node::Const(Value::Int(0))),
             },
             // var y = 1
             Node::Declare {
                 name: "y",
+                ty: None,
                 value: Box::new(Node::Const(Value::Int(1))),
             },
             // var z = 1
             Node::Declare {
                 name: "z",
+                ty: None,
                 value: Box::new(Node::Const(Value::Int(1))),
             },
             // var n = input
             Node::Declare {
                 name: "n",
+                ty: None,
                 value: Box::new(Node::Var("input")),
             },
             // while n > 0
@@ -92,13 +96,13 @@ mod tests {
 
     fn test_compile(input: i64, expected: i64) {
         let fun = vm::compile(program());
-        let actual = fun(input);
+        let actual = fun(input).unwrap();
 
         assert_eq!(expected, actual);
     }
 
     fn test_eval(input: i64, expected: i64) {
-        let actual = vm::eval(&program(), input);
+        let actual = vm::eval(&program(), input).unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -56,65 +56,23 @@ pub fn program() -> Program<'static> {
         ]),
         output: Type::Unit,
         body: Node::Block(vec![
-            // var viewport_width = input.0
-            Node::Declare {
-                name: "viewport_width",
-                value: Box::new(Node::ExtractTuple {
-                    expr: Box::new(Node::Var("input")),
-                    idx: 0,
-                }),
-            },
-            // var viewport_height = input.1
-            Node::Declare {
-                name: "viewport_height",
-                value: Box::new(Node::ExtractTuple {
-                    expr: Box::new(Node::Var("input")),
-                    idx: 1,
-                }),
-            },
-            // var x1 = input.2
-            Node::Declare {
-                name: "x1",
-                value: Box::new(Node::ExtractTuple {
-                    expr: Box::new(Node::Var("input")),
-                    idx: 2,
-                }),
-            },
-            // var y1 = input.3
-            Node::Declare {
-                name: "y1",
-                value: Box::new(Node::ExtractTuple {
-                    expr: Box::new(Node::Var("input")),
-                    idx: 3,
-                }),
-            },
-            // var x2 = input.4
-            Node::Declare {
-                name: "x2",
-                value: Box::new(Node::ExtractTuple {
-                    expr: Box::new(Node::Var("input")),
-                    idx: 4,
-                }),
-            },
-            // var y2 = input.5
-            Node::Declare {
-                name: "y2",
-                value: Box::new(Node::ExtractTuple {
-                    expr: Box::new(Node::Var("input")),
-                    idx: 5,
-                }),
-            },
-            // var max_iterations = input.6
-            Node::Declare {
-                name: "max_iterations",
-                value: Box::new(Node::ExtractTuple {
-                    expr: Box::new(Node::Var("input")),
-                    idx: 6,
-                }),
+            // var (viewport_width, viewport_height, x1, y1, x2, y2, max_iterations) = input
+            Node::Destructure {
+                pat: Pattern::Tuple(vec![
+                    Pattern::Var("viewport_width"),
+                    Pattern::Var("viewport_height"),
+                    Pattern::Var("x1"),
+                    Pattern::Var("y1"),
+                    Pattern::Var("x2"),
+                    Pattern::Var("y2"),
+                    Pattern::Var("max_iterations"),
+                ]),
+                value: Box::new(Node::Var("input")),
             },
             // var viewport_y = 0
             Node::Declare {
                 name: "viewport_y",
+                ty: None,
                 value: Box::new(Node::Const(Value::Int(0))),
             },
             // while viewport_y < viewport_height
@@ -127,6 +85,7 @@ pub fn program() -> Program<'static> {
                     // var y0 = y1 + (y2 - y1) * ((viewport_y as float) / (viewport_height as float))
                     Node::Declare {
                         name: "y0",
+                        ty: None,
                         value: Box::new(Node::Add {
                             lhs: Box::new(Node::Var("y1")),
                             rhs: Box::new(Node::Mul {
@@ -150,6 +109,7 @@ pub fn program() -> Program<'static> {
                     // var viewport_x = 0
                     Node::Declare {
                         name: "viewport_x",
+                        ty: None,
                         value: Box::new(Node::Const(Value::Int(0))),
                     },
                     // while viewport_x < viewport_width
@@ -162,6 +122,7 @@ pub fn program() -> Program<'static> {
                             // var x0 = x1 + (x2 - x1) * ((viewport_x as float) / (viewport_width as float))
                             Node::Declare {
                                 name: "x0",
+                                ty: None,
                                 value: Box::new(Node::Add {
                                     lhs: Box::new(Node::Var("x1")),
                                     rhs: Box::new(Node::Mul {
@@ -185,16 +146,19 @@ pub fn program() -> Program<'static> {
                             // var x = 0.0
                             Node::Declare {
                                 name: "x",
+                                ty: None,
                                 value: Box::new(Node::Const(Value::Float(0.0))),
                             },
                             // var y = 0.0
                             Node::Declare {
                                 name: "y",
+                                ty: None,
                                 value: Box::new(Node::Const(Value::Float(0.0))),
                             },
                             // var iterations = max_iterations
                             Node::Declare {
                                 name: "iterations",
+                                ty: None,
                                 value: Box::new(Node::Var("max_iterations")),
                             },
                             // while (x*x + y*y <= 4.0) && (iterations > 0)
@@ -222,6 +186,7 @@ pub fn program() -> Program<'static> {
                                     // var xtemp = x*x - y*y + x0
                                     Node::Declare {
                                         name: "xtemp",
+                                        ty: None,
                                         value: Box::new(Node::Add {
                                             lhs: Box::new(Node::Sub {
                                                 lhs: Box::new(Node::Mul {
@@ -267,7 +232,7 @@ pub fn program() -> Program<'static> {
                             },
                             // print("#%=-:,. "[(8.0 * (iterations as float) / (max_iterations as float)) as int])
                             Node::Print(vec![Node::ExtractArray {
-                                expr: Box::new(Node::Const(Value::Str("#%=-:,. "))),
+                                expr: Box::new(Node::Const(Value::Str("#%=-:,. ".into()))),
                                 idx: Box::new(Node::Cast {
                                     expr: Box::new(Node::Mul {
                                         lhs: Box::new(Node::Const(Value::Float(8.0))),
@@ -295,8 +260,8 @@ pub fn program() -> Program<'static> {
                             },
                         ])),
                     },
-                    // print("\n")
-                    Node::Print(vec![Node::Const(Value::Str("\n"))]),
+                    // println()
+                    Node::Println(vec![]),
                     // viewport_y = viewport_y + 1
                     Node::Assign {
                         name: "viewport_y",
@@ -390,14 +355,14 @@ mod tests {
     #[test]
     fn test_compile() {
         let fun = vm::compile::<_, ()>(program());
-        let actual = run(|| fun(ARGS));
+        let actual = run(|| fun(ARGS).unwrap());
 
         assert(&actual);
     }
 
     #[test]
     fn test_eval() {
-        let actual = run(|| vm::eval::<_, ()>(&program(), ARGS));
+        let actual = run(|| vm::eval::<_, ()>(&program(), ARGS).unwrap());
 
         assert(&actual);
     }
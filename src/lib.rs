@@ -1,9 +1,12 @@
 #![feature(internal_output_capture)]
 
 pub mod ast;
+pub mod build;
 pub mod examples;
 pub mod vm;
 
+pub use rast_jit_vm_macros::rast;
+
 pub mod prelude {
-    pub use crate::{ast::*, vm};
+    pub use crate::{ast::*, rast, vm};
 }
@@ -1,4 +1,7 @@
 mod compile;
+mod compile_error;
+mod dot;
 mod eval;
+mod runtime_error;
 
-pub use self::{compile::*, eval::*};
+pub use self::{compile::*, compile_error::*, dot::*, eval::*, runtime_error::*};
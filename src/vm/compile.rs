@@ -1,9 +1,23 @@
+use super::{CompileError, RuntimeError, RuntimeErrorKind};
 use crate::ast;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
 
-/// Type-checks given `prog` and compiles it into a Rust function.
+/// Type-checks given `prog` (see [`ast::typecheck()`]) and compiles it into
+/// a Rust function.
 ///
-/// See also: [`crate::vm::eval()`].
+/// Type errors are reported as panics; for parsed programs, their messages
+/// start with the location of the offending node (e.g. `3:5: Unknown
+/// variable: x`). If there's more than one type error, all of them get
+/// reported, one per line.
+///
+/// Runtime errors that aren't caught by the program itself are returned from
+/// the compiled function (see [`RuntimeError`]).
+///
+/// See also: [`try_compile()`], [`crate::vm::eval()`].
 ///
 /// # Example
 ///
@@ -12,53 +26,195 @@ use std::collections::BTreeMap;
 /// #
 /// let fib = vm::compile(examples::fibonacci());
 ///
-/// assert_eq!(55, fib(10));
+/// assert_eq!(Ok(55), fib(10));
 /// ```
-pub fn compile<'s, Input, Output>(prog: ast::Program<'s>) -> impl Fn(Input) -> Output + 's
+pub fn compile<'s, Input, Output>(
+    prog: ast::Program<'s>,
+) -> impl Fn(Input) -> Result<Output, RuntimeError> + 's
+where
+    Input: ast::IntoValue<'s>,
+    Output: ast::FromValue<'s>,
+{
+    // Destructured, so that the closure doesn't capture `PhantomData` (which
+    // would require `Input` and `Output` to outlive `'s`)
+    let CompiledProgram {
+        thunk,
+        stack,
+        input,
+        ..
+    } = try_compile::<Input, Output>(prog).unwrap_or_else(|err| panic!("{}", err));
+
+    move |arg| Ok(run(&thunk, &stack, &input, arg.into_value())?.unbox())
+}
+
+/// Same as [`compile()`], but returns an error instead of panicking when the
+/// program is ill-typed or when `Input` / `Output` don't match its signature.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = parse("fn(input: int) -> int { input * y }").unwrap();
+///
+/// match vm::try_compile::<i64, i64>(prog) {
+///     Err(vm::CompileError::Type(errs)) => {
+///         assert_eq!("1:33: Unknown variable: y", errs[0].to_string());
+///
+///         assert_eq!(
+///             TypeErrorKind::UnknownVariable { name: "y".into() },
+///             errs[0].kind
+///         );
+///     }
+///
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn try_compile<'s, Input, Output>(
+    prog: ast::Program<'s>,
+) -> Result<CompiledProgram<'s, Input, Output>, CompileError>
+where
+    Input: ast::IntoValue<'s>,
+    Output: ast::FromValue<'s>,
+{
+    check_signature::<Input, Output>(&prog)?;
+
+    let types = ast::typecheck(&prog)
+        .map_err(CompileError::Type)?
+        .into_types();
+
+    let mut ctxt = CompilationContext::new(types);
+    let input = ast::Pattern::Var("input").compile(&mut ctxt, &prog.input);
+    let (_, thunk) = prog.body.compile(&mut ctxt);
+
+    Ok(CompiledProgram {
+        thunk: thunk.boxed(),
+        stack: ctxt.stack,
+        input,
+        _signature: PhantomData,
+    })
+}
+
+/// Program compiled by [`try_compile()`].
+pub struct CompiledProgram<'s, Input, Output> {
+    thunk: TypedThunk<'s, ast::Value<'s>>,
+
+    /// Stack with all the variables allocated, cloned for each run
+    stack: Stack<'s>,
+
+    /// Binds program's input to its variable
+    input: Binding,
+
+    _signature: PhantomData<fn(Input) -> Output>,
+}
+
+impl<'s, Input, Output> CompiledProgram<'s, Input, Output>
 where
     Input: ast::IntoValue<'s>,
     Output: ast::FromValue<'s>,
 {
-    if Input::ty().map_or(false, |ty| ty != prog.input) {
-        panic!(
-            "Invalid invocation: Generic parameter `Input` must be `{:?}`",
-            prog.input,
-        );
+    /// Runs the program, returning the runtime error that stopped it (if
+    /// any).
+    pub fn run(&self, input: Input) -> Result<Output, RuntimeError> {
+        Ok(run(&self.thunk, &self.stack, &self.input, input.into_value())?.unbox())
     }
+}
 
-    if Output::ty().map_or(false, |ty| ty != prog.output) {
-        panic!(
-            "Invalid invocation: Generic parameter `Output` must be `{:?}`",
-            prog.output
-        );
+fn run<'s>(
+    thunk: &TypedThunk<'s, ast::Value<'s>>,
+    stack: &Stack<'s>,
+    binding: &Binding,
+    input: ast::Value<'s>,
+) -> ThunkResult<ast::Value<'s>> {
+    let mut ctxt = RuntimeContext {
+        stack: stack.clone(),
+    };
+
+    binding.bind(&mut ctxt, input);
+
+    (thunk)(&mut ctxt)
+}
+
+impl<Input, Output> fmt::Debug for CompiledProgram<'_, Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompiledProgram")
+            .field("stack_size", &self.stack.len())
+            .finish_non_exhaustive()
     }
+}
 
-    let mut ctxt = CompilationContext::new(prog.input);
-    let (ty, thunk) = prog.body.compile(&mut ctxt);
+/// Checks whether `Input` and `Output` match program's signature.
+pub(crate) fn check_signature<'s, Input, Output>(
+    prog: &ast::Program<'s>,
+) -> Result<(), CompileError>
+where
+    Input: ast::IntoValue<'s>,
+    Output: ast::FromValue<'s>,
+{
+    if let Some(ty) = Input::ty().filter(|ty| *ty != prog.input) {
+        return Err(CompileError::InvalidInput {
+            expected: prog.input.clone(),
+            actual: ty,
+        });
+    }
 
-    if ty != prog.output {
-        panic!(
-            "Output type mismatch: Program declared to return `{:?}`, but in \
-             reality it returns `{:?}`",
-            prog.output, ty
-        );
+    if let Some(ty) = Output::ty().filter(|ty| *ty != prog.output) {
+        return Err(CompileError::InvalidOutput {
+            expected: prog.output.clone(),
+            actual: ty,
+        });
     }
 
-    let stack_size = ctxt.stack.len();
+    Ok(())
+}
 
-    move |arg| {
-        let mut ctxt = RuntimeContext::new(stack_size, arg.into_value());
+/// Type-checks given `prog`, panicking with all the errors (one per line) if
+/// it's ill-typed.
+pub(crate) fn typecheck<'a, 's>(prog: &'a ast::Program<'s>) -> ast::TypedProgram<'a, 's> {
+    ast::typecheck(prog).unwrap_or_else(|errs| panic!("{}", CompileError::Type(errs)))
+}
 
-        (thunk)(&mut ctxt).unbox()
-    }
+/// Matches on thunk's variant, binding its closure to `$inner` and evaluating
+/// `$body` - for code that works the same way for thunks of all types.
+macro_rules! match_thunk {
+    ($thunk:expr, |$inner:ident| $body:expr) => {
+        match $thunk {
+            Thunk::Unit($inner) => $body,
+            Thunk::Bool($inner) => $body,
+            Thunk::Char($inner) => $body,
+            Thunk::Int($inner) => $body,
+            Thunk::Float($inner) => $body,
+            Thunk::Str($inner) => $body,
+            Thunk::Tuple($inner) => $body,
+        }
+    };
 }
 
 impl<'s> ast::Node<'s> {
+    /// Compiles the node, returning its type (as inferred by the type checker)
+    /// along with the thunk.
     fn compile(self, ctxt: &mut CompilationContext<'s>) -> (ast::Type, Thunk<'s>) {
+        // Types are recorded in pre-order, so the node's type comes before
+        // its children's
+        let ty = ctxt.types.next().expect("missing type for node");
+        let parent_node = std::mem::replace(&mut ctxt.node, ctxt.nodes);
+
+        ctxt.nodes += 1;
+
+        let thunk = self.compile_inner(ctxt, &ty);
+
+        ctxt.node = parent_node;
+
+        (ty, thunk)
+    }
+
+    fn compile_inner(self, ctxt: &mut CompilationContext<'s>, ty: &ast::Type) -> Thunk<'s> {
         match self {
             ast::Node::Const(value) => Self::compile_const(value),
-            ast::Node::Var(name) => Self::compile_var(ctxt, name),
-            ast::Node::ExtractTuple { expr, idx } => Self::compile_extract_tuple(ctxt, *expr, idx),
+            ast::Node::Var(name) => Self::compile_var(ctxt, name, ty),
+            ast::Node::ExtractTuple { expr, idx } => {
+                Self::compile_extract_tuple(ctxt, *expr, idx, ty)
+            }
             ast::Node::ExtractArray { expr, idx } => Self::compile_extract_array(ctxt, *expr, *idx),
             ast::Node::Cast { expr, ty } => Self::compile_cast(ctxt, *expr, ty),
 
@@ -76,121 +232,108 @@ impl<'s> ast::Node<'s> {
             | ast::Node::And { .. }
             | ast::Node::Or { .. }) => this.compile_binop(ctxt),
 
-            ast::Node::Declare { name, value } => Self::compile_declare(ctxt, name, *value),
+            ast::Node::Declare { name, ty, value } => Self::compile_declare(ctxt, name, ty, *value),
+            ast::Node::DeclareUninit { .. } => Self::compile_declare_uninit(),
+            ast::Node::Destructure { pat, value } => Self::compile_destructure(ctxt, pat, *value),
             ast::Node::Assign { name, value } => Self::compile_assign(ctxt, name, *value),
             ast::Node::While { cond, body } => Self::compile_while(ctxt, *cond, *body),
-            ast::Node::Print(nodes) => Self::compile_print(ctxt, nodes),
+            ast::Node::Try {
+                body,
+                name,
+                handler,
+            } => Self::compile_try(ctxt, *body, name, *handler),
+            ast::Node::Print(nodes) => Self::compile_print(ctxt, nodes, false),
+            ast::Node::Println(nodes) => Self::compile_print(ctxt, nodes, true),
+            ast::Node::Format { fmt, args } => Self::compile_format(ctxt, &fmt, args),
             ast::Node::Block(nodes) => Self::compile_block(ctxt, nodes),
+            ast::Node::Spanned { span, node } => Self::compile_spanned(ctxt, span, *node),
         }
     }
 
-    fn compile_const(value: ast::Value<'s>) -> (ast::Type, Thunk<'s>) {
-        let ty = value.ty();
-        let thunk = thunk(move |_| value.clone());
+    fn compile_const(value: ast::Value<'s>) -> Thunk<'s> {
+        match value {
+            ast::Value::Unit => Thunk::Unit(thunk(|_| Ok(()))),
+            ast::Value::Bool(value) => Thunk::Bool(thunk(move |_| Ok(value))),
+            ast::Value::Char(value) => Thunk::Char(thunk(move |_| Ok(value))),
+            ast::Value::Int(value) => Thunk::Int(thunk(move |_| Ok(value))),
+            ast::Value::Float(value) => Thunk::Float(thunk(move |_| Ok(value))),
+            ast::Value::Str(value) => {
+                let value = Rc::new(value);
+
+                Thunk::Str(thunk(move |_| Ok(value.clone())))
+            }
 
-        (ty, thunk)
+            ast::Value::Tuple(values) => {
+                let values = Rc::new(values);
+
+                Thunk::Tuple(thunk(move |_| Ok(values.clone())))
+            }
+        }
     }
 
     fn compile_var(
         ctxt: &mut CompilationContext<'s>,
         name: ast::Ident<'s>,
-    ) -> (ast::Type, Thunk<'s>) {
-        let id = ctxt
-            .vars
-            .get(name)
-            .cloned()
-            .unwrap_or_else(|| panic!("Unknown variable: {}", name));
-
-        let ty = ctxt.stack[id].clone();
-        let thunk = thunk(move |ctxt| ctxt.stack[id].clone());
-
-        (ty, thunk)
+        ty: &ast::Type,
+    ) -> Thunk<'s> {
+        Thunk::loaded(ty, ctxt.vars[name])
     }
 
     fn compile_extract_tuple(
         ctxt: &mut CompilationContext<'s>,
         expr: Self,
         idx: usize,
-    ) -> (ast::Type, Thunk<'s>) {
-        let (ty, expr) = expr.compile(ctxt);
-
-        let tys = match ty {
-            ast::Type::Tuple(tys) => tys,
-            ty => panic!("Invalid type: `{:?}` is not a tuple", ty),
-        };
-
-        let ty = tys.get(idx).cloned().unwrap_or_else(|| {
-            panic!(
-                "Invalid subscript: Tuple `{:?}` doesn't have element .{}",
-                tys, idx
-            )
-        });
+        ty: &ast::Type,
+    ) -> Thunk<'s> {
+        let (_, expr) = expr.compile(ctxt);
+        let expr = Rc::<Vec<_>>::from_thunk(expr);
 
-        let thunk = thunk(move |ctxt| (expr)(ctxt).unbox_tuple()[idx].clone());
-
-        (ty, thunk)
+        Thunk::unboxed(ty, move |ctxt| Ok((expr)(ctxt)?[idx].clone()))
     }
 
     fn compile_extract_array(
         ctxt: &mut CompilationContext<'s>,
         expr: Self,
         idx: Self,
-    ) -> (ast::Type, Thunk<'s>) {
-        let (expr_ty, expr) = expr.compile(ctxt);
-        let (idx_ty, idx) = idx.compile(ctxt);
-
-        match expr_ty {
-            ast::Type::Str => (),
-            ty => panic!("Invalid type: Expected `str`, got `{:?}`", ty),
-        }
-
-        match idx_ty {
-            ast::Type::Int => (),
-            ty => panic!("Invalid type: Expected `int`, got `{:?}`", ty),
-        }
-
-        let thunk = thunk(move |ctxt| {
-            let expr = (expr)(ctxt).unbox::<&str>();
-            let idx = (idx)(ctxt).unbox::<i64>() as _;
-            let char = expr.chars().nth(idx).expect("Index out of bounds");
-
-            ast::Value::Char(char)
-        });
-
-        (ast::Type::Char, thunk)
+    ) -> Thunk<'s> {
+        let (_, expr) = expr.compile(ctxt);
+        let (_, idx) = idx.compile(ctxt);
+        let (expr, idx) = (Rc::<Cow<str>>::from_thunk(expr), i64::from_thunk(idx));
+        let (node, span) = (ctxt.node, ctxt.span);
+
+        Thunk::Char(thunk(move |ctxt| {
+            let expr = (expr)(ctxt)?;
+            let idx = (idx)(ctxt)?;
+
+            usize::try_from(idx)
+                .ok()
+                .and_then(|idx| expr.chars().nth(idx))
+                .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::IndexOutOfBounds, node, span))
+        }))
     }
 
     fn compile_cast(
         ctxt: &mut CompilationContext<'s>,
         expr: Self,
         target_ty: ast::Type,
-    ) -> (ast::Type, Thunk<'s>) {
-        enum Cast {
-            IntToFloat,
-            FloatToInt,
-        }
+    ) -> Thunk<'s> {
+        let (_, expr) = expr.compile(ctxt);
 
-        let (source_ty, expr) = expr.compile(ctxt);
-
-        let cast = match (source_ty, &target_ty) {
-            (ast::Type::Int, ast::Type::Float) => Cast::IntToFloat,
-            (ast::Type::Float, ast::Type::Int) => Cast::FloatToInt,
-            (source_ty, target_ty) => panic!("Cannot cast `{:?}` to `{:?}`", source_ty, target_ty),
-        };
-
-        let thunk = thunk(move |ctxt| {
-            let expr = (expr)(ctxt);
+        match (expr, target_ty) {
+            (Thunk::Int(expr), ast::Type::Float) => {
+                Thunk::Float(thunk(move |ctxt| Ok((expr)(ctxt)? as _)))
+            }
 
-            match cast {
-                Cast::IntToFloat => ast::Value::Float(expr.unbox::<i64>() as _),
-                Cast::FloatToInt => ast::Value::Int(expr.unbox::<f32>() as _),
+            (Thunk::Float(expr), ast::Type::Int) => {
+                Thunk::Int(thunk(move |ctxt| Ok((expr)(ctxt)? as _)))
             }
-        });
 
-        (target_ty, thunk)
+            // Ruled out by the type checker
+            _ => unreachable!(),
+        }
     }
 
-    fn compile_binop(self, ctxt: &mut CompilationContext<'s>) -> (ast::Type, Thunk<'s>) {
+    fn compile_binop(self, ctxt: &mut CompilationContext<'s>) -> Thunk<'s> {
         #[derive(Debug)]
         enum Op {
             Add,
@@ -230,286 +373,817 @@ impl<'s> ast::Node<'s> {
             _ => unreachable!(),
         };
 
-        let (lhs_ty, lhs) = lhs.compile(ctxt);
-        let (rhs_ty, rhs) = rhs.compile(ctxt);
-
-        #[allow(clippy::single_match)]
-        match (&lhs_ty, &op, &rhs_ty) {
-            (ast::Type::Bool, Op::Eq, ast::Type::Bool) => {
-                let thunk = thunk(move |ctxt| {
-                    let lhs = lhs(ctxt).unbox::<bool>();
-                    let rhs = rhs(ctxt).unbox::<bool>();
-
-                    ast::Value::Bool(lhs == rhs)
-                });
-
-                return (ast::Type::Bool, thunk);
-            }
-
-            _ => {
-                // Because there's so many types (all three of them!) and so
-                // many operations, the rest of those have been implemented
-                // through the macro below.
-                //
-                // The code above serves as an example of what the macro
-                // outputs.
-            }
-        }
-
+        // `rhs` can refer to variables declared within `lhs`, so its shape
+        // is known only after `lhs` has been compiled
+        let lhs_shape = Shape::of(&lhs, ctxt);
+        let (_, lhs) = lhs.compile(ctxt);
+        let rhs_shape = Shape::of(&rhs, ctxt);
+        let (_, rhs) = rhs.compile(ctxt);
+        let (node, span) = (ctxt.node, ctxt.span);
+
+        // Because there's so many types (all three of them!) and so many
+        // operations, most of those have been implemented through this macro;
+        // the first few arms of the `match` below serve as an example of what
+        // the macro outputs - except that the macro also fuses operands that
+        // are variables or constants into the operation (see [`fused()`]).
+        //
+        // Since thunks return unboxed values, picking the right arm is all
+        // the type-checking there is - at runtime, operands are just `i64`s,
+        // `f32`s and `bool`s.
         macro_rules! ops {
-            ($lhs:expr, $rhs:expr, $lhs_ty:expr, $rhs_ty:expr, $op:expr, {
-                $( $match_lhs_ty:tt $match_op:tt $match_rhs_ty:tt = $out_ty:tt: $eval:expr; )*
+            ($lhs:expr, $op:expr, $rhs:expr, $lhs_shape:expr, $rhs_shape:expr, {
+                $( $match_lhs:ident $match_op:ident $match_rhs:ident = $out:ident: $eval:expr; )*
             }) => {
-                match ($lhs_ty, $op, $rhs_ty) {
+                match ($lhs, $op, $rhs) {
                     $(
-                        (ops!(@ty $match_lhs_ty), ops!(@op $match_op), ops!(@ty $match_rhs_ty)) => {
-                            let ty = ops!(@ty $out_ty);
-
-                            let thunk = thunk(move |ctxt| {
-                                let lhs = ops!(@unbox $lhs, ctxt, $match_lhs_ty);
-                                let rhs = ops!(@unbox $rhs, ctxt, $match_rhs_ty);
-                                let out = $eval(lhs, rhs);
+                        (Thunk::$match_lhs(lhs), Op::$match_op, Thunk::$match_rhs(rhs)) => {
+                            let lhs = Operand::new($lhs_shape, lhs);
+                            let rhs = Operand::new($rhs_shape, rhs);
 
-                                ops!(@box out, $out_ty)
-                            });
-
-                            (ty, thunk)
+                            Thunk::$out(fused(lhs, rhs, $eval))
                         }
                     )*
 
-                    (lhs_ty, op, rhs_ty) => {
-                        panic!("Unknown operation `{:?}` `{:?}` `{:?}`", lhs_ty, op, rhs_ty);
-                    }
+                    // Ruled out by the type checker
+                    (_, op, _) => unreachable!("{:?}", op),
                 }
             };
-
-            (@ty Bool) => { ast::Type::Bool };
-            (@ty Int) => { ast::Type::Int };
-            (@ty Float) => { ast::Type::Float };
-
-            (@op Add) => { Op::Add };
-            (@op Sub) => { Op::Sub };
-            (@op Mul) => { Op::Mul };
-            (@op Div) => { Op::Div };
-            (@op Mod) => { Op::Mod };
-
-            (@op Eq) => { Op::Eq };
-            (@op Neq) => { Op::Neq };
-            (@op Gt) => { Op::Gt };
-            (@op GtEq) => { Op::GtEq };
-            (@op Lt) => { Op::Lt };
-            (@op LtEq) => { Op::LtEq };
-
-            (@op And) => { Op::And };
-            (@op Or) => { Op::Or };
-
-            (@unbox $expr:expr, $ctxt:expr, Bool) => { ($expr)($ctxt).unbox::<bool>() };
-            (@unbox $expr:expr, $ctxt:expr, Int) => { ($expr)($ctxt).unbox::<i64>() };
-            (@unbox $expr:expr, $ctxt:expr, Float) => { ($expr)($ctxt).unbox::<f32>() };
-
-            (@box $expr:expr, Bool) => { ast::Value::Bool($expr) };
-            (@box $expr:expr, Int) => { ast::Value::Int($expr) };
-            (@box $expr:expr, Float) => { ast::Value::Float($expr) };
         }
 
-        ops!(lhs, rhs, lhs_ty, rhs_ty, op, {
-            // Bool Eq Bool = Bool: |lhs, rhs| lhs == rhs; // Already implemented above
-            Bool Neq Bool = Bool: |lhs, rhs| lhs != rhs;
-            Bool And Bool = Bool: |lhs, rhs| lhs && rhs;
-            Bool Or Bool = Bool: |lhs, rhs| lhs || rhs;
-
-            Int Add Int = Int: |lhs, rhs| lhs + rhs;
-            Int Sub Int = Int: |lhs, rhs| lhs - rhs;
-            Int Mul Int = Int: |lhs, rhs| lhs * rhs;
-            Int Div Int = Int: |lhs, rhs| lhs / rhs;
-            Int Mod Int = Int: |lhs, rhs| lhs % rhs;
-
-            Int Eq Int = Bool: |lhs, rhs| lhs == rhs;
-            Int Neq Int = Bool: |lhs, rhs| lhs != rhs;
-            Int Gt Int = Bool: |lhs, rhs| lhs > rhs;
-            Int GtEq Int = Bool: |lhs, rhs| lhs >= rhs;
-            Int Lt Int = Bool: |lhs, rhs| lhs < rhs;
-            Int LtEq Int = Bool: |lhs, rhs| lhs <= rhs;
-
-            Float Add Float = Float: |lhs, rhs| lhs + rhs;
-            Float Sub Float = Float: |lhs, rhs| lhs - rhs;
-            Float Mul Float = Float: |lhs, rhs| lhs * rhs;
-            Float Div Float = Float: |lhs, rhs| lhs / rhs;
-            Float Mod Float = Float: |lhs, rhs| lhs % rhs;
-
-            Float Eq Float = Bool: |lhs, rhs| lhs == rhs;
-            Float Neq Float = Bool: |lhs, rhs| lhs != rhs;
-            Float Gt Float = Bool: |lhs, rhs| lhs > rhs;
-            Float GtEq Float = Bool: |lhs, rhs| lhs >= rhs;
-            Float Lt Float = Bool: |lhs, rhs| lhs < rhs;
-            Float LtEq Float = Bool: |lhs, rhs| lhs <= rhs;
-        })
+        match (lhs, op, rhs) {
+            (Thunk::Bool(lhs), Op::Eq, Thunk::Bool(rhs)) => Thunk::Bool(thunk(move |ctxt| {
+                let lhs = (lhs)(ctxt)?;
+                let rhs = (rhs)(ctxt)?;
+
+                Ok(lhs == rhs)
+            })),
+
+            // Integer division and modulo can fail at runtime, so they are
+            // implemented by hand instead of through the macro
+            (Thunk::Int(lhs), Op::Div, Thunk::Int(rhs)) => Thunk::Int(thunk(move |ctxt| {
+                let lhs = (lhs)(ctxt)?;
+                let rhs = (rhs)(ctxt)?;
+
+                if rhs == 0 {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::DivisionByZero,
+                        node,
+                        span,
+                    ));
+                }
+
+                lhs.checked_div(rhs)
+                    .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::Overflow, node, span))
+            })),
+
+            (Thunk::Int(lhs), Op::Mod, Thunk::Int(rhs)) => Thunk::Int(thunk(move |ctxt| {
+                let lhs = (lhs)(ctxt)?;
+                let rhs = (rhs)(ctxt)?;
+
+                if rhs == 0 {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::DivisionByZero,
+                        node,
+                        span,
+                    ));
+                }
+
+                lhs.checked_rem(rhs)
+                    .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::Overflow, node, span))
+            })),
+
+            (lhs, op, rhs) => ops!(lhs, op, rhs, lhs_shape, rhs_shape, {
+                // Bool Eq Bool = Bool: |lhs, rhs| lhs == rhs; // Already implemented above
+                Bool Neq Bool = Bool: |lhs, rhs| lhs != rhs;
+                Bool And Bool = Bool: |lhs, rhs| lhs && rhs;
+                Bool Or Bool = Bool: |lhs, rhs| lhs || rhs;
+
+                Int Add Int = Int: |lhs, rhs| lhs + rhs;
+                Int Sub Int = Int: |lhs, rhs| lhs - rhs;
+                Int Mul Int = Int: |lhs, rhs| lhs * rhs;
+                // Int Div Int = Int: |lhs, rhs| lhs / rhs; // Already implemented above
+                // Int Mod Int = Int: |lhs, rhs| lhs % rhs; // Already implemented above
+
+                Int Eq Int = Bool: |lhs, rhs| lhs == rhs;
+                Int Neq Int = Bool: |lhs, rhs| lhs != rhs;
+                Int Gt Int = Bool: |lhs, rhs| lhs > rhs;
+                Int GtEq Int = Bool: |lhs, rhs| lhs >= rhs;
+                Int Lt Int = Bool: |lhs, rhs| lhs < rhs;
+                Int LtEq Int = Bool: |lhs, rhs| lhs <= rhs;
+
+                Float Add Float = Float: |lhs, rhs| lhs + rhs;
+                Float Sub Float = Float: |lhs, rhs| lhs - rhs;
+                Float Mul Float = Float: |lhs, rhs| lhs * rhs;
+                Float Div Float = Float: |lhs, rhs| lhs / rhs;
+                Float Mod Float = Float: |lhs, rhs| lhs % rhs;
+
+                Float Eq Float = Bool: |lhs, rhs| lhs == rhs;
+                Float Neq Float = Bool: |lhs, rhs| lhs != rhs;
+                Float Gt Float = Bool: |lhs, rhs| lhs > rhs;
+                Float GtEq Float = Bool: |lhs, rhs| lhs >= rhs;
+                Float Lt Float = Bool: |lhs, rhs| lhs < rhs;
+                Float LtEq Float = Bool: |lhs, rhs| lhs <= rhs;
+            }),
+        }
     }
 
     fn compile_declare(
         ctxt: &mut CompilationContext<'s>,
         name: ast::Ident<'s>,
+        ty: Option<ast::Type>,
         value: Self,
-    ) -> (ast::Type, Thunk<'s>) {
-        let (ty, value) = value.compile(ctxt);
-        let id = ctxt.allocate_var(name, ty);
+    ) -> Thunk<'s> {
+        let (value_ty, value) = value.compile(ctxt);
 
-        let node = thunk(move |ctxt| {
-            ctxt.stack[id] = (value)(ctxt);
-            ast::Value::Unit
-        });
+        // Already checked by the type checker
+        debug_assert!(ty.map_or(true, |ty| ty == value_ty));
+        let id = ctxt.allocate_var(name, &value_ty);
+
+        Thunk::Unit(value.stored(id))
+    }
 
-        (ast::Type::Unit, node)
+    /// Variables declared without a value get allocated once they are
+    /// assigned to (see [`Self::compile_assign()`]), so there's nothing to
+    /// do here.
+    fn compile_declare_uninit() -> Thunk<'s> {
+        Thunk::Unit(thunk(|_| Ok(())))
+    }
+
+    fn compile_destructure(
+        ctxt: &mut CompilationContext<'s>,
+        pat: ast::Pattern<'s>,
+        value: Self,
+    ) -> Thunk<'s> {
+        let (value_ty, value) = value.compile(ctxt);
+        let value = value.boxed();
+        let binding = pat.compile(ctxt, &value_ty);
+
+        Thunk::Unit(thunk(move |ctxt| {
+            let value = (value)(ctxt)?;
+
+            binding.bind(ctxt, value);
+
+            Ok(())
+        }))
     }
 
     fn compile_assign(
         ctxt: &mut CompilationContext<'s>,
         name: ast::Ident<'s>,
         value: Self,
-    ) -> (ast::Type, Thunk<'s>) {
-        if name == "input" {
-            panic!("`input` variable is read-only");
-        }
+    ) -> Thunk<'s> {
+        // Variables declared without a value get their slot on the first
+        // assignment, since that's where their type becomes known
+        let id = ctxt.vars.get(name).copied();
+
+        // `x = x + c` and `x = x - c` update the variable's slot in place,
+        // without going through the value's thunk
+        let update = match (id, ast::BinOp::of(value.unspanned())) {
+            (Some(id), Some((op, lhs, rhs))) if Shape::of(lhs, ctxt) == Shape::Var(id) => {
+                match (op, rhs.unspanned()) {
+                    (ast::BinOp::Add, ast::Node::Const(ast::Value::Int(c))) => {
+                        let c = *c;
+
+                        Some(updated(id, move |value: i64| value + c))
+                    }
+
+                    (ast::BinOp::Sub, ast::Node::Const(ast::Value::Int(c))) => {
+                        let c = *c;
+
+                        Some(updated(id, move |value: i64| value - c))
+                    }
+
+                    (ast::BinOp::Add, ast::Node::Const(ast::Value::Float(c))) => {
+                        let c = *c;
+
+                        Some(updated(id, move |value: f32| value + c))
+                    }
 
-        let id = ctxt
-            .vars
-            .get(name)
-            .cloned()
-            .unwrap_or_else(|| panic!("Unknown variable: {}", name));
+                    (ast::BinOp::Sub, ast::Node::Const(ast::Value::Float(c))) => {
+                        let c = *c;
+
+                        Some(updated(id, move |value: f32| value - c))
+                    }
+
+                    _ => None,
+                }
+            }
+
+            _ => None,
+        };
 
-        let ty = ctxt.stack[id].clone();
         let (value_ty, value) = value.compile(ctxt);
+        let id = id.unwrap_or_else(|| ctxt.allocate_var(name, &value_ty));
 
-        if value_ty != ty {
-            panic!(
-                "Type mismatch: Cannot assign `{:?}` to `{:?}`",
-                value_ty, ty
-            );
-        }
+        Thunk::Unit(update.unwrap_or_else(|| value.stored(id)))
+    }
 
-        let thunk = thunk(move |ctxt| {
-            ctxt.stack[id] = (value)(ctxt);
-            ast::Value::Unit
-        });
+    fn compile_while(ctxt: &mut CompilationContext<'s>, cond: Self, body: Self) -> Thunk<'s> {
+        // `while x < c { ... }` (and other comparisons of an `int` variable
+        // with a constant) reads the variable's slot directly, without going
+        // through the condition's thunk
+        let var_cmp_const = match ast::BinOp::of(cond.unspanned()) {
+            Some((op, lhs, rhs)) => match (Shape::of(lhs, ctxt), rhs.unspanned()) {
+                (Shape::Var(id), ast::Node::Const(ast::Value::Int(c))) => Some((op, id, *c)),
+                _ => None,
+            },
+
+            None => None,
+        };
 
-        (ast::Type::Unit, thunk)
+        let (_, cond) = cond.compile(ctxt);
+        let (_, body) = body.compile(ctxt);
+        let (cond, body) = (bool::from_thunk(cond), body.erased());
+
+        let var = move |ctxt: &mut RuntimeContext<'s>, id| i64::load(&mut ctxt.stack, id);
+
+        Thunk::Unit(match var_cmp_const {
+            Some((ast::BinOp::Eq, id, c)) => looped(move |ctxt| Ok(var(ctxt, id) == c), body),
+            Some((ast::BinOp::Neq, id, c)) => looped(move |ctxt| Ok(var(ctxt, id) != c), body),
+            Some((ast::BinOp::Gt, id, c)) => looped(move |ctxt| Ok(var(ctxt, id) > c), body),
+            Some((ast::BinOp::GtEq, id, c)) => looped(move |ctxt| Ok(var(ctxt, id) >= c), body),
+            Some((ast::BinOp::Lt, id, c)) => looped(move |ctxt| Ok(var(ctxt, id) < c), body),
+            Some((ast::BinOp::LtEq, id, c)) => looped(move |ctxt| Ok(var(ctxt, id) <= c), body),
+            _ => looped(cond, body),
+        })
     }
 
-    fn compile_while(
+    fn compile_try(
         ctxt: &mut CompilationContext<'s>,
-        cond: Self,
         body: Self,
-    ) -> (ast::Type, Thunk<'s>) {
-        let (cond_ty, cond) = cond.compile(ctxt);
+        name: ast::Ident<'s>,
+        handler: Self,
+    ) -> Thunk<'s> {
+        // Variables declared inside `body` might get initialized only
+        // partially, but there's no need to clean them up when it fails: the
+        // type checker doesn't allow for them to be read afterwards
         let (_, body) = body.compile(ctxt);
+        let id = ctxt.allocate_var(name, &ast::Type::Str);
+        let (_, handler) = handler.compile(ctxt);
 
-        if cond_ty != ast::Type::Bool {
-            panic!("Type mismatch: Loop's condition was expected to be `bool`");
-        }
+        // The error is visible only inside `handler`
+        ctxt.vars.remove(name);
 
-        let thunk = thunk(move |ctxt| {
-            while (cond)(ctxt).unbox() {
-                (body)(ctxt);
-            }
+        // Both branches are of the same type, so `handler` is of the same
+        // variant as `body`
+        match_thunk!(body, |body| {
+            let handler = ThunkValue::from_thunk(handler);
 
-            ast::Value::Unit
-        });
+            ThunkValue::into_thunk(thunk(move |ctxt| match (body)(ctxt) {
+                Ok(value) => Ok(value),
+
+                Err(err) => {
+                    ctxt.stack.strs[id] = Rc::new(err.message().into());
 
-        (ast::Type::Unit, thunk)
+                    (handler)(ctxt)
+                }
+            }))
+        })
     }
 
     fn compile_print(
         ctxt: &mut CompilationContext<'s>,
         nodes: Vec<Self>,
-    ) -> (ast::Type, Thunk<'s>) {
+        newline: bool,
+    ) -> Thunk<'s> {
         let nodes: Vec<_> = nodes
             .into_iter()
             .map(|node| node.compile(ctxt))
-            .map(|(_, node)| node)
+            .map(|(_, node)| node.boxed())
             .collect();
 
-        let node = thunk(move |ctxt| {
+        Thunk::Unit(thunk(move |ctxt| {
             for node in &nodes {
-                (node)(ctxt).print();
+                print!("{}", (node)(ctxt)?);
             }
 
-            ast::Value::Unit
-        });
+            if newline {
+                println!();
+            }
 
-        (ast::Type::Unit, node)
+            Ok(())
+        }))
     }
 
-    fn compile_block(
-        ctxt: &mut CompilationContext<'s>,
-        nodes: Vec<Self>,
-    ) -> (ast::Type, Thunk<'s>) {
-        let (types, nodes): (Vec<_>, Vec<_>) =
-            nodes.into_iter().map(|node| node.compile(ctxt)).unzip();
+    fn compile_format(ctxt: &mut CompilationContext<'s>, fmt: &str, args: Vec<Self>) -> Thunk<'s> {
+        let fmt = ast::Format::parse(fmt).expect("format string should've been validated");
 
-        let ty = types.into_iter().last().unwrap_or(ast::Type::Unit);
+        let args: Vec<_> = args
+            .into_iter()
+            .map(|arg| arg.compile(ctxt))
+            .map(|(_, arg)| arg.boxed())
+            .collect();
 
-        let thunk = thunk(move |ctxt| {
-            let mut value = ast::Value::Unit;
+        Thunk::Str(thunk(move |ctxt| {
+            let args = args
+                .iter()
+                .map(|arg| (arg)(ctxt))
+                .collect::<Result<Vec<_>, _>>()?;
 
+            Ok(Rc::new(fmt.render(&args).into()))
+        }))
+    }
+
+    fn compile_block(ctxt: &mut CompilationContext<'s>, nodes: Vec<Self>) -> Thunk<'s> {
+        let mut nodes: Vec<_> = nodes
+            .into_iter()
+            .map(|node| node.compile(ctxt))
+            .map(|(_, node)| node)
+            .collect();
+
+        // Only the last node's value is returned, so the rest doesn't have to
+        // be of any particular type
+        let last = match nodes.pop() {
+            Some(last) => last,
+            None => return Thunk::Unit(thunk(|_| Ok(()))),
+        };
+
+        let nodes: Vec<_> = nodes.into_iter().map(Thunk::erased).collect();
+
+        match_thunk!(last, |last| ThunkValue::into_thunk(thunk(move |ctxt| {
             for node in &nodes {
-                value = (node)(ctxt);
+                (node)(ctxt)?;
             }
 
-            value
-        });
+            (last)(ctxt)
+        })))
+    }
 
-        (ty, thunk)
+    fn compile_spanned(
+        ctxt: &mut CompilationContext<'s>,
+        span: ast::Span,
+        node: Self,
+    ) -> Thunk<'s> {
+        let parent_span = ctxt.span.replace(span);
+        let (_, thunk) = node.compile(ctxt);
+
+        ctxt.span = parent_span;
+
+        thunk
+    }
+}
+
+impl<'s> ast::Pattern<'s> {
+    fn compile(self, ctxt: &mut CompilationContext<'s>, ty: &ast::Type) -> Binding {
+        match (self, ty) {
+            (ast::Pattern::Var(name), ty) => Binding::Var(ctxt.allocate_var(name, ty)),
+            (ast::Pattern::Ignore, _) => Binding::Ignore,
+
+            (ast::Pattern::Tuple(pats), ast::Type::Tuple(tys)) => Binding::Tuple(
+                pats.into_iter()
+                    .zip(tys)
+                    .map(|(pat, ty)| pat.compile(ctxt, ty))
+                    .collect(),
+            ),
+
+            // Ruled out by the type checker
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Compiled counterpart of [`ast::Pattern`].
+enum Binding {
+    /// Stores value into given stack slot
+    Var(usize),
+
+    /// Drops value
+    Ignore,
+
+    /// Unpacks tuple and binds its elements
+    Tuple(Vec<Self>),
+}
+
+impl Binding {
+    fn bind<'s>(&self, ctxt: &mut RuntimeContext<'s>, value: ast::Value<'s>) {
+        match self {
+            Binding::Var(id) => {
+                ctxt.stack.store(*id, value);
+            }
+
+            Binding::Ignore => {
+                //
+            }
+
+            Binding::Tuple(bindings) => {
+                for (binding, value) in bindings.iter().zip(value.unbox_tuple()) {
+                    binding.bind(ctxt, value);
+                }
+            }
+        }
     }
 }
 
 struct CompilationContext<'s> {
-    /// Variables allocated so far
-    stack: Vec<ast::Type>,
+    /// Variables allocated so far, initialized with placeholder values
+    stack: Stack<'s>,
 
-    /// Maps variable name into its stack slot
+    /// Maps variable name into its stack slot (in the array for variable's
+    /// type)
     vars: BTreeMap<ast::Ident<'s>, usize>,
+
+    /// Number of nodes compiled so far
+    nodes: usize,
+
+    /// Index (in pre-order) of the innermost node being compiled, used to
+    /// report runtime errors
+    node: usize,
+
+    /// Span of the innermost node being compiled (if known), used to report
+    /// runtime errors
+    span: Option<ast::Span>,
+
+    /// Types of the nodes yet to be compiled, in pre-order; see
+    /// [`ast::TypedProgram::types()`]
+    types: std::vec::IntoIter<ast::Type>,
 }
 
 impl<'s> CompilationContext<'s> {
-    fn new(input: ast::Type) -> Self {
-        let mut this = Self {
+    fn new(types: Vec<ast::Type>) -> Self {
+        Self {
             stack: Default::default(),
             vars: Default::default(),
+            nodes: 0,
+            node: 0,
+            span: None,
+            types: types.into_iter(),
+        }
+    }
+
+    fn allocate_var(&mut self, name: ast::Ident<'s>, ty: &ast::Type) -> usize {
+        fn allocate<'s, T>(stack: &mut Stack<'s>) -> usize
+        where
+            T: ThunkValue<'s>,
+        {
+            let slots = T::slots(stack);
+
+            slots.push(T::default());
+            slots.len() - 1
+        }
+
+        let id = match ty {
+            ast::Type::Unit => allocate::<()>(&mut self.stack),
+            ast::Type::Bool => allocate::<bool>(&mut self.stack),
+            ast::Type::Char => allocate::<char>(&mut self.stack),
+            ast::Type::Int => allocate::<i64>(&mut self.stack),
+            ast::Type::Float => allocate::<f32>(&mut self.stack),
+            ast::Type::Str => allocate::<Rc<Cow<str>>>(&mut self.stack),
+            ast::Type::Tuple(_) => allocate::<Rc<Vec<ast::Value>>>(&mut self.stack),
         };
 
-        this.allocate_var("input", input);
-        this
+        self.vars.insert(name, id);
+
+        id
     }
+}
 
-    fn allocate_var(&mut self, name: ast::Ident<'s>, ty: ast::Type) -> usize {
-        let id = self.stack.len();
+struct RuntimeContext<'s> {
+    stack: Stack<'s>,
+}
 
-        self.stack.push(ty);
+/// Variables of a running program.
+///
+/// Each type has its own array of slots, so that scalars are stored unboxed
+/// and reading them doesn't have to go through [`ast::Value`] - variable's
+/// slot is an index into the array for its type.
+///
+/// Strings and tuples are reference-counted, so that reading them only bumps
+/// the counter instead of copying the whole value.
+#[derive(Clone, Debug, Default)]
+struct Stack<'s> {
+    units: Vec<()>,
+    bools: Vec<bool>,
+    chars: Vec<char>,
+    ints: Vec<i64>,
+    floats: Vec<f32>,
+    strs: Vec<Rc<Cow<'s, str>>>,
+    tuples: Vec<Rc<Vec<ast::Value<'s>>>>,
+}
 
-        if self.vars.insert(name, id).is_some() {
-            panic!("Variable `{}` has been already defined", name);
+impl<'s> Stack<'s> {
+    fn len(&self) -> usize {
+        self.units.len()
+            + self.bools.len()
+            + self.chars.len()
+            + self.ints.len()
+            + self.floats.len()
+            + self.strs.len()
+            + self.tuples.len()
+    }
+
+    /// Stores boxed value into given slot of the array for value's type.
+    fn store(&mut self, id: usize, value: ast::Value<'s>) {
+        match value {
+            ast::Value::Unit => self.units[id] = (),
+            ast::Value::Bool(value) => self.bools[id] = value,
+            ast::Value::Char(value) => self.chars[id] = value,
+            ast::Value::Int(value) => self.ints[id] = value,
+            ast::Value::Float(value) => self.floats[id] = value,
+            ast::Value::Str(value) => self.strs[id] = Rc::new(value),
+            ast::Value::Tuple(values) => self.tuples[id] = Rc::new(values),
         }
+    }
+}
 
-        id
+/// Compiled node.
+///
+/// Since node's type is known at compile time, its thunk returns an unboxed
+/// value (e.g. `i64` instead of `ast::Value::Int`), so that operations don't
+/// have to check types at runtime; values get boxed only when they leave the
+/// thunks - when they're stored into variables, printed or returned from the
+/// program.
+enum Thunk<'s> {
+    Unit(TypedThunk<'s, ()>),
+    Bool(TypedThunk<'s, bool>),
+    Char(TypedThunk<'s, char>),
+    Int(TypedThunk<'s, i64>),
+    Float(TypedThunk<'s, f32>),
+    Str(TypedThunk<'s, Rc<Cow<'s, str>>>),
+
+    /// Tuples' elements can be of different types, so they're kept boxed
+    Tuple(TypedThunk<'s, Rc<Vec<ast::Value<'s>>>>),
+}
+
+impl<'s> Thunk<'s> {
+    /// Creates a thunk out of a closure that returns a boxed value of type
+    /// `ty`, unboxing it.
+    fn unboxed(
+        ty: &ast::Type,
+        f: impl Fn(&mut RuntimeContext<'s>) -> ThunkResult<ast::Value<'s>> + 's,
+    ) -> Self {
+        fn unboxed<'s, T>(
+            f: impl Fn(&mut RuntimeContext<'s>) -> ThunkResult<ast::Value<'s>> + 's,
+        ) -> Thunk<'s>
+        where
+            T: ThunkValue<'s>,
+        {
+            T::into_thunk(thunk(move |ctxt| Ok(T::from_value((f)(ctxt)?))))
+        }
+
+        match ty {
+            ast::Type::Unit => unboxed::<()>(f),
+            ast::Type::Bool => unboxed::<bool>(f),
+            ast::Type::Char => unboxed::<char>(f),
+            ast::Type::Int => unboxed::<i64>(f),
+            ast::Type::Float => unboxed::<f32>(f),
+            ast::Type::Str => unboxed::<Rc<Cow<str>>>(f),
+            ast::Type::Tuple(_) => unboxed::<Rc<Vec<ast::Value>>>(f),
+        }
+    }
+
+    /// Creates a thunk that reads variable of type `ty` from given stack slot.
+    fn loaded(ty: &ast::Type, id: usize) -> Self {
+        fn loaded<'s, T>(id: usize) -> Thunk<'s>
+        where
+            T: ThunkValue<'s>,
+        {
+            T::into_thunk(thunk(move |ctxt| Ok(T::load(&mut ctxt.stack, id))))
+        }
+
+        match ty {
+            ast::Type::Unit => loaded::<()>(id),
+            ast::Type::Bool => loaded::<bool>(id),
+            ast::Type::Char => loaded::<char>(id),
+            ast::Type::Int => loaded::<i64>(id),
+            ast::Type::Float => loaded::<f32>(id),
+            ast::Type::Str => loaded::<Rc<Cow<str>>>(id),
+            ast::Type::Tuple(_) => loaded::<Rc<Vec<ast::Value>>>(id),
+        }
+    }
+
+    /// Returns a thunk that boxes this thunk's value.
+    fn boxed(self) -> TypedThunk<'s, ast::Value<'s>> {
+        match_thunk!(self, |this| thunk(move |ctxt| Ok(
+            (this)(ctxt)?.into_value()
+        )))
+    }
+
+    /// Returns a thunk that stores this thunk's value into given stack slot.
+    fn stored(self, id: usize) -> TypedThunk<'s, ()> {
+        match_thunk!(self, |this| thunk(move |ctxt| {
+            (this)(ctxt)?.store(&mut ctxt.stack, id);
+            Ok(())
+        }))
+    }
+
+    /// Returns a thunk that discards this thunk's value.
+    fn erased(self) -> TypedThunk<'s, ()> {
+        match self {
+            Thunk::Unit(this) => this,
+
+            this => match_thunk!(this, |this| thunk(move |ctxt| {
+                (this)(ctxt)?;
+                Ok(())
+            })),
+        }
     }
 }
 
-struct RuntimeContext<'s> {
-    stack: Vec<ast::Value<'s>>,
+/// Rust type that thunks of given [`Thunk`] variant return.
+trait ThunkValue<'s>: Clone + Default + 's {
+    fn into_value(self) -> ast::Value<'s>;
+
+    /// Panics if `value` is of a different type
+    fn from_value(value: ast::Value<'s>) -> Self;
+
+    fn into_thunk(thunk: TypedThunk<'s, Self>) -> Thunk<'s>;
+
+    /// Panics if `thunk` is of a different type
+    fn from_thunk(thunk: Thunk<'s>) -> TypedThunk<'s, Self>;
+
+    /// Returns stack's array for variables of this type
+    fn slots<'a>(stack: &'a mut Stack<'s>) -> &'a mut Vec<Self>;
+
+    fn load(stack: &mut Stack<'s>, id: usize) -> Self {
+        Self::slots(stack)[id].clone()
+    }
+
+    fn store(self, stack: &mut Stack<'s>, id: usize) {
+        Self::slots(stack)[id] = self;
+    }
+}
+
+macro_rules! thunk_values {
+    ([ $( $variant:ident($param:ident: $ty:ty) in $slots:ident => $into_value:expr, $from_value:expr ),+ $(,)? ]) => {
+        $(
+            impl<'s> ThunkValue<'s> for $ty {
+                fn into_value(self) -> ast::Value<'s> {
+                    let $param = self;
+
+                    $into_value
+                }
+
+                fn from_value($param: ast::Value<'s>) -> Self {
+                    $from_value
+                }
+
+                fn into_thunk(thunk: TypedThunk<'s, Self>) -> Thunk<'s> {
+                    Thunk::$variant(thunk)
+                }
+
+                fn from_thunk(thunk: Thunk<'s>) -> TypedThunk<'s, Self> {
+                    match thunk {
+                        Thunk::$variant(thunk) => thunk,
+
+                        // Ruled out by the type checker
+                        _ => unreachable!(),
+                    }
+                }
+
+                fn slots<'a>(stack: &'a mut Stack<'s>) -> &'a mut Vec<Self> {
+                    &mut stack.$slots
+                }
+            }
+        )+
+    };
 }
 
-impl<'s> RuntimeContext<'s> {
-    fn new(stack_size: usize, input: ast::Value<'s>) -> Self {
-        let mut stack = vec![ast::Value::Unit; stack_size];
+thunk_values!([
+    Unit(value: ()) in units => { let () = value; ast::Value::Unit }, value.unbox(),
+    Bool(value: bool) in bools => ast::Value::Bool(value), value.unbox(),
+    Char(value: char) in chars => ast::Value::Char(value), value.unbox(),
+    Int(value: i64) in ints => ast::Value::Int(value), value.unbox(),
+    Float(value: f32) in floats => ast::Value::Float(value), value.unbox(),
+    Str(value: Rc<Cow<'s, str>>) in strs => ast::Value::Str(unshared(value)), Rc::new(value.unbox()),
+    Tuple(value: Rc<Vec<ast::Value<'s>>>) in tuples => ast::Value::Tuple(unshared(value)), Rc::new(value.unbox_tuple()),
+]);
+
+/// Takes value out of the `Rc`, cloning it only if it's shared (e.g. still
+/// stored in a variable).
+fn unshared<T>(value: Rc<T>) -> T
+where
+    T: Clone,
+{
+    Rc::try_unwrap(value).unwrap_or_else(|value| (*value).clone())
+}
 
-        stack[0] = input;
+/// Shape of an operand, as far as fusing it into the operation goes; see
+/// [`fused()`].
+#[derive(PartialEq)]
+enum Shape<'s> {
+    /// `name`, stored in given stack slot
+    Var(usize),
 
-        Self { stack }
+    /// `123` etc.
+    Const(ast::Value<'s>),
+
+    /// Anything else
+    Other,
+}
+
+impl<'s> Shape<'s> {
+    fn of(node: &ast::Node<'s>, ctxt: &CompilationContext<'s>) -> Self {
+        match node.unspanned() {
+            ast::Node::Var(name) => Shape::Var(ctxt.vars[name]),
+            ast::Node::Const(value) => Shape::Const(value.clone()),
+            _ => Shape::Other,
+        }
     }
 }
 
-type Thunk<'s> = Box<dyn Fn(&mut RuntimeContext<'s>) -> ast::Value<'s> + 's>;
+/// Operand of a binary operation, typed.
+enum Operand<'s, T> {
+    Var(usize),
+    Const(T),
+    Thunk(TypedThunk<'s, T>),
+}
+
+impl<'s, T> Operand<'s, T>
+where
+    T: ThunkValue<'s>,
+{
+    fn new(shape: Shape<'s>, thunk: TypedThunk<'s, T>) -> Self {
+        match shape {
+            Shape::Var(id) => Operand::Var(id),
+            Shape::Const(value) => Operand::Const(T::from_value(value)),
+            Shape::Other => Operand::Thunk(thunk),
+        }
+    }
+
+    fn into_thunk(self) -> TypedThunk<'s, T> {
+        match self {
+            Operand::Var(id) => thunk(move |ctxt| Ok(T::load(&mut ctxt.stack, id))),
+            Operand::Const(value) => thunk(move |_| Ok(value.clone())),
+            Operand::Thunk(thunk) => thunk,
+        }
+    }
+}
+
+/// Creates a thunk for a binary operation.
+///
+/// Operands that are variables or constants get fused into the operation's
+/// closure (a so-called superinstruction), e.g. `x + 1` reads `x`'s slot and
+/// adds `1` to it directly, instead of calling separate thunks for `x` and
+/// `1`; other operands are called as usual.
+fn fused<'s, L, R, O>(
+    lhs: Operand<'s, L>,
+    rhs: Operand<'s, R>,
+    eval: impl Fn(L, R) -> O + 's,
+) -> TypedThunk<'s, O>
+where
+    L: ThunkValue<'s>,
+    R: ThunkValue<'s>,
+{
+    match (lhs, rhs) {
+        (Operand::Var(lhs), Operand::Var(rhs)) => thunk(move |ctxt| {
+            let lhs = L::load(&mut ctxt.stack, lhs);
+            let rhs = R::load(&mut ctxt.stack, rhs);
+
+            Ok(eval(lhs, rhs))
+        }),
+
+        (Operand::Var(lhs), Operand::Const(rhs)) => {
+            thunk(move |ctxt| Ok(eval(L::load(&mut ctxt.stack, lhs), rhs.clone())))
+        }
+
+        (Operand::Const(lhs), Operand::Var(rhs)) => {
+            thunk(move |ctxt| Ok(eval(lhs.clone(), R::load(&mut ctxt.stack, rhs))))
+        }
+
+        (lhs, rhs) => {
+            let (lhs, rhs) = (lhs.into_thunk(), rhs.into_thunk());
+
+            thunk(move |ctxt| {
+                let lhs = (lhs)(ctxt)?;
+                let rhs = (rhs)(ctxt)?;
+
+                Ok(eval(lhs, rhs))
+            })
+        }
+    }
+}
+
+/// Creates a thunk that updates variable in given stack slot in place.
+fn updated<'s, T>(id: usize, update: impl Fn(T) -> T + 's) -> TypedThunk<'s, ()>
+where
+    T: ThunkValue<'s>,
+{
+    thunk(move |ctxt| {
+        let slot = &mut T::slots(&mut ctxt.stack)[id];
+
+        *slot = update(slot.clone());
+        Ok(())
+    })
+}
+
+/// Creates a thunk for a `while` loop.
+fn looped<'s>(
+    cond: impl Fn(&mut RuntimeContext<'s>) -> ThunkResult<bool> + 's,
+    body: TypedThunk<'s, ()>,
+) -> TypedThunk<'s, ()> {
+    thunk(move |ctxt| {
+        while cond(ctxt)? {
+            (body)(ctxt)?;
+        }
+
+        Ok(())
+    })
+}
+
+type TypedThunk<'s, T> = Box<dyn Fn(&mut RuntimeContext<'s>) -> ThunkResult<T> + 's>;
+
+type ThunkResult<T> = Result<T, RuntimeError>;
 
-fn thunk<'s>(f: impl Fn(&mut RuntimeContext<'s>) -> ast::Value<'s> + 's) -> Thunk<'s> {
+fn thunk<'s, T>(f: impl Fn(&mut RuntimeContext<'s>) -> ThunkResult<T> + 's) -> TypedThunk<'s, T> {
     Box::new(f)
 }
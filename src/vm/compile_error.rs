@@ -0,0 +1,66 @@
+use crate::ast;
+use std::fmt;
+
+/// Error returned by [`crate::vm::try_compile()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompileError {
+    /// Generic parameter `Input` doesn't match the program's input type
+    InvalidInput {
+        expected: ast::Type,
+        actual: ast::Type,
+    },
+
+    /// Generic parameter `Output` doesn't match the program's output type
+    InvalidOutput {
+        expected: ast::Type,
+        actual: ast::Type,
+    },
+
+    /// Program is ill-typed; contains all the errors found by
+    /// [`ast::typecheck()`] (there's always at least one)
+    Type(Vec<ast::TypeError>),
+}
+
+impl CompileError {
+    /// Renders the error rustc-style, with snippets of `src` (the source code
+    /// the program has been parsed from); see [`ast::render_errors()`].
+    pub fn render(&self, src: &str) -> String {
+        match self {
+            CompileError::Type(errs) => ast::render_errors(src, errs),
+            err => format!("error: {}\n", err),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    /// Formats the error; type errors are printed one per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::InvalidInput { expected, .. } => write!(
+                f,
+                "Invalid invocation: Generic parameter `Input` must be `{:?}`",
+                expected
+            ),
+
+            CompileError::InvalidOutput { expected, .. } => write!(
+                f,
+                "Invalid invocation: Generic parameter `Output` must be `{:?}`",
+                expected
+            ),
+
+            CompileError::Type(errs) => {
+                for (idx, err) in errs.iter().enumerate() {
+                    if idx > 0 {
+                        writeln!(f)?;
+                    }
+
+                    write!(f, "{}", err)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
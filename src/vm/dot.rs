@@ -0,0 +1,27 @@
+use super::typecheck;
+use crate::ast;
+
+/// Same as [`ast::to_dot()`], but annotates each node with its type, as
+/// inferred by [`ast::typecheck()`].
+///
+/// Note that this is the tree of AST nodes, not of the thunks
+/// [`crate::vm::compile()`] builds - the compiler fuses e.g. variables and
+/// constants into the operations using them, so it creates fewer thunks than
+/// there are nodes.
+///
+/// Panics if the program doesn't type-check.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = rast! { fn(input: int) -> float { input as float } };
+///
+/// assert!(vm::to_dot(&prog).contains(r#"n2 [label="as float\n: float"];"#));
+/// ```
+pub fn to_dot(prog: &ast::Program) -> String {
+    let typed = typecheck(prog);
+
+    ast::to_dot_with_types(prog, Some(typed.types()))
+}
@@ -1,7 +1,20 @@
+use super::{check_signature, typecheck, RuntimeError, RuntimeErrorKind};
 use crate::ast;
-use std::collections::HashMap;
+use crate::ast::Visitor;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
-/// Evaluates specified `prog`, given `input`.
+/// Type-checks (see [`ast::typecheck()`]) and evaluates specified `prog`,
+/// given `input`.
+///
+/// Type errors are reported as panics; for parsed programs, their messages
+/// start with the location of the offending node (e.g. `3:5: Unknown
+/// variable: x`). They are reported before the program starts, even if they
+/// are in code that would never run.
+///
+/// Runtime errors that aren't caught by the program itself are returned
+/// (see [`RuntimeError`]).
 ///
 /// See also: [`crate::vm::compile()`].
 ///
@@ -10,41 +23,36 @@ use std::collections::HashMap;
 /// ```
 /// # use rast_jit_vm::{examples, prelude::*};
 /// #
-/// assert_eq!(55, vm::eval(&examples::fibonacci(), 10));
+/// assert_eq!(Ok(55), vm::eval(&examples::fibonacci(), 10));
 /// ```
-pub fn eval<'s, Input, Output>(prog: &ast::Program<'s>, input: Input) -> Output
+pub fn eval<'s, Input, Output>(
+    prog: &ast::Program<'s>,
+    input: Input,
+) -> Result<Output, RuntimeError>
 where
     Input: ast::IntoValue<'s>,
     Output: ast::FromValue<'s>,
 {
-    if Input::ty().map_or(false, |ty| ty != prog.input) {
-        panic!(
-            "Invalid invocation: Generic parameter `Input` must be `{:?}`",
-            prog.input,
-        );
-    }
-
-    if Output::ty().map_or(false, |ty| ty != prog.output) {
-        panic!(
-            "Invalid invocation: Generic parameter `Output` must be `{:?}`",
-            prog.output
-        );
-    }
+    check_signature::<Input, Output>(prog).unwrap_or_else(|err| panic!("{}", err));
+    typecheck(prog);
 
     let mut ctxt = RuntimeContext {
+        prog,
         vars: HashMap::from_iter(vec![("input", input.into_value())]),
+        formats: formats(prog),
+        span: None,
     };
 
-    prog.body.eval(&mut ctxt).unbox()
+    Ok(prog.body.eval(&mut ctxt)?.unbox())
 }
 
 impl<'s> ast::Node<'s> {
-    fn eval(&self, ctxt: &mut RuntimeContext<'s>) -> ast::Value<'s> {
+    fn eval(&self, ctxt: &mut RuntimeContext<'_, 's>) -> EvalResult<'s> {
         match self {
             ast::Node::Const(value) => Self::eval_const(value),
             ast::Node::Var(name) => Self::eval_var(ctxt, name),
             ast::Node::ExtractTuple { expr, idx } => Self::eval_extract_tuple(ctxt, expr, *idx),
-            ast::Node::ExtractArray { expr, idx } => Self::eval_extract_array(ctxt, expr, idx),
+            ast::Node::ExtractArray { expr, idx } => self.eval_extract_array(ctxt, expr, idx),
             ast::Node::Cast { expr, ty } => Self::eval_cast(ctxt, expr, ty),
 
             ast::Node::Add { lhs, rhs }
@@ -61,56 +69,82 @@ impl<'s> ast::Node<'s> {
             | ast::Node::And { lhs, rhs }
             | ast::Node::Or { lhs, rhs } => self.eval_binop(ctxt, lhs, rhs),
 
-            ast::Node::Declare { name, value } => Self::eval_declare(ctxt, name, value),
+            ast::Node::Declare { name, ty, value } => {
+                Self::eval_declare(ctxt, name, ty.as_ref(), value)
+            }
+            ast::Node::DeclareUninit { .. } => Ok(ast::Value::Unit),
+            ast::Node::Destructure { pat, value } => Self::eval_destructure(ctxt, pat, value),
             ast::Node::Assign { name, value } => Self::eval_assign(ctxt, name, value),
             ast::Node::While { cond, body } => Self::eval_while(ctxt, cond, body),
-            ast::Node::Print(nodes) => Self::eval_print(ctxt, nodes),
+            ast::Node::Try {
+                body,
+                name,
+                handler,
+            } => Self::eval_try(ctxt, body, name, handler),
+            ast::Node::Print(nodes) => Self::eval_print(ctxt, nodes, false),
+            ast::Node::Println(nodes) => Self::eval_print(ctxt, nodes, true),
+            ast::Node::Format { fmt, args } => Self::eval_format(ctxt, fmt, args),
             ast::Node::Block(nodes) => Self::eval_block(ctxt, nodes),
+            ast::Node::Spanned { span, node } => Self::eval_spanned(ctxt, *span, node),
         }
     }
 
-    fn eval_const(value: &ast::Value<'s>) -> ast::Value<'s> {
-        value.to_owned()
+    fn eval_const(value: &ast::Value<'s>) -> EvalResult<'s> {
+        Ok(value.to_owned())
     }
 
-    fn eval_var(ctxt: &mut RuntimeContext<'s>, name: ast::Ident<'s>) -> ast::Value<'s> {
-        ctxt.vars
+    fn eval_var(ctxt: &mut RuntimeContext<'_, 's>, name: ast::Ident<'s>) -> EvalResult<'s> {
+        Ok(ctxt
+            .vars
             .get(name)
-            .unwrap_or_else(|| panic!("Cannot find variable `{}`", name))
-            .to_owned()
+            .unwrap_or_else(|| ctxt.fail(format!("Cannot find variable `{}`", name)))
+            .to_owned())
     }
 
     fn eval_extract_tuple(
-        ctxt: &mut RuntimeContext<'s>,
+        ctxt: &mut RuntimeContext<'_, 's>,
         expr: &Self,
         idx: usize,
-    ) -> ast::Value<'s> {
-        expr.eval(ctxt).unbox_tuple()[idx].to_owned()
+    ) -> EvalResult<'s> {
+        Ok(expr.eval(ctxt)?.unbox_tuple()[idx].to_owned())
     }
 
     fn eval_extract_array(
-        ctxt: &mut RuntimeContext<'s>,
+        &self,
+        ctxt: &mut RuntimeContext<'_, 's>,
         expr: &Self,
         idx: &Self,
-    ) -> ast::Value<'s> {
-        let expr = expr.eval(ctxt).unbox::<&str>();
-        let idx = idx.eval(ctxt).unbox::<i64>() as _;
-        let value = expr.chars().nth(idx).expect("Index out of bounds");
+    ) -> EvalResult<'s> {
+        let expr = expr.eval(ctxt)?.unbox::<Cow<str>>();
+        let idx = idx.eval(ctxt)?.unbox::<i64>();
+
+        let value = usize::try_from(idx)
+            .ok()
+            .and_then(|idx| expr.chars().nth(idx))
+            .ok_or_else(|| ctxt.error(self, RuntimeErrorKind::IndexOutOfBounds))?;
 
-        ast::Value::Char(value)
+        Ok(ast::Value::Char(value))
     }
 
-    fn eval_cast(ctxt: &mut RuntimeContext<'s>, expr: &Self, ty: &ast::Type) -> ast::Value<'s> {
-        let expr = expr.eval(ctxt);
+    fn eval_cast(ctxt: &mut RuntimeContext<'_, 's>, expr: &Self, ty: &ast::Type) -> EvalResult<'s> {
+        let expr = expr.eval(ctxt)?;
 
-        match (expr.ty(), ty) {
+        Ok(match (expr.ty(), ty) {
             (ast::Type::Int, ast::Type::Float) => ast::Value::Float(expr.unbox::<i64>() as _),
             (ast::Type::Float, ast::Type::Int) => ast::Value::Int(expr.unbox::<f32>() as _),
-            (source_ty, target_ty) => panic!("Cannot cast `{:?}` to `{:?}`", source_ty, target_ty),
-        }
+            (source_ty, target_ty) => ctxt.fail(format!(
+                "Cannot cast `{:?}` to `{:?}`",
+                source_ty, target_ty
+            )),
+        })
     }
 
-    fn eval_binop(&self, ctxt: &mut RuntimeContext<'s>, lhs: &Self, rhs: &Self) -> ast::Value<'s> {
+    fn eval_binop(
+        &self,
+        ctxt: &mut RuntimeContext<'_, 's>,
+        lhs: &Self,
+        rhs: &Self,
+    ) -> EvalResult<'s> {
         #[derive(Debug)]
         enum Op {
             Add,
@@ -150,10 +184,10 @@ impl<'s> ast::Node<'s> {
             _ => unreachable!(),
         };
 
-        let lhs = lhs.eval(ctxt);
-        let rhs = rhs.eval(ctxt);
+        let lhs = lhs.eval(ctxt)?;
+        let rhs = rhs.eval(ctxt)?;
 
-        match (lhs.ty(), op, rhs.ty()) {
+        Ok(match (lhs.ty(), op, rhs.ty()) {
             (ast::Type::Bool, op @ (Op::Eq | Op::Neq | Op::And | Op::Or), ast::Type::Bool) => {
                 let lhs = lhs.unbox::<bool>();
                 let rhs = rhs.unbox::<bool>();
@@ -175,12 +209,20 @@ impl<'s> ast::Node<'s> {
                 let lhs = lhs.unbox::<i64>();
                 let rhs = rhs.unbox::<i64>();
 
+                if matches!(op, Op::Div | Op::Mod) && rhs == 0 {
+                    return Err(ctxt.error(self, RuntimeErrorKind::DivisionByZero));
+                }
+
                 ast::Value::Int(match op {
                     Op::Add => lhs + rhs,
                     Op::Sub => lhs - rhs,
                     Op::Mul => lhs * rhs,
-                    Op::Div => lhs / rhs,
-                    Op::Mod => lhs % rhs,
+                    Op::Div => lhs
+                        .checked_div(rhs)
+                        .ok_or_else(|| ctxt.error(self, RuntimeErrorKind::Overflow))?,
+                    Op::Mod => lhs
+                        .checked_rem(rhs)
+                        .ok_or_else(|| ctxt.error(self, RuntimeErrorKind::Overflow))?,
                     _ => unreachable!(),
                 })
             }
@@ -241,80 +283,281 @@ impl<'s> ast::Node<'s> {
                 })
             }
 
-            (lhs_ty, op, rhs_ty) => {
-                panic!("Unknown operation `{:?}` `{:?}` `{:?}`", lhs_ty, op, rhs_ty)
-            }
-        }
+            (lhs_ty, op, rhs_ty) => ctxt.fail(format!(
+                "Unknown operation `{:?}` `{:?}` `{:?}`",
+                lhs_ty, op, rhs_ty
+            )),
+        })
     }
 
     fn eval_declare(
-        ctxt: &mut RuntimeContext<'s>,
+        ctxt: &mut RuntimeContext<'_, 's>,
         name: ast::Ident<'s>,
+        ty: Option<&ast::Type>,
         value: &Self,
-    ) -> ast::Value<'s> {
-        let value = value.eval(ctxt);
+    ) -> EvalResult<'s> {
+        let value = value.eval(ctxt)?;
+
+        if let Some(ty) = ty.filter(|ty| **ty != value.ty()) {
+            ctxt.fail(format!(
+                "Cannot assign `{:?}` to a variable of type `{:?}`",
+                value.ty(),
+                ty
+            ));
+        }
 
         ctxt.vars.insert(name, value);
 
-        ast::Value::Unit
+        Ok(ast::Value::Unit)
+    }
+
+    fn eval_destructure(
+        ctxt: &mut RuntimeContext<'_, 's>,
+        pat: &ast::Pattern<'s>,
+        value: &Self,
+    ) -> EvalResult<'s> {
+        let value = value.eval(ctxt)?;
+
+        pat.eval(ctxt, value);
+
+        Ok(ast::Value::Unit)
     }
 
     fn eval_assign(
-        ctxt: &mut RuntimeContext<'s>,
+        ctxt: &mut RuntimeContext<'_, 's>,
         name: ast::Ident<'s>,
         value: &Self,
-    ) -> ast::Value<'s> {
+    ) -> EvalResult<'s> {
         if name == "input" {
-            panic!("`input` variable is read-only");
+            ctxt.fail("`input` variable is read-only");
         }
 
-        let new_value = value.eval(ctxt);
-
-        let curr_value = ctxt
-            .vars
-            .get(name)
-            .unwrap_or_else(|| panic!("Cannot find variable `{}`", name));
-
-        if new_value.ty() != curr_value.ty() {
-            panic!(
-                "Cannot assign `{:?}` to a variable of type `{:?}`",
-                new_value.ty(),
-                curr_value.ty()
-            );
+        let new_value = value.eval(ctxt)?;
+
+        // Variables declared without a value (`var name;`) don't have an
+        // entry until they are assigned to
+        if let Some(curr_value) = ctxt.vars.get(name) {
+            if new_value.ty() != curr_value.ty() {
+                ctxt.fail(format!(
+                    "Cannot assign `{:?}` to a variable of type `{:?}`",
+                    new_value.ty(),
+                    curr_value.ty()
+                ));
+            }
         }
 
         ctxt.vars.insert(name, new_value);
 
-        ast::Value::Unit
+        Ok(ast::Value::Unit)
     }
 
-    fn eval_while(ctxt: &mut RuntimeContext<'s>, cond: &Self, body: &Self) -> ast::Value<'s> {
-        while cond.eval(ctxt).unbox() {
-            body.eval(ctxt);
+    fn eval_while(ctxt: &mut RuntimeContext<'_, 's>, cond: &Self, body: &Self) -> EvalResult<'s> {
+        while cond.eval(ctxt)?.unbox() {
+            body.eval(ctxt)?;
         }
 
-        ast::Value::Unit
+        Ok(ast::Value::Unit)
+    }
+
+    fn eval_try(
+        ctxt: &mut RuntimeContext<'_, 's>,
+        body: &Self,
+        name: ast::Ident<'s>,
+        handler: &Self,
+    ) -> EvalResult<'s> {
+        let vars: HashSet<_> = ctxt.vars.keys().copied().collect();
+
+        match body.eval(ctxt) {
+            Ok(value) => Ok(value),
+
+            Err(err) => {
+                // Forget about variables declared inside `body`, since they
+                // might've been initialized only partially
+                ctxt.vars.retain(|name, _| vars.contains(name));
+                ctxt.vars
+                    .insert(name, ast::Value::Str(err.message().into()));
+
+                let value = handler.eval(ctxt);
+
+                ctxt.vars.remove(name);
+                value
+            }
+        }
     }
 
-    fn eval_print(ctxt: &mut RuntimeContext<'s>, nodes: &[Self]) -> ast::Value<'s> {
+    fn eval_print(
+        ctxt: &mut RuntimeContext<'_, 's>,
+        nodes: &[Self],
+        newline: bool,
+    ) -> EvalResult<'s> {
         for node in nodes {
-            node.eval(ctxt).print();
+            print!("{}", node.eval(ctxt)?);
         }
 
-        ast::Value::Unit
+        if newline {
+            println!();
+        }
+
+        Ok(ast::Value::Unit)
     }
 
-    fn eval_block(ctxt: &mut RuntimeContext<'s>, nodes: &[Self]) -> ast::Value<'s> {
+    fn eval_format(ctxt: &mut RuntimeContext<'_, 's>, fmt: &str, args: &[Self]) -> EvalResult<'s> {
+        let args = args
+            .iter()
+            .map(|arg| arg.eval(ctxt))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ast::Value::Str(ctxt.formats[fmt].render(&args).into()))
+    }
+
+    fn eval_block(ctxt: &mut RuntimeContext<'_, 's>, nodes: &[Self]) -> EvalResult<'s> {
         let mut value = ast::Value::Unit;
 
         for node in nodes {
-            value = node.eval(ctxt);
+            value = node.eval(ctxt)?;
         }
 
-        value
+        Ok(value)
+    }
+
+    fn eval_spanned(
+        ctxt: &mut RuntimeContext<'_, 's>,
+        span: ast::Span,
+        node: &Self,
+    ) -> EvalResult<'s> {
+        let parent_span = ctxt.span.replace(span);
+        let result = node.eval(ctxt);
+
+        ctxt.span = parent_span;
+
+        result
+    }
+}
+
+impl<'s> ast::Pattern<'s> {
+    fn eval(&self, ctxt: &mut RuntimeContext<'_, 's>, value: ast::Value<'s>) {
+        match self {
+            ast::Pattern::Var(name) => {
+                ctxt.vars.insert(name, value);
+            }
+
+            ast::Pattern::Ignore => {
+                //
+            }
+
+            ast::Pattern::Tuple(pats) => {
+                let values = value.unbox_tuple();
+
+                if pats.len() != values.len() {
+                    ctxt.fail(format!(
+                        "Invalid pattern: Tuple has {} element(s), but the \
+                         pattern expects {}",
+                        values.len(),
+                        pats.len()
+                    ));
+                }
+
+                for (pat, value) in pats.iter().zip(values) {
+                    pat.eval(ctxt, value);
+                }
+            }
+        }
     }
 }
 
-struct RuntimeContext<'s> {
+struct RuntimeContext<'a, 's> {
+    /// Program being evaluated, used to report runtime errors
+    prog: &'a ast::Program<'s>,
+
     vars: HashMap<ast::Ident<'s>, ast::Value<'s>>,
+
+    /// Format strings used by the program, parsed up-front so that loops
+    /// don't have to parse them over and over
+    formats: HashMap<String, ast::Format>,
+
+    /// Span of the innermost node being evaluated (if known), used to report
+    /// errors
+    span: Option<ast::Span>,
+}
+
+impl<'s> RuntimeContext<'_, 's> {
+    /// Returns runtime error that happened at given `node`.
+    fn error(&self, node: &ast::Node<'s>, kind: RuntimeErrorKind) -> RuntimeError {
+        RuntimeError::new(kind, node_index(self.prog, node), self.span)
+    }
+
+    /// Panics with given message, prefixed with the current node's location.
+    fn fail(&self, message: impl fmt::Display) -> ! {
+        match self.span {
+            Some(span) => panic!("{}: {}", span, message),
+            None => panic!("{}", message),
+        }
+    }
+}
+
+type EvalResult<'s> = Result<ast::Value<'s>, RuntimeError>;
+
+/// Parses all format strings used by `prog` (which must've been type-checked
+/// already).
+fn formats(prog: &ast::Program<'_>) -> HashMap<String, ast::Format> {
+    #[derive(Default)]
+    struct Formats(HashMap<String, ast::Format>);
+
+    impl<'s> ast::Visitor<'s> for Formats {
+        fn visit_node(&mut self, node: &ast::Node<'s>) {
+            if let ast::Node::Format { fmt, .. } = node {
+                if !self.0.contains_key(fmt.as_ref()) {
+                    let parsed =
+                        ast::Format::parse(fmt).expect("format string should've been validated");
+
+                    self.0.insert(fmt.to_string(), parsed);
+                }
+            }
+
+            ast::walk_node(self, node);
+        }
+    }
+
+    let mut formats = Formats::default();
+
+    formats.visit_program(prog);
+    formats.0
+}
+
+/// Returns index of given `node` (which must belong to `prog`), counting
+/// nodes in pre-order.
+///
+/// The evaluator doesn't keep track of indices on its own, since they are
+/// needed only when something goes wrong.
+fn node_index<'s>(prog: &ast::Program<'s>, node: &ast::Node<'s>) -> usize {
+    struct Finder<'a, 's> {
+        node: &'a ast::Node<'s>,
+        nodes: usize,
+        found: Option<usize>,
+    }
+
+    impl<'s> ast::Visitor<'s> for Finder<'_, 's> {
+        fn visit_node(&mut self, node: &ast::Node<'s>) {
+            if self.found.is_some() {
+                return;
+            }
+
+            if std::ptr::eq(node, self.node) {
+                self.found = Some(self.nodes);
+                return;
+            }
+
+            self.nodes += 1;
+            ast::walk_node(self, node);
+        }
+    }
+
+    let mut finder = Finder {
+        node,
+        nodes: 0,
+        found: None,
+    };
+
+    finder.visit_program(prog);
+    finder.found.expect("node doesn't belong to the program")
 }
@@ -0,0 +1,73 @@
+use crate::ast;
+use std::fmt;
+
+/// Error that can happen while a (type-checked) program is running.
+///
+/// Those errors can be caught from within the program using
+/// [`crate::ast::Node::Try`]; if they're not, they are returned from
+/// [`crate::vm::eval()`] and from the compiled function.
+///
+/// # Example
+///
+/// ```
+/// # use rast_jit_vm::prelude::*;
+/// #
+/// let prog = parse("fn(input: int) -> int {\n    10 / input\n}").unwrap();
+/// let err = vm::eval::<_, i64>(&prog, 0).unwrap_err();
+///
+/// assert_eq!(vm::RuntimeErrorKind::DivisionByZero, err.kind);
+/// assert_eq!("2:5: Division by zero", err.to_string());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+
+    /// Index of the node that failed, counting nodes in pre-order (i.e. the
+    /// same way as [`ast::TypedProgram::types()`] and [`ast::Visitor`] do)
+    pub node: usize,
+
+    /// Span of the node that failed, if the program has been parsed from
+    /// source code
+    pub span: Option<ast::Span>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuntimeErrorKind {
+    /// `int / 0` or `int % 0`
+    DivisionByZero,
+
+    /// `int / int` or `int % int` whose result doesn't fit in `int`, i.e.
+    /// the smallest `int` divided by `-1`
+    Overflow,
+
+    /// `str[idx]`, where `idx` is negative or past the string's end
+    IndexOutOfBounds,
+}
+
+impl RuntimeError {
+    pub(crate) fn new(kind: RuntimeErrorKind, node: usize, span: Option<ast::Span>) -> Self {
+        Self { kind, node, span }
+    }
+
+    /// Returns error's message, without the location; that's what gets
+    /// bound to the variable in `catch`.
+    pub fn message(&self) -> &'static str {
+        match self.kind {
+            RuntimeErrorKind::DivisionByZero => "Division by zero",
+            RuntimeErrorKind::Overflow => "Arithmetic overflow",
+            RuntimeErrorKind::IndexOutOfBounds => "Index out of bounds",
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = self.span {
+            write!(f, "{}: ", span)?;
+        }
+
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for RuntimeError {}
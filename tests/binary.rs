@@ -0,0 +1,191 @@
+use rast_jit_vm::{ast::*, examples};
+
+fn error(bytes: &[u8]) -> String {
+    decode(bytes).unwrap_err().to_string()
+}
+
+#[test]
+fn test_examples() {
+    for prog in [examples::fibonacci(), examples::mandelbrot()] {
+        let bytes = encode(&prog);
+
+        assert_eq!(prog, decode(&bytes).unwrap());
+
+        // Sanity check: the binary format should be smaller than the source
+        assert!(bytes.len() < prog.to_string().len());
+    }
+}
+
+#[test]
+fn test_values() {
+    let values = vec![
+        Value::Unit,
+        Value::Bool(true),
+        Value::Char('ż'),
+        Value::Int(0),
+        Value::Int(-1),
+        Value::Int(i64::MIN),
+        Value::Int(i64::MAX),
+        Value::Float(-1.5),
+        Value::Float(f32::INFINITY),
+        Value::Str("".into()),
+        Value::Str("Zażółć gęślą jaźń".into()),
+        Value::Tuple(vec![Value::Tuple(vec![]), Value::Int(1)]),
+    ];
+
+    for value in values {
+        let prog = Program {
+            input: Type::Unit,
+            output: value.ty(),
+            body: Node::Const(value),
+        };
+
+        assert_eq!(prog, decode(&encode(&prog)).unwrap());
+    }
+}
+
+#[test]
+fn test_borrows_from_input() {
+    let prog = examples::fibonacci();
+    let bytes = encode(&prog);
+    let bytes_range = bytes.as_ptr_range();
+
+    match decode(&bytes).unwrap().body {
+        Node::Block(nodes) => match &nodes[0] {
+            Node::Declare { name, .. } => {
+                assert!(bytes_range.contains(&name.as_ptr()));
+            }
+
+            node => panic!("unexpected node: {:?}", node),
+        },
+
+        node => panic!("unexpected node: {:?}", node),
+    }
+}
+
+#[test]
+fn test_errors() {
+    let mut valid = encode(&Program {
+        input: Type::Unit,
+        output: Type::Str,
+        body: Node::Const(Value::Str("abc".into())),
+    });
+
+    assert_eq!("byte 0: missing magic header", error(b""));
+    assert_eq!("byte 0: missing magic header", error(b"RUST\x01"));
+    assert_eq!("byte 4: unexpected end of input", error(b"RAST"));
+    assert_eq!(
+        "byte 4: unsupported version 0 (expected 1 to 3)",
+        error(b"RAST\x00")
+    );
+    assert_eq!(
+        "byte 4: unsupported version 4 (expected 1 to 3)",
+        error(b"RAST\x04")
+    );
+    assert_eq!("byte 5: invalid type tag: 7", error(b"RAST\x01\x07"));
+
+    // Tuple claiming to contain more types than there are bytes
+    assert_eq!(
+        "byte 6: length exceeds the input",
+        error(b"RAST\x01\x06\xff\xff\xff\xff\x0f")
+    );
+
+    // Varint longer than 10 bytes
+    assert_eq!(
+        "byte 6: varint is too large",
+        error(b"RAST\x01\x06\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01")
+    );
+
+    valid.push(0);
+    assert_eq!("byte 13: unexpected trailing bytes", error(&valid));
+    valid.pop();
+
+    // Invalid UTF-8 in the string
+    *valid.last_mut().unwrap() = 0xff;
+    assert_eq!("byte 10: invalid UTF-8", error(&valid));
+}
+
+#[test]
+fn test_versions() {
+    let prog = parse("fn(input: int) { var x: float = 1.5; var y = x; var z; var w: int; }")
+        .unwrap()
+        .without_spans();
+
+    assert_eq!(prog, decode(&encode(&prog)).unwrap());
+
+    // Version 1 doesn't have type annotations on declarations
+    let bytes = b"RAST\x01\x00\x00\x12\x01x\x00\x00";
+
+    assert_eq!(
+        Program {
+            input: Type::Unit,
+            output: Type::Unit,
+            body: Node::Declare {
+                name: "x",
+                ty: None,
+                value: Box::new(Node::Const(Value::Unit)),
+            },
+        },
+        decode(bytes).unwrap()
+    );
+
+    assert_eq!(
+        "byte 10: invalid option",
+        error(b"RAST\x02\x00\x00\x12\x01x\x02")
+    );
+
+    // Versions before 3 don't have declarations without values
+    let bytes = b"RAST\x03\x00\x00\x1c\x01x\x01\x03";
+
+    assert_eq!(
+        Program {
+            input: Type::Unit,
+            output: Type::Unit,
+            body: Node::DeclareUninit {
+                name: "x",
+                ty: Some(Type::Int),
+            },
+        },
+        decode(bytes).unwrap()
+    );
+
+    assert_eq!(
+        "byte 7: invalid node tag: 28",
+        error(b"RAST\x02\x00\x00\x1c\x01x\x01\x03")
+    );
+}
+
+#[test]
+fn test_depth() {
+    let mut bytes = b"RAST\x01\x00\x00".to_vec();
+
+    // 10k nested `Node::Block`-s
+    for _ in 0..10_000 {
+        bytes.extend([26, 1]);
+    }
+
+    bytes.extend([26, 0]);
+
+    assert_eq!("byte 519: nesting is too deep", error(&bytes));
+}
+
+#[test]
+fn test_malformed_input_does_not_panic() {
+    let bytes = encode(&examples::mandelbrot());
+
+    // Every truncation is an error
+    for len in 0..bytes.len() {
+        assert!(decode(&bytes[..len]).is_err(), "len = {}", len);
+    }
+
+    // Every corruption either fails or decodes into something
+    for idx in 0..bytes.len() {
+        for byte in [0x00, 0x01, 0x06, 0x1a, 0x7f, 0x80, 0xff] {
+            let mut bytes = bytes.clone();
+
+            bytes[idx] = byte;
+
+            let _ = decode(&bytes);
+        }
+    }
+}
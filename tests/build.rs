@@ -0,0 +1,109 @@
+use rast_jit_vm::build::*;
+use rast_jit_vm::{ast::*, examples, vm};
+
+#[test]
+fn test_operators() {
+    let cases = vec![
+        (var("a") + 1 - var("b") * 2.5, "a + 1 - b * 2.5"),
+        (var("a") / (var("b") % 3), "a / (b % 3)"),
+        (
+            var("a").gt(1).and(var("b").lt_eq(2)).or(lit(true)),
+            "a > 1 && b <= 2 || true",
+        ),
+        (var("a").eq_(var("b")).neq(lit(false)), "(a == b) != false"),
+        (var("a").gt_eq(1).and(var("a").lt(2)), "a >= 1 && a < 2"),
+        (
+            var("input").extract(0).index(var("i") + 1).cast(Type::Int),
+            "input.0[i + 1] as int",
+        ),
+        (lit((1, "two", '3')), "(1, \"two\", '3')"),
+    ];
+
+    for (expr, expected) in cases {
+        assert_eq!(
+            parse_node(expected).unwrap().without_spans(),
+            Node::from(expr)
+        );
+    }
+}
+
+#[test]
+fn test_eq_compares_expressions() {
+    // `eq()` is `PartialEq`'s, while `eq_()` builds an `==` node
+    assert!(var("a").eq(&var("a")));
+    assert!(!var("a").eq(&var("b")));
+    assert_eq!(
+        Node::from(var("a").eq_(var("a"))),
+        parse_node("a == a").unwrap().without_spans()
+    );
+}
+
+#[test]
+fn test_literals() {
+    assert_eq!(Node::Const(Value::Int(1)), Node::from(1));
+    assert_eq!(Node::Const(Value::Int(1)), Node::from(1i64));
+    assert_eq!(Node::Const(Value::Float(1.5)), Node::from(1.5));
+    assert_eq!(Node::Const(Value::Bool(true)), Node::from(true));
+    assert_eq!(Node::Const(Value::Char('x')), Node::from('x'));
+    assert_eq!(Node::Const(Value::Str("x".into())), Node::from("x"));
+    assert_eq!(Node::Const(Value::Unit), Node::from(()));
+}
+
+#[test]
+fn test_statements() {
+    let expr = block![
+        destructure(
+            Pattern::Tuple(vec![Pattern::Var("a"), Pattern::Ignore]),
+            var("input"),
+        ),
+        declare("x", 0),
+        while_(var("x").lt(var("a")), block![assign("x", var("x") + 1)]),
+        try_(
+            block![var("x") / 0],
+            "err",
+            block![println([var("err")]), -1],
+        ),
+        print([format("{:>3}", [var("x")]), lit("!")]),
+    ];
+
+    let expected = r#"{
+        var (a, _) = input;
+        var x = 0;
+        while x < a { x = x + 1 }
+        try { x / 0 } catch err { println(err); -1 }
+        print(format("{:>3}", x), "!")
+    }"#;
+
+    assert_eq!(
+        parse_node(expected).unwrap().without_spans(),
+        Node::from(expr)
+    );
+}
+
+#[test]
+fn test_fibonacci() {
+    let fibonacci = Program {
+        input: Type::Int,
+        output: Type::Int,
+        body: block![
+            declare("x", 0),
+            declare("y", 1),
+            declare("z", 1),
+            declare("n", var("input")),
+            while_(
+                var("n").gt(0),
+                block![
+                    assign("x", var("y")),
+                    assign("y", var("z")),
+                    assign("z", var("x") + var("y")),
+                    assign("n", var("n") - 1),
+                ],
+            ),
+            var("x"),
+        ]
+        .into(),
+    };
+
+    assert_eq!(examples::fibonacci(), fibonacci);
+    assert_eq!(55, vm::compile::<i64, i64>(fibonacci)(10).unwrap());
+}
@@ -0,0 +1,139 @@
+use rast_jit_vm::{ast::*, examples, vm};
+
+#[test]
+fn test_ok() {
+    let fib = vm::try_compile::<i64, i64>(examples::fibonacci()).unwrap();
+
+    assert_eq!(Ok(55), fib.run(10));
+    assert_eq!(Ok(6765), fib.run(20));
+}
+
+#[test]
+fn test_invalid_signature() {
+    assert_eq!(
+        vm::CompileError::InvalidInput {
+            expected: Type::Int,
+            actual: Type::Float,
+        },
+        vm::try_compile::<f32, i64>(examples::fibonacci()).unwrap_err()
+    );
+
+    let err = vm::try_compile::<i64, String>(examples::fibonacci()).unwrap_err();
+
+    assert_eq!(
+        vm::CompileError::InvalidOutput {
+            expected: Type::Int,
+            actual: Type::Str,
+        },
+        err
+    );
+
+    assert_eq!(
+        "Invalid invocation: Generic parameter `Output` must be `Int`",
+        err.to_string()
+    );
+}
+
+#[test]
+fn test_type_errors() {
+    let src = "fn(input: int) -> int {\n    var x = y;\n    input = x as str;\n    x\n}";
+    let prog = parse(src).unwrap();
+
+    let errs = match vm::try_compile::<i64, i64>(prog.clone()) {
+        Err(vm::CompileError::Type(errs)) => errs,
+        result => panic!("unexpected result: {:?}", result),
+    };
+
+    let kinds: Vec<_> = errs.iter().map(|err| err.kind.clone()).collect();
+
+    assert_eq!(
+        vec![
+            TypeErrorKind::UnknownVariable { name: "y".into() },
+            TypeErrorKind::ReadOnlyVariable {
+                name: "input".into()
+            },
+        ],
+        kinds
+    );
+
+    let spans: Vec<_> = errs
+        .iter()
+        .map(|err| err.span.unwrap().to_string())
+        .collect();
+
+    assert_eq!(vec!["2:13", "3:5"], spans);
+
+    assert_eq!(
+        "2:13: Unknown variable: y\n3:5: `input` variable is read-only",
+        vm::CompileError::Type(errs).to_string()
+    );
+
+    // `x as str` is not reported, since `x`'s type is unknown; once it is, it
+    // gets reported
+    let prog = parse("fn(input: int) -> str { var x = 1; x as str }").unwrap();
+
+    let errs = match vm::try_compile::<i64, String>(prog) {
+        Err(vm::CompileError::Type(errs)) => errs,
+        result => panic!("unexpected result: {:?}", result),
+    };
+
+    assert_eq!(
+        vec![TypeErrorKind::InvalidCast {
+            from: Type::Int,
+            to: Type::Str,
+        }],
+        errs.into_iter().map(|err| err.kind).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_fused_shapes() {
+    let src = r#"fn(input: (int, float)) -> int {
+        var i = input.0;
+        var f = input.1;
+        var n = 0;
+
+        while i >= 3 {
+            i = i - 3;
+            f = f + 0.5;
+            n = 10 - i;
+        }
+
+        while n != 20 {
+            n = n + 1;
+        }
+
+        var ok = 0;
+
+        while i < n && 1 >= i && ok == 0 {
+            ok = 10000;
+        }
+
+        ok + i + n * 10 + (f * 2.0) as int * 1000
+    }"#;
+
+    let prog = parse(src).unwrap();
+    let expected = vm::eval::<_, i64>(&prog, (10, 1.0)).unwrap();
+
+    assert_eq!(15201, expected);
+    assert_eq!(Ok(expected), vm::compile(prog)((10, 1.0)));
+}
+
+#[test]
+fn test_variables_declared_in_lhs() {
+    // `rhs` refers to a variable declared within `lhs`
+    let cases = [
+        ("fn(input: int) -> int { ({ var y = 1; y }) + y }", 2),
+        (
+            "fn(input: int) -> int { var x = input; x = { var y = 1; x } + y; x }",
+            6,
+        ),
+    ];
+
+    for (src, expected) in cases {
+        let prog = parse(src).unwrap();
+
+        assert_eq!(Ok(expected), vm::eval::<_, i64>(&prog, 5), "{}", src);
+        assert_eq!(Ok(expected), vm::compile::<_, i64>(prog)(5), "{}", src);
+    }
+}
@@ -0,0 +1,117 @@
+use rast_jit_vm::{ast::*, vm};
+
+fn render(src: &str) -> String {
+    let prog = parse(src).unwrap();
+
+    render_errors(src, &typecheck(&prog).unwrap_err())
+}
+
+#[test]
+fn test_many_errors() {
+    let src = r#"fn(input: int) -> str {
+    var x = input + true;
+    var y = x * 2;
+    while 1 { }
+    format("{}")
+}"#;
+
+    // `x * 2` is not reported, since `x` is ill-typed already
+    let expected = r#"error[E0010]: Unknown operation `Int` `Add` `Bool`
+ --> 2:13
+  |
+2 |     var x = input + true;
+  |             ^^^^^^^^^^^^
+
+error[E0011]: Type mismatch: Loop's condition was expected to be `bool`
+ --> 4:5
+  |
+4 |     while 1 { }
+  |     ^^^^^^^^^^^
+
+error[E0014]: Invalid format: Format string expects 1 argument(s), got 0
+ --> 5:5
+  |
+5 |     format("{}")
+  |     ^^^^^^^^^^^^
+"#;
+
+    assert_eq!(expected, render(src));
+}
+
+#[test]
+fn test_snippets() {
+    // Multi-line nodes are underlined up to the end of their first line
+    let src = "fn(input: int) -> str {\n    input\n}";
+
+    let expected = "\
+error[E0015]: Output type mismatch: Program declared to return `Str`, but in reality it returns `Int`
+ --> 1:23
+  |
+1 | fn(input: int) -> str {
+  |                       ^
+";
+
+    assert_eq!(expected, render(src));
+
+    // Tabs are preserved, so that the markers line up
+    let src = "fn(input: int) -> int {\n\tinput = 1;\n\t\tinput\n}";
+
+    let expected = "\
+error[E0003]: `input` variable is read-only
+ --> 2:2
+  |
+2 | \tinput = 1;
+  | \t^^^^^^^^^
+";
+
+    assert_eq!(expected, render(src));
+
+    // The gutter grows along with line numbers
+    let src = format!("fn(input: int) -> int {{{}\n    x\n}}", "\n".repeat(9));
+
+    let expected = "\
+error[E0001]: Unknown variable: x
+  --> 11:5
+   |
+11 |     x
+   |     ^
+";
+
+    assert_eq!(expected, render(&src));
+}
+
+#[test]
+fn test_without_spans() {
+    let prog = parse("fn(input: int) -> int { input = 1; input }")
+        .unwrap()
+        .without_spans();
+
+    assert_eq!(
+        "error[E0003]: `input` variable is read-only\n",
+        render_errors("", &typecheck(&prog).unwrap_err())
+    );
+}
+
+#[test]
+fn test_compile_error() {
+    let src = "fn(input: int) -> int {\n    input * y\n}";
+
+    let err = vm::try_compile::<i64, i64>(parse(src).unwrap()).unwrap_err();
+
+    let expected = "\
+error[E0001]: Unknown variable: y
+ --> 2:13
+  |
+2 |     input * y
+  |             ^
+";
+
+    assert_eq!(expected, err.render(src));
+
+    let err = vm::try_compile::<f32, i64>(parse(src).unwrap()).unwrap_err();
+
+    assert_eq!(
+        "error: Invalid invocation: Generic parameter `Input` must be `Int`\n",
+        err.render(src)
+    );
+}
@@ -0,0 +1,88 @@
+use rast_jit_vm::{ast::*, examples, vm};
+
+fn vertices(dot: &str) -> Vec<&str> {
+    dot.lines()
+        .filter(|line| line.contains("[label=") && !line.contains(" -> n"))
+        .collect()
+}
+
+#[test]
+fn test_examples() {
+    for prog in [examples::fibonacci(), examples::mandelbrot()] {
+        let dot = to_dot(&prog);
+
+        assert!(dot.starts_with("digraph program {\n"));
+        assert!(dot.ends_with("}\n"));
+
+        // Every node (plus the program itself) gets a vertex, every vertex
+        // except for the root has exactly one incoming edge
+        struct Count(usize);
+
+        impl<'s> Visitor<'s> for Count {
+            fn visit_node(&mut self, node: &Node<'s>) {
+                self.0 += 1;
+                walk_node(self, node);
+            }
+        }
+
+        let mut count = Count(0);
+
+        count.visit_program(&prog);
+
+        let edges = dot.lines().filter(|line| line.contains(" -> n")).count();
+
+        assert_eq!(count.0 + 1, vertices(&dot).len());
+        assert_eq!(count.0, edges);
+    }
+}
+
+#[test]
+fn test_labels() {
+    let prog = parse(
+        r#"fn(input: (str, int)) -> str {
+            var (s, _) = input;
+            try { s[input.1] as int; } catch err { }
+            format("\"{}\"", s)
+        }"#,
+    )
+    .unwrap();
+
+    let dot = to_dot(&prog);
+
+    let expected = [
+        r#"n0 [label="fn(input: (str, int)) -> str", shape=oval];"#,
+        r#"n2 [label="var (s, _)\n@ 2:13"];"#,
+        r#"n4 [label="try / catch err\n@ 3:13"];"#,
+        r#"n6 [label="as int\n@ 3:19"];"#,
+        r#"n7 [label="[]\n@ 3:19"];"#,
+        r#"n9 [label=".1\n@ 3:21"];"#,
+        r#"n12 [label="format(\"\\\"{}\\\"\")\n@ 4:13"];"#,
+        r#"n4 -> n5 [label="body"];"#,
+        r#"n4 -> n11 [label="handler"];"#,
+        r#"n7 -> n9 [label="idx"];"#,
+    ];
+
+    for line in expected {
+        assert!(dot.contains(line), "missing `{}` in:\n{}", line, dot);
+    }
+}
+
+#[test]
+fn test_types() {
+    let prog = examples::fibonacci();
+    let dot = vm::to_dot(&prog);
+
+    assert_eq!(vertices(&to_dot(&prog)).len(), vertices(&dot).len());
+
+    let expected = [
+        r#"n1 [label="{ }\n: int"];"#,
+        r#"n2 [label="var x\n: ()"];"#,
+        r#"n3 [label="0\n: int"];"#,
+        r#"n10 [label="while\n: ()"];"#,
+        r#"n11 [label=">\n: bool"];"#,
+    ];
+
+    for line in expected {
+        assert!(dot.contains(line), "missing `{}` in:\n{}", line, dot);
+    }
+}
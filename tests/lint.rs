@@ -0,0 +1,180 @@
+use rast_jit_vm::{ast::*, examples};
+
+fn lints(src: &str) -> Vec<String> {
+    let prog = parse(src).unwrap();
+
+    lint(&prog, &LintLevels::default())
+        .iter()
+        .map(|lint| lint.to_string())
+        .collect()
+}
+
+#[test]
+fn test_examples() {
+    for prog in [examples::fibonacci(), examples::mandelbrot()] {
+        assert_eq!(Vec::<Lint>::new(), lint(&prog, &LintLevels::default()));
+    }
+}
+
+#[test]
+fn test_unused_variables() {
+    let src = r#"fn(input: (int, (int, int))) -> int {
+        var x = 1;
+        var _y = 2;
+        var (a, (b, _)) = input;
+        try { a / 0 } catch err { a }
+    }"#;
+
+    let expected = [
+        "2:9: Variable `x` is never read",
+        "4:9: Variable `b` is never read",
+        "5:9: Variable `err` is never read",
+    ];
+
+    assert_eq!(Vec::from(expected), lints(src));
+}
+
+#[test]
+fn test_unused_assignments() {
+    let src = r#"fn(input: int) -> int {
+        var x = 0;
+        x = 1;
+        x = input;
+
+        var i = 0;
+        var sum = 0;
+        var prev = 0;
+
+        while i < input {
+            prev = sum;
+            sum = sum + i;
+            i = i + 1;
+            x = 2;
+        }
+
+        x = prev;
+
+        var y = 0;
+
+        try {
+            y = 1;
+            y = 10 / input;
+        } catch err {
+            println(err);
+        };
+
+        y = sum;
+        x + y
+    }"#;
+
+    // `x = 2` is overwritten after the loop, while `y = 1` might be
+    // overwritten (if the division succeeds) or not (if it fails, but then
+    // the handler doesn't read `y`)
+    let expected = [
+        "3:9: Value assigned to `x` is never read",
+        "4:9: Value assigned to `x` is never read",
+        "14:13: Value assigned to `x` is never read",
+        "22:13: Value assigned to `y` is never read",
+        "23:13: Value assigned to `y` is never read",
+    ];
+
+    assert_eq!(Vec::from(expected), lints(src));
+
+    // Values read by `catch` are live throughout `try`
+    let src = r#"fn(input: int) -> int {
+        var x = 0;
+        try { x = 1; x = 10 / input; 0 } catch _err { x }
+    }"#;
+
+    assert_eq!(Vec::<String>::new(), lints(src));
+}
+
+#[test]
+fn test_loops() {
+    let src = r#"fn(input: int) {
+        var x = 0;
+
+        while false {
+            x = x + 1;
+        }
+
+        while true {
+            println(x);
+        }
+
+        println("unreachable");
+        println("not reported again");
+    }"#;
+
+    let expected = [
+        "4:9: Loop's condition is always `false`, so its body never runs",
+        "8:9: Loop's condition is always `true`, so it never finishes",
+        "12:9: Unreachable code: it follows a loop that never finishes",
+    ];
+
+    assert_eq!(Vec::from(expected), lints(src));
+
+    // Divergence propagates through blocks, but not through `try`, since its
+    // body can fail
+    let src = r#"fn(input: int) {
+        { while true { } };
+        println("unreachable");
+    }"#;
+
+    assert_eq!(
+        "3:9: Unreachable code: it follows a loop that never finishes",
+        lints(src)[1]
+    );
+
+    let src = r#"fn(input: int) {
+        try { while true { } } catch err { println(err) };
+        println("reachable");
+    }"#;
+
+    assert_eq!(1, lints(src).len());
+}
+
+#[test]
+fn test_shadowed_input() {
+    // Ill-typed programs can be linted, too
+    let src = r#"fn(input: int) {
+        var input = 1;
+        try { 1 / 0 } catch input { println(input) }
+    }"#;
+
+    let expected = [
+        "2:9: Variable `input` shadows program's input",
+        "3:9: Variable `input` shadows program's input",
+    ];
+
+    assert_eq!(Vec::from(expected), lints(src));
+}
+
+#[test]
+fn test_levels() {
+    let src = "fn(input: int) -> int {\n    var x = 1;\n    while false { }\n    input\n}";
+    let prog = parse(src).unwrap();
+
+    let levels = LintLevels::default()
+        .set(LintKind::UnusedVariable, Level::Deny)
+        .set(LintKind::ConstantCondition, Level::Allow);
+
+    let lints = lint(&prog, &levels);
+
+    assert_eq!(1, lints.len());
+    assert_eq!(LintKind::UnusedVariable, lints[0].kind);
+    assert_eq!(Level::Deny, lints[0].level);
+
+    let expected = "\
+error[unused_variable]: Variable `x` is never read
+ --> 2:5
+  |
+2 |     var x = 1;
+  |     ^^^^^^^^^
+";
+
+    assert_eq!(expected, render_lints(src, &lints));
+
+    assert!(lint(&prog, &LintLevels::all(Level::Allow)).is_empty());
+    assert_eq!(2, lint(&prog, &LintLevels::all(Level::Deny)).len());
+}
@@ -0,0 +1,109 @@
+use rast_jit_vm::{ast::*, examples, vm};
+
+fn optimized(src: &str) -> Program<'_> {
+    optimize(parse(src).unwrap()).without_spans()
+}
+
+fn parsed(src: &str) -> Program<'_> {
+    parse(src).unwrap().without_spans()
+}
+
+#[test]
+fn test_examples() {
+    let fib = optimize(examples::fibonacci());
+
+    assert_eq!(Ok(6765), vm::eval::<i64, i64>(&fib, 20));
+    assert_eq!(Ok(6765), vm::compile::<i64, i64>(fib)(20));
+}
+
+#[test]
+fn test_folding() {
+    let src = r#"fn(input: int) -> float {
+        var a = 1.5 < 2.0 && 2 != 3;
+        var b = "abc"[1];
+        var c = (1, 2).1;
+        (1 + 2 * 3 - 6) as float * 2.5 + input as float
+    }"#;
+
+    let expected = r#"fn(input: int) -> float {
+        var a = true;
+        var b = 'b';
+        var c = 2;
+        2.5 + input as float
+    }"#;
+
+    assert_eq!(parsed(expected), optimized(src));
+}
+
+#[test]
+fn test_propagation() {
+    let src = r#"fn(input: int) -> int {
+        var x = 2;
+        var y = x * 3;
+        var z = y;
+        var w = input;
+
+        while z > 0 {
+            z = z - x;
+        }
+
+        z + y + w
+    }"#;
+
+    // `z` gets assigned to, so it's not constant (even though its initial
+    // value is)
+    let expected = r#"fn(input: int) -> int {
+        var x = 2;
+        var y = 6;
+        var z = 6;
+        var w = input;
+
+        while z > 0 {
+            z = z - 2;
+        }
+
+        z + 6 + w
+    }"#;
+
+    assert_eq!(parsed(expected), optimized(src));
+}
+
+#[test]
+fn test_runtime_errors() {
+    let cases = [
+        (
+            "fn(input: ()) {\n    var x = 0;\n    print(1 + 10 / x);\n}",
+            "fn(input: ()) {\n    var x = 0;\n    print(1 + 10 / 0);\n}",
+            "3:15: Division by zero",
+        ),
+        (
+            "fn(input: ()) { print(\"abc\"[1 + 2]) }",
+            "fn(input: ()) { print(\"abc\"[3]) }",
+            "1:23: Index out of bounds",
+        ),
+    ];
+
+    for (src, expected_src, expected_err) in cases {
+        let prog = optimize(parse(src).unwrap());
+
+        assert_eq!(parsed(expected_src), prog.clone().without_spans());
+
+        let err = vm::eval::<(), ()>(&prog, ()).unwrap_err();
+
+        assert_eq!(expected_err, err.to_string(), "{}", src);
+        assert_eq!(err, vm::compile::<(), ()>(prog)(()).unwrap_err(), "{}", src);
+    }
+
+    // Overflows are left as they are, too
+    let src = "fn(input: ()) -> int 9223372036854775807 + 1";
+
+    assert_eq!(parsed(src), optimized(src));
+}
+
+#[test]
+fn test_ill_typed_programs() {
+    // `x` is declared only when the loop runs, so it cannot be propagated
+    let src = "fn(input: ()) -> int { while false { var x = 1; } 1 + 2 + x }";
+
+    assert_eq!(parsed(src), optimized(src));
+}
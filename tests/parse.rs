@@ -0,0 +1,379 @@
+use rast_jit_vm::{ast::*, examples, vm};
+
+const FIBONACCI: &str = r#"
+fn(input: int) -> int {
+    var x = 0;
+    var y = 1;
+    var z = 1;
+    var n = input;
+
+    while n > 0 {
+        x = y;
+        y = z;
+        z = x + y;
+        n = n - 1;
+    }
+
+    x
+}
+"#;
+
+const MANDELBROT: &str = r##"
+fn(input: (int, int, float, float, float, float, int)) {
+    var (viewport_width, viewport_height, x1, y1, x2, y2, max_iterations) = input;
+    var viewport_y = 0;
+
+    while viewport_y < viewport_height {
+        var y0 = y1 + (y2 - y1) * (viewport_y as float / viewport_height as float);
+        var viewport_x = 0;
+
+        while viewport_x < viewport_width {
+            var x0 = x1 + (x2 - x1) * (viewport_x as float / viewport_width as float);
+            var x = 0.0;
+            var y = 0.0;
+            var iterations = max_iterations;
+
+            while x * x + y * y <= 4.0 && iterations > 0 {
+                var xtemp = x * x - y * y + x0;
+                y = 2.0 * (x * y) + y0;
+                x = xtemp;
+                iterations = iterations - 1;
+            }
+
+            print("#%=-:,. "[(8.0 * (iterations as float / max_iterations as float)) as int]);
+            viewport_x = viewport_x + 1;
+        }
+
+        println();
+        viewport_y = viewport_y + 1;
+    }
+}
+"##;
+
+#[test]
+fn test_examples() {
+    assert_eq!(
+        examples::fibonacci(),
+        parse(FIBONACCI).unwrap().without_spans()
+    );
+
+    assert_eq!(
+        examples::mandelbrot(),
+        parse(MANDELBROT).unwrap().without_spans()
+    );
+}
+
+#[test]
+fn test_borrows_from_source() {
+    let src = String::from(r#"fn(input: str) -> str { var name = "foo"; "bar\n" }"#);
+    let prog = parse(&src).unwrap().without_spans();
+
+    let src_range = src.as_bytes().as_ptr_range();
+
+    let nodes = match &prog.body {
+        Node::Block(nodes) => nodes,
+        node => panic!("unexpected node: {:?}", node),
+    };
+
+    match &nodes[0] {
+        Node::Declare { name, value, .. } => {
+            assert!(src_range.contains(&name.as_ptr()));
+
+            match &**value {
+                Node::Const(Value::Str(std::borrow::Cow::Borrowed(value))) => {
+                    assert!(src_range.contains(&value.as_ptr()));
+                }
+                node => panic!("unexpected node: {:?}", node),
+            }
+        }
+        node => panic!("unexpected node: {:?}", node),
+    }
+
+    // Literals with escape sequences have to be allocated
+    assert_eq!(nodes[1], Node::Const(Value::Str("bar\n".into())));
+}
+
+#[test]
+fn test_precedence() {
+    let cases = [
+        ("1 + 2 * 3", 7),
+        ("(1 + 2) * 3", 9),
+        ("10 - 4 - 3", 3),
+        ("10 - (4 - 3)", 9),
+        ("100 / 10 / 5", 2),
+        ("7 % 4 * 2", 6),
+        ("2 * 3.5 as int", 6),
+        ("-5 - -5", 0),
+        ("(1, (2, 30), 4).1.1 + 1", 31),
+        ("2 * (3 + 4) % 5", 4),
+    ];
+
+    for (expr, expected) in cases {
+        let src = format!("fn(input: ()) -> int {}", expr);
+        let prog = parse(&src).unwrap_or_else(|err| panic!("{}: {}", expr, err));
+
+        assert_eq!(expected, vm::eval::<_, i64>(&prog, ()).unwrap(), "{}", expr);
+    }
+}
+
+#[test]
+fn test_logic() {
+    let prog = parse("fn(input: int) -> bool input > 1 && input < 5 || input == 10").unwrap();
+
+    for n in 0..12 {
+        let expected = n > 1 && n < 5 || n == 10;
+
+        assert_eq!(expected, vm::eval::<_, bool>(&prog, n).unwrap());
+    }
+}
+
+#[test]
+fn test_statements() {
+    let src = r#"
+        fn(input: (int, int)) -> str {
+            /* block comment */
+            var (a, _) = input;
+            var (b) = input.1;
+
+            try {
+                a = a / b;
+            } catch err {
+                a = -1;
+            }
+
+            format("{:>3}|{}", a, 'x')
+        }
+    "#;
+
+    let prog = parse(src).unwrap();
+
+    assert_eq!(" 10|x", vm::eval::<_, String>(&prog, (50, 5)).unwrap());
+    assert_eq!(" -1|x", vm::eval::<_, String>(&prog, (50, 0)).unwrap());
+    assert_eq!(" -1|x", (vm::compile::<_, String>(prog))((50, 0)).unwrap());
+}
+
+#[test]
+fn test_declare_annotations() {
+    let src = r#"
+        fn(input: (int, int)) -> float {
+            var a: float = 1;
+            var b: (int, (str, char)) = (1, ("x", 'y'));
+            var c: int = input.0;
+            var (d) = c;
+            var e;
+            var f: str;
+            a
+        }
+    "#;
+
+    let prog = parse(src).unwrap().without_spans();
+
+    let expected = Node::Block(vec![
+        Node::Declare {
+            name: "a",
+            ty: Some(Type::Float),
+            value: Box::new(Node::Const(Value::Int(1))),
+        },
+        Node::Declare {
+            name: "b",
+            ty: Some(Type::Tuple(vec![
+                Type::Int,
+                Type::Tuple(vec![Type::Str, Type::Char]),
+            ])),
+            value: Box::new(Node::Const(Value::Tuple(vec![
+                Value::Int(1),
+                Value::Tuple(vec![Value::Str("x".into()), Value::Char('y')]),
+            ]))),
+        },
+        Node::Declare {
+            name: "c",
+            ty: Some(Type::Int),
+            value: Box::new(Node::ExtractTuple {
+                expr: Box::new(Node::Var("input")),
+                idx: 0,
+            }),
+        },
+        Node::Destructure {
+            pat: Pattern::Var("d"),
+            value: Box::new(Node::Var("c")),
+        },
+        Node::DeclareUninit {
+            name: "e",
+            ty: None,
+        },
+        Node::DeclareUninit {
+            name: "f",
+            ty: Some(Type::Str),
+        },
+        Node::Var("a"),
+    ]);
+
+    assert_eq!(expected, prog.body);
+
+    let cases = [
+        (
+            "fn(input: int) { var x: = 1 }",
+            "1:25: expected type, found `=`",
+        ),
+        (
+            "fn(input: int) { var x: int 1 }",
+            "1:29: expected `;` or `}`, found `1`",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        let actual = parse(src).map(|_| ()).unwrap_err().to_string();
+
+        assert_eq!(expected, actual, "{}", src);
+    }
+}
+
+#[test]
+fn test_literals() {
+    let prog = parse(r#"fn(input: ()) -> (int, float, char, str, bool, (), (,)) (-9223372036854775808, 1.5e3, '\'', "\u{1F980}", true, (), (,))"#).unwrap().without_spans();
+
+    assert_eq!(
+        Node::Const(Value::Tuple(vec![
+            Value::Int(i64::MIN),
+            Value::Float(1500.0),
+            Value::Char('\''),
+            Value::Str("🦀".into()),
+            Value::Bool(true),
+            Value::Unit,
+            Value::Tuple(vec![]),
+        ])),
+        prog.body
+    );
+}
+
+#[test]
+fn test_errors() {
+    let cases = [
+        (
+            "fn(input: int) 1 +",
+            "1:19: expected expression, found end of input",
+        ),
+        (
+            "fn(input: integer) 1",
+            "1:11: expected type, found `integer`",
+        ),
+        (
+            "fn(input: int) {\n  var x = 1\n  x\n}",
+            "3:3: expected `;` or `}`, found `x`",
+        ),
+        (
+            "fn(input: int) 1 < 2 < 3",
+            "1:22: comparison operators cannot be chained",
+        ),
+        (
+            "fn(input: int) (1, input)",
+            "1:20: tuple literals can contain only constants",
+        ),
+        (
+            "fn(input: int) -input",
+            "1:17: expected number after `-` (there's no unary minus operator; use `0 - expr`)",
+        ),
+        ("fn(input: int) \"abc", "1:16: unterminated string literal"),
+        ("fn(input: int) '\\q'", "1:17: unknown escape sequence"),
+        (
+            "fn(input: int) 99999999999999999999",
+            "1:16: integer literal is too large",
+        ),
+        (
+            "fn(input: int) { var while = 1 }",
+            "1:22: expected identifier, found `while`",
+        ),
+        (
+            "fn(input: int) 1 2",
+            "1:18: expected end of input, found `2`",
+        ),
+        ("fn(input: int) ?", "1:16: unexpected character '?'"),
+    ];
+
+    for (src, expected) in cases {
+        let actual = parse(src).map(|_| ()).unwrap_err().to_string();
+
+        assert_eq!(expected, actual, "{}", src);
+    }
+}
+
+#[test]
+fn test_nesting_limit() {
+    let nested = |depth: usize, open: &str, inner: &str, close: &str| {
+        format!("{}{}{}", open.repeat(depth), inner, close.repeat(depth))
+    };
+
+    // 255 levels (plus the program's body itself) are fine...
+    for body in [nested(255, "(", "1", ")"), nested(254, "{", "1", "}")] {
+        let src = format!("fn(input: int) -> int {}", body);
+
+        assert_eq!(Ok(1), vm::eval(&parse(&src).unwrap(), 0), "{}", body);
+    }
+
+    // ... but anything deeper is rejected (instead of overflowing the stack)
+    let cases = [
+        (
+            format!("fn(input: int) {}", nested(256, "(", "1", ")")),
+            "1:272: nesting is too deep",
+        ),
+        (
+            format!("fn(input: int) {}", nested(10000, "{", "", "}")),
+            "1:271: nesting is too deep",
+        ),
+        (
+            format!("fn(input: {}) 1", nested(10000, "(", "int", ",)")),
+            "1:267: nesting is too deep",
+        ),
+        (
+            format!(
+                "fn(input: int) {{ var {} = 1 }}",
+                nested(10000, "(", "x", ")")
+            ),
+            "1:275: nesting is too deep",
+        ),
+        (
+            format!("fn(input: int) {}1", "var x = ".repeat(10000)),
+            "1:2064: nesting is too deep",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        let actual = parse(&src).map(|_| ()).unwrap_err().to_string();
+
+        assert_eq!(expected, actual);
+    }
+}
+
+#[test]
+fn test_chain_nesting_limit() {
+    // Chains build nested nodes (`1 + 1 + 1` is `(1 + 1) + 1`), so they count
+    // towards the limit as well...
+    for body in [
+        format!("1{}", " + 1".repeat(200)),
+        format!("201{}", " as float as int".repeat(100)),
+    ] {
+        let src = format!("fn(input: int) -> int {}", body);
+        let prog = parse(&src).unwrap();
+
+        assert_eq!(prog, decode(&encode(&prog)).unwrap(), "{}", body);
+        assert_eq!(Ok(201), vm::eval(&prog, 0), "{}", body);
+        assert_eq!(Ok(201), vm::compile(prog)(0), "{}", body);
+    }
+
+    // ... so that long ones get rejected (instead of overflowing the stack)
+    let cases = [
+        (format!("1{}", " + 1".repeat(1000)), "1:1038"),
+        (format!("1{}", " * 1 - 1".repeat(1000)), "1:2050"),
+        (format!("1{}", " as int".repeat(1000)), "1:1799"),
+        (format!("input{}", ".0".repeat(1000)), "1:531"),
+        (format!("input{}", "[0]".repeat(1000)), "1:784"),
+        (format!("input{}", "[0].0".repeat(1000)), "1:657"),
+    ];
+
+    for (body, expected) in cases {
+        let src = format!("fn(input: int) {}", body);
+        let actual = parse(&src).map(|_| ()).unwrap_err().to_string();
+
+        assert_eq!(format!("{}: nesting is too deep", expected), actual);
+    }
+}
@@ -0,0 +1,259 @@
+use rast_jit_vm::{ast::*, examples};
+
+fn assert_roundtrips(prog: &Program) {
+    let src = prog.to_string();
+    let parsed = parse(&src)
+        .unwrap_or_else(|err| panic!("{}\n\n{}", err, src))
+        .without_spans();
+
+    assert_eq!(prog, &parsed, "\n{}", src);
+}
+
+fn int(value: i64) -> Node<'static> {
+    Node::Const(Value::Int(value))
+}
+
+fn var(name: &str) -> Node<'_> {
+    Node::Var(name)
+}
+
+#[test]
+fn test_fibonacci() {
+    let expected = r#"fn(input: int) -> int {
+    var x = 0;
+    var y = 1;
+    var z = 1;
+    var n = input;
+    while n > 0 {
+        x = y;
+        y = z;
+        z = x + y;
+        n = n - 1
+    }
+    x
+}"#;
+
+    assert_eq!(expected, examples::fibonacci().to_string());
+}
+
+#[test]
+fn test_examples() {
+    assert_roundtrips(&examples::fibonacci());
+    assert_roundtrips(&examples::mandelbrot());
+}
+
+#[test]
+fn test_minimal_parens() {
+    let cases = vec![
+        (
+            Node::Sub {
+                lhs: Box::new(Node::Sub {
+                    lhs: Box::new(var("a")),
+                    rhs: Box::new(var("b")),
+                }),
+                rhs: Box::new(var("c")),
+            },
+            "a - b - c",
+        ),
+        (
+            Node::Sub {
+                lhs: Box::new(var("a")),
+                rhs: Box::new(Node::Sub {
+                    lhs: Box::new(var("b")),
+                    rhs: Box::new(var("c")),
+                }),
+            },
+            "a - (b - c)",
+        ),
+        (
+            Node::Mul {
+                lhs: Box::new(Node::Add {
+                    lhs: Box::new(var("a")),
+                    rhs: Box::new(var("b")),
+                }),
+                rhs: Box::new(var("c")),
+            },
+            "(a + b) * c",
+        ),
+        (
+            Node::Add {
+                lhs: Box::new(var("a")),
+                rhs: Box::new(Node::Mul {
+                    lhs: Box::new(var("b")),
+                    rhs: Box::new(var("c")),
+                }),
+            },
+            "a + b * c",
+        ),
+        (
+            Node::Eq {
+                lhs: Box::new(Node::Lt {
+                    lhs: Box::new(var("a")),
+                    rhs: Box::new(var("b")),
+                }),
+                rhs: Box::new(Node::Const(Value::Bool(true))),
+            },
+            "(a < b) == true",
+        ),
+        (
+            Node::Cast {
+                expr: Box::new(Node::Add {
+                    lhs: Box::new(var("a")),
+                    rhs: Box::new(int(1)),
+                }),
+                ty: Type::Float,
+            },
+            "(a + 1) as float",
+        ),
+        (
+            Node::ExtractTuple {
+                expr: Box::new(Node::Cast {
+                    expr: Box::new(var("a")),
+                    ty: Type::Tuple(vec![Type::Int]),
+                }),
+                idx: 0,
+            },
+            "(a as (int,)).0",
+        ),
+        (
+            Node::ExtractArray {
+                expr: Box::new(Node::ExtractTuple {
+                    expr: Box::new(var("a")),
+                    idx: 1,
+                }),
+                idx: Box::new(int(-2)),
+            },
+            "a.1[-2]",
+        ),
+        (
+            Node::Add {
+                lhs: Box::new(var("a")),
+                rhs: Box::new(Node::Assign {
+                    name: "b",
+                    value: Box::new(int(1)),
+                }),
+            },
+            "a + (b = 1)",
+        ),
+    ];
+
+    for (node, expected) in cases {
+        assert_eq!(expected, node.to_string());
+    }
+}
+
+#[test]
+fn test_all_nodes() {
+    let body = Node::Block(vec![
+        Node::Destructure {
+            pat: Pattern::Tuple(vec![
+                Pattern::Var("a"),
+                Pattern::Ignore,
+                Pattern::Tuple(vec![Pattern::Var("b")]),
+            ]),
+            value: Box::new(var("input")),
+        },
+        Node::Destructure {
+            pat: Pattern::Var("c"),
+            value: Box::new(Node::Const(Value::Tuple(vec![]))),
+        },
+        Node::Declare {
+            name: "s",
+            ty: None,
+            value: Box::new(Node::Const(Value::Str("tab\t\"quote\"\n\\".into()))),
+        },
+        Node::Declare {
+            name: "ch",
+            ty: Some(Type::Char),
+            value: Box::new(Node::Const(Value::Char('\''))),
+        },
+        Node::DeclareUninit {
+            name: "u",
+            ty: None,
+        },
+        Node::DeclareUninit {
+            name: "v",
+            ty: Some(Type::Tuple(vec![Type::Int, Type::Bool])),
+        },
+        Node::Print(vec![
+            Node::Const(Value::Tuple(vec![
+                Value::Unit,
+                Value::Float(-1.5),
+                Value::Float(1e30),
+                Value::Tuple(vec![Value::Bool(false)]),
+            ])),
+            Node::ExtractTuple {
+                expr: Box::new(Node::Const(Value::Float(1.0))),
+                idx: 0,
+            },
+        ]),
+        Node::Println(vec![]),
+        // Statements starting with a block need parentheses
+        Node::Add {
+            lhs: Box::new(Node::Block(vec![var("a")])),
+            rhs: Box::new(int(1)),
+        },
+        Node::While {
+            cond: Box::new(Node::Or {
+                lhs: Box::new(Node::And {
+                    lhs: Box::new(Node::Gt {
+                        lhs: Box::new(var("a")),
+                        rhs: Box::new(int(0)),
+                    }),
+                    rhs: Box::new(Node::Neq {
+                        lhs: Box::new(var("b")),
+                        rhs: Box::new(int(1)),
+                    }),
+                }),
+                rhs: Box::new(Node::Block(vec![])),
+            }),
+            body: Box::new(Node::Block(vec![Node::Assign {
+                name: "a",
+                value: Box::new(Node::Mod {
+                    lhs: Box::new(Node::Div {
+                        lhs: Box::new(var("a")),
+                        rhs: Box::new(int(2)),
+                    }),
+                    rhs: Box::new(int(3)),
+                }),
+            }])),
+        },
+        Node::Try {
+            body: Box::new(Node::Block(vec![Node::ExtractArray {
+                expr: Box::new(var("s")),
+                idx: Box::new(int(100)),
+            }])),
+            name: "err",
+            handler: Box::new(Node::Block(vec![Node::ExtractArray {
+                expr: Box::new(Node::Format {
+                    fmt: "{:>5}{{}}".into(),
+                    args: vec![var("err")],
+                }),
+                idx: Box::new(int(0)),
+            }])),
+        },
+        Node::Block(vec![Node::GtEq {
+            lhs: Box::new(Node::LtEq {
+                lhs: Box::new(var("a")),
+                rhs: Box::new(var("b")),
+            }),
+            rhs: Box::new(Node::Lt {
+                lhs: Box::new(var("b")),
+                rhs: Box::new(var("c")),
+            }),
+        }]),
+        Node::Cast {
+            expr: Box::new(Node::Mul {
+                lhs: Box::new(var("a")),
+                rhs: Box::new(int(2)),
+            }),
+            ty: Type::Float,
+        },
+    ]);
+
+    assert_roundtrips(&Program {
+        input: Type::Tuple(vec![Type::Int, Type::Unit, Type::Tuple(vec![Type::Str])]),
+        output: Type::Float,
+        body,
+    });
+}
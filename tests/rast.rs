@@ -0,0 +1,115 @@
+use rast_jit_vm::{ast::*, examples, rast, vm};
+
+#[test]
+fn test_mul2() {
+    let prog = rast! { fn(input: int) -> int { input * 2 } };
+
+    assert_eq!(
+        parse("fn(input: int) -> int { input * 2 }")
+            .unwrap()
+            .without_spans(),
+        prog
+    );
+    assert_eq!(30, vm::compile::<i64, i64>(prog)(15).unwrap());
+}
+
+#[test]
+fn test_examples() {
+    let fibonacci = rast! {
+        fn(input: int) -> int {
+            var x = 0;
+            var y = 1;
+            var z = 1;
+            var n = input;
+
+            while n > 0 {
+                x = y;
+                y = z;
+                z = x + y;
+                n = n - 1;
+            }
+
+            x
+        }
+    };
+
+    assert_eq!(examples::fibonacci(), fibonacci);
+
+    let mandelbrot = rast! {
+        fn(input: (int, int, float, float, float, float, int)) {
+            var (viewport_width, viewport_height, x1, y1, x2, y2, max_iterations) = input;
+            var viewport_y = 0;
+
+            while viewport_y < viewport_height {
+                var y0 = y1 + (y2 - y1) * (viewport_y as float / viewport_height as float);
+                var viewport_x = 0;
+
+                while viewport_x < viewport_width {
+                    var x0 = x1 + (x2 - x1) * (viewport_x as float / viewport_width as float);
+                    var x = 0.0;
+                    var y = 0.0;
+                    var iterations = max_iterations;
+
+                    while x * x + y * y <= 4.0 && iterations > 0 {
+                        var xtemp = x * x - y * y + x0;
+                        y = 2.0 * (x * y) + y0;
+                        x = xtemp;
+                        iterations = iterations - 1;
+                    }
+
+                    print("#%=-:,. "[(8.0 * (iterations as float / max_iterations as float)) as int]);
+
+                    viewport_x = viewport_x + 1;
+                }
+
+                println();
+
+                viewport_y = viewport_y + 1;
+            }
+        }
+    };
+
+    assert_eq!(examples::mandelbrot(), mandelbrot);
+}
+
+#[test]
+fn test_nodes() {
+    assert_eq!(
+        parse_node("input.0.1").unwrap().without_spans(),
+        rast!(input.0 .1)
+    );
+    assert_eq!(
+        parse_node("-1 - -2.5e3").unwrap().without_spans(),
+        rast!(-1 - -2.5e3)
+    );
+
+    assert_eq!(
+        parse_node(r#"format("{:>5}\n", 'x', "a\"b")"#)
+            .unwrap()
+            .without_spans(),
+        rast!(format("{:>5}\n", 'x', "a\"b")),
+    );
+
+    assert_eq!(
+        parse_node("try { 1 / 0 } catch err { var (_, x) = (1, 2); x }")
+            .unwrap()
+            .without_spans(),
+        rast!(try { 1 / 0 } catch err { var (_, x) = (1, 2); x }),
+    );
+
+    assert_eq!(
+        parse_node("{ var x: (int, str) = (1, \"one\"); x }")
+            .unwrap()
+            .without_spans(),
+        rast!({ var x: (int, str) = (1, "one"); x }),
+    );
+
+    assert_eq!(
+        parse_node("{ var x; var y: int; x = y }")
+            .unwrap()
+            .without_spans(),
+        rast!({ var x; var y: int; x = y }),
+    );
+
+    assert_eq!(Node::Const(Value::Float(f32::INFINITY)), rast!(1e99));
+}
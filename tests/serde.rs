@@ -0,0 +1,132 @@
+#![cfg(feature = "serde")]
+
+use rast_jit_vm::{ast::*, examples};
+use std::borrow::Cow;
+
+const MUL2: &str = r#"{"input":"Int","output":"Int","body":{"Block":[{"Mul":{"lhs":{"Var":"input"},"rhs":{"Const":{"Int":2}}}}]}}"#;
+
+#[test]
+fn test_shape() {
+    let prog = parse("fn(input: int) -> int { input * 2 }")
+        .unwrap()
+        .without_spans();
+
+    assert_eq!(MUL2, serde_json::to_string(&prog).unwrap());
+    assert_eq!(prog, serde_json::from_str(MUL2).unwrap());
+
+    let cases = vec![
+        (Type::Unit, r#""Unit""#),
+        (
+            Type::Tuple(vec![Type::Str, Type::Tuple(vec![])]),
+            r#"{"Tuple":["Str",{"Tuple":[]}]}"#,
+        ),
+    ];
+
+    for (ty, expected) in cases {
+        assert_eq!(expected, serde_json::to_string(&ty).unwrap());
+    }
+
+    let cases = vec![
+        (Value::Unit, r#""Unit""#),
+        (Value::Float(1.5), r#"{"Float":1.5}"#),
+        (Value::Char('x'), r#"{"Char":"x"}"#),
+        (
+            Value::Tuple(vec![Value::Bool(true), Value::Str("x".into())]),
+            r#"{"Tuple":[{"Bool":true},{"Str":"x"}]}"#,
+        ),
+    ];
+
+    for (value, expected) in cases {
+        assert_eq!(expected, serde_json::to_string(&value).unwrap());
+    }
+
+    let cases = vec![
+        (
+            Node::Destructure {
+                pat: Pattern::Tuple(vec![Pattern::Var("a"), Pattern::Ignore]),
+                value: Box::new(Node::Var("b")),
+            },
+            r#"{"Destructure":{"pat":{"Tuple":[{"Var":"a"},"Ignore"]},"value":{"Var":"b"}}}"#,
+        ),
+        // Type annotations are skipped when missing
+        (
+            Node::Declare {
+                name: "x",
+                ty: None,
+                value: Box::new(Node::Const(Value::Unit)),
+            },
+            r#"{"Declare":{"name":"x","value":{"Const":"Unit"}}}"#,
+        ),
+        (
+            Node::Declare {
+                name: "x",
+                ty: Some(Type::Float),
+                value: Box::new(Node::Const(Value::Unit)),
+            },
+            r#"{"Declare":{"name":"x","ty":"Float","value":{"Const":"Unit"}}}"#,
+        ),
+        (
+            Node::DeclareUninit {
+                name: "x",
+                ty: None,
+            },
+            r#"{"DeclareUninit":{"name":"x"}}"#,
+        ),
+        (
+            Node::DeclareUninit {
+                name: "x",
+                ty: Some(Type::Int),
+            },
+            r#"{"DeclareUninit":{"name":"x","ty":"Int"}}"#,
+        ),
+        (
+            Node::Println(vec![Node::Const(Value::Unit)]),
+            r#"{"Println":[{"Const":"Unit"}]}"#,
+        ),
+    ];
+
+    for (node, expected) in cases {
+        assert_eq!(expected, serde_json::to_string(&node).unwrap());
+        assert_eq!(node, serde_json::from_str(expected).unwrap());
+    }
+}
+
+#[test]
+fn test_examples() {
+    for prog in [examples::fibonacci(), examples::mandelbrot()] {
+        let json = serde_json::to_string(&prog).unwrap();
+
+        assert_eq!(prog, serde_json::from_str::<Program>(&json).unwrap());
+    }
+}
+
+#[test]
+fn test_borrows_from_input() {
+    let json = r#"{"Block":[{"Declare":{"name":"foo","value":{"Const":{"Str":"bar"}}}},{"Const":{"Str":"a\nb"}}]}"#;
+    let node: Node = serde_json::from_str(json).unwrap();
+    let json_range = json.as_bytes().as_ptr_range();
+
+    let nodes = match node {
+        Node::Block(nodes) => nodes,
+        node => panic!("unexpected node: {:?}", node),
+    };
+
+    match &nodes[0] {
+        Node::Declare { name, value, .. } => {
+            assert!(json_range.contains(&name.as_ptr()));
+
+            match &**value {
+                Node::Const(Value::Str(Cow::Borrowed(value))) => {
+                    assert!(json_range.contains(&value.as_ptr()));
+                }
+
+                value => panic!("unexpected value: {:?}", value),
+            }
+        }
+
+        node => panic!("unexpected node: {:?}", node),
+    }
+
+    // Strings with escape sequences have to be allocated
+    assert_eq!(Node::Const(Value::Str(Cow::Owned("a\nb".into()))), nodes[1]);
+}
@@ -0,0 +1,308 @@
+use rast_jit_vm::{ast::*, examples, rast, vm};
+use std::borrow::Cow;
+
+#[test]
+fn test_examples() {
+    for prog in [examples::fibonacci(), examples::mandelbrot()] {
+        assert_eq!(prog, parse_sexpr(&to_sexpr(&prog)).unwrap());
+
+        // Including the spans
+        let src = prog.to_string();
+        let prog = parse(&src).unwrap();
+
+        assert_eq!(prog, parse_sexpr(&to_sexpr(&prog)).unwrap());
+    }
+}
+
+#[test]
+fn test_writer() {
+    let prog = rast! {
+        fn(input: int) -> int {
+            var x = 0;
+
+            while x < input {
+                x = x + 1;
+            }
+
+            x
+        }
+    };
+
+    let expected = "\
+(program int int (block
+  (declare x (const 0))
+  (while (< (var x) (var input)) (block
+    (assign x (+ (var x) (const 1)))))
+  (var x)))";
+
+    assert_eq!(expected, to_sexpr(&prog));
+}
+
+#[test]
+fn test_every_variant() {
+    let src = r#"
+        ; Comments are ignored
+        (program (tuple int (tuple str char)) str (block
+          (destructure (tuple n (tuple _ "_")) (var input))
+          (declare "my var" (const (tuple unit true false -12 1.5 -0.0 1e30 inf -inf)))
+          (declare s str (const "a\"b\n\u{1f980}"))
+          (assign "my var" (const (char "\t")))
+          (print (extract-tuple (extract-tuple (var input) 1) 0))
+          (println)
+          (try (block (/ (const 1) (const 0))) err (println (var err)))
+          (while (|| (&& (== (var n) (const 1)) (!= (var n) (const 2)))
+                     (|| (> (var n) (const 3)) (>= (var n) (const 4))))
+            (assign n (- (* (var n) (const 2)) (% (var n) (const 3)))))
+          (while (< (var n) (<= (const 1) (const 2))) (block))
+          (span 1 2 3 4 (cast (extract-array (var s) (+ (var n) (const 0))) int))
+          (declare-uninit u)
+          (declare-uninit "my other var" (tuple int))
+          (format "{}-{:>5}" (var n) (var s))))
+    "#;
+
+    let prog = parse_sexpr(src).unwrap();
+
+    assert_eq!(prog, parse_sexpr(&to_sexpr(&prog)).unwrap());
+
+    let nodes = match &prog.body {
+        Node::Block(nodes) => nodes,
+        _ => unreachable!(),
+    };
+
+    assert_eq!(
+        nodes[0],
+        Node::Destructure {
+            pat: Pattern::Tuple(vec![
+                Pattern::Var("n"),
+                Pattern::Tuple(vec![Pattern::Ignore, Pattern::Var("_")]),
+            ]),
+            value: Box::new(Node::Var("input")),
+        }
+    );
+
+    assert_eq!(
+        nodes[1],
+        Node::Declare {
+            name: "my var",
+            ty: None,
+            value: Box::new(Node::Const(Value::Tuple(vec![
+                Value::Unit,
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Int(-12),
+                Value::Float(1.5),
+                Value::Float(-0.0),
+                Value::Float(1e30),
+                Value::Float(f32::INFINITY),
+                Value::Float(f32::NEG_INFINITY),
+            ]))),
+        }
+    );
+
+    assert_eq!(
+        nodes[2],
+        Node::Declare {
+            name: "s",
+            ty: Some(Type::Str),
+            value: Box::new(Node::Const(Value::Str("a\"b\n🦀".into()))),
+        }
+    );
+
+    assert_eq!(
+        nodes[9].span(),
+        Some(Span {
+            start: 1,
+            end: 2,
+            line: 3,
+            column: 4,
+        })
+    );
+
+    // Names that aren't valid atoms get quoted
+    let written = to_sexpr(&prog);
+
+    assert!(written.contains(r#"(destructure (tuple n (tuple _ "_")) (var input))"#));
+    assert!(written.contains(r#"(assign "my var" (const (char "\t")))"#));
+    assert!(written.contains(r#"(const "a\"b\n🦀")"#));
+
+    assert_eq!(
+        nodes[11],
+        Node::DeclareUninit {
+            name: "my other var",
+            ty: Some(Type::Tuple(vec![Type::Int])),
+        }
+    );
+
+    // NaN doesn't compare equal to itself, so it's checked separately
+    let prog = parse_sexpr("(program unit float (const NaN))").unwrap();
+
+    assert_eq!("(program unit float (const NaN))", to_sexpr(&prog));
+    assert!(vm::eval::<_, f32>(&prog, ()).unwrap().is_nan());
+}
+
+#[test]
+fn test_borrows_from_source() {
+    let src = r#"(program unit str (block (declare x (const "foo")) (var x)))"#;
+    let src_range = src.as_bytes().as_ptr_range();
+    let prog = parse_sexpr(src).unwrap();
+
+    assert_eq!("foo", vm::eval::<_, String>(&prog, ()).unwrap());
+
+    let (name, value) = match &prog.body {
+        Node::Block(nodes) => match &nodes[0] {
+            Node::Declare { name, value, .. } => match &**value {
+                Node::Const(Value::Str(Cow::Borrowed(value))) => (name, value),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    assert!(src_range.contains(&name.as_ptr()));
+    assert!(src_range.contains(&value.as_ptr()));
+}
+
+#[test]
+fn test_errors() {
+    let cases = [
+        ("", "1:1: expected `(program ...)`, found end of input"),
+        ("program", "1:1: expected `(program ...)`, found `program`"),
+        (
+            "(prog int int (block))",
+            "1:2: expected `program`, found `prog`",
+        ),
+        ("(program int int", "1:1: unclosed `(`"),
+        ("(program int int (block)))", "1:26: unexpected `)`"),
+        (
+            "(program int int (block)) (block)",
+            "1:27: expected end of input, found list",
+        ),
+        ("(program int)", "1:13: expected output type, found `)`"),
+        (
+            "(program int integer (block))",
+            "1:14: expected type, found `integer`",
+        ),
+        (
+            "(program int (list int) (block))",
+            "1:15: expected `tuple`, found `list`",
+        ),
+        (
+            "(program int int\n  (block\n    (lol 1)))",
+            "3:6: unknown node `lol`",
+        ),
+        (
+            "(program int int (block\n  (+ (const 1))))",
+            "2:15: expected right-hand side, found `)`",
+        ),
+        (
+            "(program int int (var x y))",
+            "1:25: expected `)`, found `y`",
+        ),
+        (
+            "(program int int (const 99999999999999999999))",
+            "1:25: integer literal is too large",
+        ),
+        (
+            "(program int int (const 1.2.3))",
+            "1:25: expected value, found `1.2.3`",
+        ),
+        (
+            "(program int char (const (char \"ab\")))",
+            "1:32: expected a single-character string",
+        ),
+        (
+            "(program int int (extract-tuple (var input) -1))",
+            "1:45: expected tuple index, found `-1`",
+        ),
+        (
+            "(program int int (var \"a\\nb\"))",
+            "1:23: identifiers cannot contain escape sequences",
+        ),
+        (
+            "(program int int (var (block)))",
+            "1:23: expected identifier, found list",
+        ),
+        (
+            "(program int int (format x))",
+            "1:26: expected format string, found `x`",
+        ),
+        ("(program int int ())", "1:18: expected node, found list"),
+        (
+            "(program int int ((var x)))",
+            "1:19: expected name of node, found list",
+        ),
+        (
+            "(program int str (const \"abc))",
+            "1:25: unterminated string literal",
+        ),
+        (
+            "(program int str (const \"\\q\"))",
+            "1:26: unknown escape sequence",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        let actual = parse_sexpr(src).map(|_| ()).unwrap_err().to_string();
+
+        assert_eq!(expected, actual, "{}", src);
+    }
+}
+
+#[test]
+fn test_nesting_limit() {
+    let nested = |depth: usize| {
+        format!(
+            "(program unit int {}(const 1){})",
+            "(block ".repeat(depth),
+            ")".repeat(depth)
+        )
+    };
+
+    // 256 lists, counting `(program ...)` and `(const ...)`
+    let src = nested(254);
+    let prog = parse_sexpr(&src).unwrap();
+
+    assert_eq!(prog, parse_sexpr(&to_sexpr(&prog)).unwrap());
+
+    // Anything deeper is rejected (instead of overflowing the stack)
+    assert_eq!(
+        "1:1804: nesting is too deep",
+        parse_sexpr(&nested(255)).unwrap_err().to_string()
+    );
+
+    assert_eq!(
+        "1:1804: nesting is too deep",
+        parse_sexpr(&nested(10000)).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn test_names_with_special_characters() {
+    // Names that need quoting are written as-is, without escape sequences,
+    // so that they can be borrowed back from the source
+    let prog = Program {
+        input: Type::Unit,
+        output: Type::Int,
+        body: Node::Declare {
+            name: "a\nb\tc",
+            ty: None,
+            value: Box::new(Node::Const(Value::Int(1))),
+        },
+    };
+
+    let written = to_sexpr(&prog);
+
+    assert!(written.contains("(declare \"a\nb\tc\" (const 1))"));
+    assert_eq!(prog, parse_sexpr(&written).unwrap());
+}
+
+#[test]
+#[should_panic(expected = r#"Identifier "a\"b" cannot be represented as an s-expression"#)]
+fn test_unrepresentable_names() {
+    to_sexpr(&Program {
+        input: Type::Unit,
+        output: Type::Unit,
+        body: Node::Var("a\"b"),
+    });
+}
@@ -8,8 +8,8 @@ fn test_const() {
         body: Node::Const(Value::Int(1234)),
     };
 
-    assert_eq!(1234, vm::eval(&prog, ()));
-    assert_eq!(1234, (vm::compile(prog))(()));
+    assert_eq!(1234, vm::eval(&prog, ()).unwrap());
+    assert_eq!(1234, (vm::compile(prog))(()).unwrap());
 }
 
 #[test]
@@ -20,8 +20,8 @@ fn test_var() {
         body: Node::Var("input"),
     };
 
-    assert_eq!(1234, vm::eval(&prog, 1234));
-    assert_eq!(1234, (vm::compile(prog))(1234));
+    assert_eq!(1234, vm::eval(&prog, 1234).unwrap());
+    assert_eq!(1234, (vm::compile(prog))(1234).unwrap());
 }
 
 #[test]
@@ -35,8 +35,8 @@ fn test_extract_tuple() {
         },
     };
 
-    assert_eq!(123, vm::eval(&prog, ("Hello!", 123, 45.67)));
-    assert_eq!(123, (vm::compile(prog))(("Hello!", 123, 45.67)));
+    assert_eq!(123, vm::eval(&prog, ("Hello!", 123, 45.67)).unwrap());
+    assert_eq!(123, (vm::compile(prog))(("Hello!", 123, 45.67)).unwrap());
 }
 
 #[test]
@@ -50,8 +50,8 @@ fn test_extract_array() {
         },
     };
 
-    assert_eq!('l', vm::eval(&prog, "Hello!"));
-    assert_eq!('l', (vm::compile(prog))("Hello!"));
+    assert_eq!('l', vm::eval(&prog, "Hello!").unwrap());
+    assert_eq!('l', (vm::compile(prog))("Hello!").unwrap());
 }
 
 #[test]
@@ -65,8 +65,8 @@ fn test_cast_float_to_int() {
         },
     };
 
-    assert_eq!(123, vm::eval(&prog, 123.45));
-    assert_eq!(123, (vm::compile(prog))(123.45));
+    assert_eq!(123, vm::eval(&prog, 123.45).unwrap());
+    assert_eq!(123, (vm::compile(prog))(123.45).unwrap());
 }
 
 #[test]
@@ -80,8 +80,8 @@ fn test_cast_int_to_float() {
         },
     };
 
-    assert_eq!(123.0f32, vm::eval(&prog, 123));
-    assert_eq!(123.0f32, (vm::compile(prog))(123));
+    assert_eq!(123.0f32, vm::eval(&prog, 123).unwrap());
+    assert_eq!(123.0f32, (vm::compile(prog))(123).unwrap());
 }
 
 #[test]
@@ -95,8 +95,8 @@ fn test_add_int_int() {
         },
     };
 
-    assert_eq!(13, vm::eval(&prog, 3));
-    assert_eq!(13, (vm::compile(prog))(3));
+    assert_eq!(13, vm::eval(&prog, 3).unwrap());
+    assert_eq!(13, (vm::compile(prog))(3).unwrap());
 }
 
 #[test]
@@ -110,8 +110,8 @@ fn test_add_float_float() {
         },
     };
 
-    assert_eq!(13.0, vm::eval(&prog, 3.0));
-    assert_eq!(13.0, (vm::compile(prog))(3.0));
+    assert_eq!(13.0, vm::eval(&prog, 3.0).unwrap());
+    assert_eq!(13.0, (vm::compile(prog))(3.0).unwrap());
 }
 
 #[test]
@@ -125,8 +125,8 @@ fn test_sub_int_int() {
         },
     };
 
-    assert_eq!(-7, vm::eval(&prog, 3));
-    assert_eq!(-7, (vm::compile(prog))(3));
+    assert_eq!(-7, vm::eval(&prog, 3).unwrap());
+    assert_eq!(-7, (vm::compile(prog))(3).unwrap());
 }
 
 #[test]
@@ -140,8 +140,8 @@ fn test_sub_float_float() {
         },
     };
 
-    assert_eq!(-7.0, vm::eval(&prog, 3.0));
-    assert_eq!(-7.0, (vm::compile(prog))(3.0));
+    assert_eq!(-7.0, vm::eval(&prog, 3.0).unwrap());
+    assert_eq!(-7.0, (vm::compile(prog))(3.0).unwrap());
 }
 
 #[test]
@@ -155,8 +155,8 @@ fn test_mul_int_int() {
         },
     };
 
-    assert_eq!(30, vm::eval(&prog, 3));
-    assert_eq!(30, (vm::compile(prog))(3));
+    assert_eq!(30, vm::eval(&prog, 3).unwrap());
+    assert_eq!(30, (vm::compile(prog))(3).unwrap());
 }
 
 #[test]
@@ -170,8 +170,8 @@ fn test_mul_float_float() {
         },
     };
 
-    assert_eq!(30.0, vm::eval(&prog, 3.0));
-    assert_eq!(30.0, (vm::compile(prog))(3.0));
+    assert_eq!(30.0, vm::eval(&prog, 3.0).unwrap());
+    assert_eq!(30.0, (vm::compile(prog))(3.0).unwrap());
 }
 
 #[test]
@@ -185,8 +185,8 @@ fn test_div_int_int() {
         },
     };
 
-    assert_eq!(3, vm::eval(&prog, 38));
-    assert_eq!(3, (vm::compile(prog))(38));
+    assert_eq!(3, vm::eval(&prog, 38).unwrap());
+    assert_eq!(3, (vm::compile(prog))(38).unwrap());
 }
 
 #[test]
@@ -200,8 +200,8 @@ fn test_div_float_float() {
         },
     };
 
-    assert_eq!(5.0, vm::eval(&prog, 50.0));
-    assert_eq!(5.0, (vm::compile(prog))(50.0));
+    assert_eq!(5.0, vm::eval(&prog, 50.0).unwrap());
+    assert_eq!(5.0, (vm::compile(prog))(50.0).unwrap());
 }
 
 #[test]
@@ -215,8 +215,8 @@ fn test_mod_int_int() {
         },
     };
 
-    assert_eq!(8, vm::eval(&prog, 38));
-    assert_eq!(8, (vm::compile(prog))(38));
+    assert_eq!(8, vm::eval(&prog, 38).unwrap());
+    assert_eq!(8, (vm::compile(prog))(38).unwrap());
 }
 
 #[test]
@@ -233,8 +233,8 @@ fn test_eq_int_int() {
     for n in 0..20 {
         let actual = n == 10;
 
-        assert_eq!(vm::eval::<_, bool>(&prog, n), actual);
-        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n), actual);
+        assert_eq!(vm::eval::<_, bool>(&prog, n).unwrap(), actual);
+        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n).unwrap(), actual);
     }
 }
 
@@ -252,8 +252,8 @@ fn test_gt_int_int() {
     for n in 0..20 {
         let actual = n > 10;
 
-        assert_eq!(vm::eval::<_, bool>(&prog, n), actual);
-        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n), actual);
+        assert_eq!(vm::eval::<_, bool>(&prog, n).unwrap(), actual);
+        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n).unwrap(), actual);
     }
 }
 
@@ -271,8 +271,8 @@ fn test_gteq_int_int() {
     for n in 0..20 {
         let actual = n >= 10;
 
-        assert_eq!(vm::eval::<_, bool>(&prog, n), actual);
-        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n), actual);
+        assert_eq!(vm::eval::<_, bool>(&prog, n).unwrap(), actual);
+        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n).unwrap(), actual);
     }
 }
 
@@ -290,8 +290,8 @@ fn test_lt_int_int() {
     for n in 0..20 {
         let actual = n < 10;
 
-        assert_eq!(vm::eval::<_, bool>(&prog, n), actual);
-        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n), actual);
+        assert_eq!(vm::eval::<_, bool>(&prog, n).unwrap(), actual);
+        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n).unwrap(), actual);
     }
 }
 
@@ -309,8 +309,8 @@ fn test_lteq_int_int() {
     for n in 0..20 {
         let actual = n <= 10;
 
-        assert_eq!(vm::eval::<_, bool>(&prog, n), actual);
-        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n), actual);
+        assert_eq!(vm::eval::<_, bool>(&prog, n).unwrap(), actual);
+        assert_eq!((vm::compile::<_, bool>(prog.clone()))(n).unwrap(), actual);
     }
 }
 
@@ -325,11 +325,11 @@ fn test_and_bool_bool() {
         },
     };
 
-    assert!(vm::eval::<_, bool>(&prog, true));
-    assert!(!vm::eval::<_, bool>(&prog, false));
+    assert!(vm::eval::<_, bool>(&prog, true).unwrap());
+    assert!(!vm::eval::<_, bool>(&prog, false).unwrap());
 
-    assert!((vm::compile::<_, bool>(prog.clone()))(true));
-    assert!(!(vm::compile::<_, bool>(prog))(false));
+    assert!((vm::compile::<_, bool>(prog.clone()))(true).unwrap());
+    assert!(!(vm::compile::<_, bool>(prog))(false).unwrap());
 }
 
 #[test]
@@ -343,9 +343,258 @@ fn test_or_bool_bool() {
         },
     };
 
-    assert!(vm::eval::<_, bool>(&prog, true));
-    assert!(vm::eval::<_, bool>(&prog, false));
+    assert!(vm::eval::<_, bool>(&prog, true).unwrap());
+    assert!(vm::eval::<_, bool>(&prog, false).unwrap());
 
-    assert!((vm::compile::<_, bool>(prog.clone()))(true));
-    assert!((vm::compile::<_, bool>(prog))(false));
+    assert!((vm::compile::<_, bool>(prog.clone()))(true).unwrap());
+    assert!((vm::compile::<_, bool>(prog))(false).unwrap());
+}
+
+#[test]
+fn test_try_ok() {
+    let prog = Program {
+        input: Type::Int,
+        output: Type::Int,
+        body: Node::Try {
+            body: Box::new(Node::Div {
+                lhs: Box::new(Node::Const(Value::Int(100))),
+                rhs: Box::new(Node::Var("input")),
+            }),
+            name: "err",
+            handler: Box::new(Node::Const(Value::Int(-1))),
+        },
+    };
+
+    assert_eq!(25, vm::eval(&prog, 4).unwrap());
+    assert_eq!(25, (vm::compile(prog))(4).unwrap());
+}
+
+#[test]
+fn test_try_division_by_zero() {
+    let prog = Program {
+        input: Type::Int,
+        output: Type::Int,
+        body: Node::Try {
+            body: Box::new(Node::Mod {
+                lhs: Box::new(Node::Const(Value::Int(100))),
+                rhs: Box::new(Node::Var("input")),
+            }),
+            name: "err",
+            handler: Box::new(Node::Const(Value::Int(-1))),
+        },
+    };
+
+    assert_eq!(-1, vm::eval(&prog, 0).unwrap());
+    assert_eq!(-1, (vm::compile(prog))(0).unwrap());
+}
+
+#[test]
+fn test_try_index_out_of_bounds() {
+    let prog = Program {
+        input: Type::Int,
+        output: Type::Str,
+        body: Node::Try {
+            body: Box::new(Node::Block(vec![
+                Node::ExtractArray {
+                    expr: Box::new(Node::Const(Value::Str("Hello!".into()))),
+                    idx: Box::new(Node::Var("input")),
+                },
+                Node::Const(Value::Str("ok".into())),
+            ])),
+            name: "err",
+            handler: Box::new(Node::Var("err")),
+        },
+    };
+
+    for input in [0, 5] {
+        assert_eq!("ok", vm::eval::<_, &str>(&prog, input).unwrap());
+        assert_eq!("ok", (vm::compile::<_, &str>(prog.clone()))(input).unwrap());
+    }
+
+    for input in [-1, 6] {
+        assert_eq!(
+            "Index out of bounds",
+            vm::eval::<_, &str>(&prog, input).unwrap()
+        );
+        assert_eq!(
+            "Index out of bounds",
+            (vm::compile::<_, &str>(prog.clone()))(input).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_try_keeps_assignments() {
+    // var x = 1;
+    // try { x = 2; var y = 3; x = 10 / input; } catch err { x = x + 10; }
+    // x
+    let prog = Program {
+        input: Type::Int,
+        output: Type::Int,
+        body: Node::Block(vec![
+            Node::Declare {
+                name: "x",
+                ty: None,
+                value: Box::new(Node::Const(Value::Int(1))),
+            },
+            Node::Try {
+                body: Box::new(Node::Block(vec![
+                    Node::Assign {
+                        name: "x",
+                        value: Box::new(Node::Const(Value::Int(2))),
+                    },
+                    Node::Declare {
+                        name: "y",
+                        ty: None,
+                        value: Box::new(Node::Const(Value::Int(3))),
+                    },
+                    Node::Assign {
+                        name: "x",
+                        value: Box::new(Node::Div {
+                            lhs: Box::new(Node::Const(Value::Int(10))),
+                            rhs: Box::new(Node::Var("input")),
+                        }),
+                    },
+                ])),
+                name: "err",
+                handler: Box::new(Node::Assign {
+                    name: "x",
+                    value: Box::new(Node::Add {
+                        lhs: Box::new(Node::Var("x")),
+                        rhs: Box::new(Node::Const(Value::Int(10))),
+                    }),
+                }),
+            },
+            Node::Var("x"),
+        ]),
+    };
+
+    assert_eq!(5, vm::eval(&prog, 2).unwrap());
+    assert_eq!(5, (vm::compile(prog.clone()))(2).unwrap());
+
+    assert_eq!(12, vm::eval(&prog, 0).unwrap());
+    assert_eq!(12, (vm::compile(prog))(0).unwrap());
+}
+
+#[test]
+fn test_uncaught_division_by_zero() {
+    // input + 1 / input
+    let prog = Program {
+        input: Type::Int,
+        output: Type::Int,
+        body: Node::Add {
+            lhs: Box::new(Node::Var("input")),
+            rhs: Box::new(Node::Div {
+                lhs: Box::new(Node::Const(Value::Int(1))),
+                rhs: Box::new(Node::Var("input")),
+            }),
+        },
+    };
+
+    let expected = vm::RuntimeError {
+        kind: vm::RuntimeErrorKind::DivisionByZero,
+        node: 2,
+        span: None,
+    };
+
+    assert_eq!(Err(expected), vm::eval::<_, i64>(&prog, 0));
+    assert_eq!(Err(expected), (vm::compile::<_, i64>(prog))(0));
+}
+
+#[test]
+fn test_destructure() {
+    // var (a, _, (b, c)) = input;
+    // a + b * c
+    let prog = Program {
+        input: Type::Tuple(vec![
+            Type::Int,
+            Type::Str,
+            Type::Tuple(vec![Type::Int, Type::Int]),
+        ]),
+        output: Type::Int,
+        body: Node::Block(vec![
+            Node::Destructure {
+                pat: Pattern::Tuple(vec![
+                    Pattern::Var("a"),
+                    Pattern::Ignore,
+                    Pattern::Tuple(vec![Pattern::Var("b"), Pattern::Var("c")]),
+                ]),
+                value: Box::new(Node::Var("input")),
+            },
+            Node::Add {
+                lhs: Box::new(Node::Var("a")),
+                rhs: Box::new(Node::Mul {
+                    lhs: Box::new(Node::Var("b")),
+                    rhs: Box::new(Node::Var("c")),
+                }),
+            },
+        ]),
+    };
+
+    assert_eq!(23, vm::eval(&prog, (3, "Hello!", (4, 5))).unwrap());
+    assert_eq!(23, (vm::compile(prog))((3, "Hello!", (4, 5))).unwrap());
+}
+
+#[test]
+#[should_panic(expected = "has 2 element(s), but the pattern expects 3")]
+fn test_destructure_arity_mismatch() {
+    let prog = Program {
+        input: Type::Tuple(vec![Type::Int, Type::Int]),
+        output: Type::Unit,
+        body: Node::Destructure {
+            pat: Pattern::Tuple(vec![
+                Pattern::Var("a"),
+                Pattern::Var("b"),
+                Pattern::Var("c"),
+            ]),
+            value: Box::new(Node::Var("input")),
+        },
+    };
+
+    let _ = vm::compile::<(i64, i64), ()>(prog);
+}
+
+#[test]
+fn test_format() {
+    let prog = Program {
+        input: Type::Tuple(vec![Type::Int, Type::Float]),
+        output: Type::Str,
+        body: Node::Format {
+            fmt: "[{:>4}] x [{:.3}] = {:?<8}".into(),
+            args: vec![
+                Node::ExtractTuple {
+                    expr: Box::new(Node::Var("input")),
+                    idx: 0,
+                },
+                Node::ExtractTuple {
+                    expr: Box::new(Node::Var("input")),
+                    idx: 1,
+                },
+                Node::Var("input"),
+            ],
+        },
+    };
+
+    let expected = "[  12] x [0.500] = (12, 0.5)";
+
+    assert_eq!(expected, vm::eval::<_, String>(&prog, (12, 0.5)).unwrap());
+    assert_eq!(
+        expected,
+        (vm::compile::<_, String>(prog))((12, 0.5)).unwrap()
+    );
+}
+
+#[test]
+#[should_panic(expected = "Format string expects 2 argument(s), got 1")]
+fn test_format_arity_mismatch() {
+    let prog = Program {
+        input: Type::Int,
+        output: Type::Str,
+        body: Node::Format {
+            fmt: "{} {}".into(),
+            args: vec![Node::Var("input")],
+        },
+    };
+
+    let _ = vm::compile::<i64, String>(prog);
 }
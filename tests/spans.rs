@@ -0,0 +1,173 @@
+use rast_jit_vm::{ast::*, vm};
+use std::panic::{self, AssertUnwindSafe};
+
+fn panic_message(f: impl FnOnce()) -> String {
+    let err = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+
+    err.downcast_ref::<String>()
+        .cloned()
+        .unwrap_or_else(|| err.downcast_ref::<&str>().unwrap().to_string())
+}
+
+fn compile_error(src: &str) -> String {
+    let prog = parse(src).unwrap();
+
+    panic_message(|| {
+        let _ = vm::compile::<(), Value>(prog)(());
+    })
+}
+
+fn eval_error(src: &str) -> String {
+    let prog = parse(src).unwrap();
+
+    panic_message(|| {
+        let _ = vm::eval::<(), Value>(&prog, ());
+    })
+}
+
+#[test]
+fn test_parse() {
+    let src = "fn(input: int) -> int {\n    var x = input;\n    x * (2 + x)\n}";
+    let prog = parse(src).unwrap();
+
+    let span = |node: &Node| {
+        let span = node.span().unwrap();
+
+        (&src[span.start..span.end], span.to_string())
+    };
+
+    assert_eq!((&src[22..], "1:23".into()), span(&prog.body));
+
+    let nodes = match prog.body.unspanned() {
+        Node::Block(nodes) => nodes,
+        node => panic!("unexpected node: {:?}", node),
+    };
+
+    assert_eq!(("var x = input", "2:5".into()), span(&nodes[0]));
+    assert_eq!(("x * (2 + x)", "3:5".into()), span(&nodes[1]));
+
+    match nodes[1].unspanned() {
+        Node::Mul { lhs, rhs } => {
+            assert_eq!(("x", "3:5".into()), span(lhs));
+            assert_eq!(("2 + x", "3:10".into()), span(rhs));
+        }
+
+        node => panic!("unexpected node: {:?}", node),
+    }
+
+    // Spans survive serialization
+    assert_eq!(prog, decode(&encode(&prog)).unwrap());
+}
+
+#[test]
+fn test_compile_errors() {
+    let cases = [
+        (
+            "fn(input: ()) {\n    var x = 1;\n    x = y;\n}",
+            "3:9: Unknown variable: y",
+        ),
+        (
+            "fn(input: ()) {\n    var x = 1;\n    x = 1.5;\n}",
+            "3:5: Type mismatch: Cannot assign `Float` to `Int`",
+        ),
+        (
+            "fn(input: ()) { var x = 1; var x = 2 }",
+            "1:28: Variable `x` has been already defined",
+        ),
+        (
+            "fn(input: ()) { print(1 + (2 < 'c')) }",
+            "1:28: Unknown operation `Int` `Lt` `Char`",
+        ),
+        (
+            "fn(input: ()) { var (a, b) = (1, 2, 3) }",
+            "1:17: Invalid pattern: Tuple `[Int, Int, Int]` has 3 element(s), but the pattern expects 2",
+        ),
+        (
+            "fn(input: ()) -> int { 1.5 }",
+            "1:22: Output type mismatch: Program declared to return `Int`, but in reality it returns `Float`",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(expected, compile_error(src), "{}", src);
+    }
+}
+
+#[test]
+fn test_runtime_errors() {
+    let cases = [
+        (
+            "fn(input: ()) {\n    var x = 0;\n    print(1 + 10 / x);\n}",
+            "3:15: Division by zero",
+        ),
+        (
+            "fn(input: ()) { print(\"abc\"[3]) }",
+            "1:23: Index out of bounds",
+        ),
+        (
+            "fn(input: ()) {\n    var x = 0 - 9223372036854775807 - 1;\n    print(x / -1);\n}",
+            "3:11: Arithmetic overflow",
+        ),
+        (
+            "fn(input: ()) {\n    var x = 0 - 9223372036854775807 - 1;\n    print(x % -1);\n}",
+            "3:11: Arithmetic overflow",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        let prog = parse(src).unwrap();
+        let err = vm::eval::<(), ()>(&prog, ()).unwrap_err();
+
+        assert_eq!(expected, err.to_string(), "{}", src);
+        assert_eq!(err, vm::compile::<(), ()>(prog)(()).unwrap_err(), "{}", src);
+    }
+
+    // ... but `catch` sees just the message
+    let src = "fn(input: ()) -> str try { 1 % 0; \"\" } catch err { err }";
+    let prog = parse(src).unwrap();
+
+    assert_eq!(
+        "Division by zero",
+        vm::eval::<_, String>(&prog, ()).unwrap()
+    );
+    assert_eq!(
+        "Division by zero",
+        vm::compile::<_, String>(prog)(()).unwrap()
+    );
+
+    let src = "fn(input: int) -> str try { (0 - 9223372036854775807 - 1) / input; \"\" } catch err { err }";
+    let prog = parse(src).unwrap();
+
+    assert_eq!(
+        "Arithmetic overflow",
+        vm::eval::<_, String>(&prog, -1).unwrap()
+    );
+    assert_eq!(
+        "Arithmetic overflow",
+        vm::compile::<_, String>(prog)(-1).unwrap()
+    );
+}
+
+#[test]
+fn test_eval_errors() {
+    assert_eq!(
+        "1:24: Cannot cast `Bool` to `Int`",
+        eval_error("fn(input: ()) -> int { true as int }")
+    );
+}
+
+#[test]
+fn test_nodes_without_spans() {
+    let prog = Program {
+        input: Type::Unit,
+        output: Type::Unit,
+        body: Node::Var("x"),
+    };
+
+    assert_eq!(
+        "Unknown variable: x",
+        panic_message(|| {
+            vm::compile::<(), ()>(prog)(()).unwrap();
+        })
+    );
+}
@@ -0,0 +1,302 @@
+use rast_jit_vm::{ast::*, examples, vm};
+use std::panic::{self, AssertUnwindSafe};
+
+fn errors(src: &str) -> Vec<String> {
+    let prog = parse(src).unwrap();
+
+    typecheck(&prog)
+        .unwrap_err()
+        .iter()
+        .map(|err| err.to_string())
+        .collect()
+}
+
+#[test]
+fn test_types() {
+    let prog = examples::fibonacci();
+    let typed = typecheck(&prog).unwrap();
+
+    // One type per node, in pre-order
+    struct Count(usize);
+
+    impl<'s> Visitor<'s> for Count {
+        fn visit_node(&mut self, node: &Node<'s>) {
+            self.0 += 1;
+            walk_node(self, node);
+        }
+    }
+
+    let mut count = Count(0);
+
+    count.visit_program(&prog);
+
+    assert_eq!(count.0, typed.types().len());
+    assert_eq!(&[Type::Int, Type::Unit, Type::Int], &typed.types()[..3]);
+    assert_eq!(Some(&Type::Int), typed.types().last());
+
+    // Spans get the type of the node they wrap
+    let prog = parse("fn(input: (int, str)) -> str { input.1 }").unwrap();
+    let typed = typecheck(&prog).unwrap();
+
+    let expected = [
+        Type::Str,
+        Type::Str,
+        Type::Str,
+        Type::Str,
+        Type::Tuple(vec![Type::Int, Type::Str]),
+        Type::Tuple(vec![Type::Int, Type::Str]),
+    ];
+
+    assert_eq!(&expected, typed.types());
+}
+
+#[test]
+fn test_errors() {
+    let src = r#"fn(input: int) -> str {
+        var x = input + true;
+        var y = x * 2;
+        var (a, b) = (1, 2, 3);
+        input = 1;
+        z = 2.0 as int;
+        while 1 { }
+        try { 1 } catch err { "oops" };
+        x[y];
+        format("{}")
+    }"#;
+
+    let expected = [
+        "2:17: Unknown operation `Int` `Add` `Bool`",
+        "4:9: Invalid pattern: Tuple `[Int, Int, Int]` has 3 element(s), but the pattern expects 2",
+        "5:9: `input` variable is read-only",
+        "6:9: Unknown variable: z",
+        "7:9: Type mismatch: Loop's condition was expected to be `bool`",
+        "8:9: Type mismatch: `try` returns `Int`, but `catch` returns `Str`",
+        "10:9: Invalid format: Format string expects 1 argument(s), got 0",
+    ];
+
+    // Errors don't cascade, e.g. `x * 2` is not reported, since `x` is
+    // ill-typed already
+    assert_eq!(Vec::from(expected), errors(src));
+
+    assert_eq!(
+        vec![
+            "1:26: Invalid type: Expected `str`, got `Int`",
+            "1:26: Invalid type: Expected `int`, got `Bool`",
+        ],
+        errors("fn(input: int) -> char { input[true] }")
+    );
+
+    assert_eq!(
+        vec![
+            "1:36: Variable `x` has been already defined",
+            "1:23: Output type mismatch: Program declared to return `Str`, but in reality it returns `Unit`",
+        ],
+        errors("fn(input: int) -> str { var x = 1; var x = 2; }")
+    );
+
+    // Widths are capped, instead of trying to allocate that much padding at
+    // runtime
+    assert_eq!(
+        vec![
+            "1:24: Invalid format string `{:18446744073709551615}`: number \
+             `18446744073709551615` is too large",
+        ],
+        errors(r#"fn(input: ()) -> str { format("{:18446744073709551615}", 1) }"#)
+    );
+}
+
+#[test]
+fn test_declare_annotations() {
+    let src = r#"fn(input: int) -> float {
+        var x: float = 0;
+        var y: (int, float) = (1, 2.5);
+        var z: int = unknown;
+        x = x + y.1;
+        z = 1;
+        x * 2
+    }"#;
+
+    // The variables get their declared types, so their uses are checked
+    // against them
+    let expected = [
+        "2:9: Type mismatch: Variable `x` is declared as `Float`, but its value is `Int`",
+        "4:22: Unknown variable: unknown",
+        "7:9: Unknown operation `Float` `Mul` `Int`",
+    ];
+
+    assert_eq!(Vec::from(expected), errors(src));
+}
+
+#[test]
+fn test_uninitialized_variables() {
+    let src = r#"fn(input: int) -> int {
+        while input > 0 {
+            var a = 1;
+            a = a + 1;
+        }
+
+        try {
+            var b = 10 / input;
+        } catch err {
+            println(err);
+        }
+
+        input > 0 && { var c = true; c };
+        a = 2;
+        println(c, err);
+        b + a
+    }"#;
+
+    let expected = [
+        "14:9: Variable `a` might be uninitialized here",
+        "15:17: Variable `c` might be uninitialized here",
+        "15:20: Unknown variable: err",
+        "16:9: Variable `b` might be uninitialized here",
+        "16:13: Variable `a` might be uninitialized here",
+    ];
+
+    assert_eq!(Vec::from(expected), errors(src));
+}
+
+#[test]
+fn test_engines_reject_ill_typed_programs() {
+    // The body of the loop never runs, but the program gets rejected anyway
+    let prog = parse("fn(input: int) -> int { while false { input + 1.0; } input }").unwrap();
+
+    for run in [
+        Box::new(|| {
+            vm::eval::<_, i64>(&prog, 1).unwrap();
+        }) as Box<dyn Fn()>,
+        Box::new(|| {
+            vm::compile::<_, i64>(prog.clone())(1).unwrap();
+        }),
+    ] {
+        let err = panic::catch_unwind(AssertUnwindSafe(run)).unwrap_err();
+
+        assert_eq!(
+            "1:39: Unknown operation `Int` `Add` `Float`",
+            err.downcast_ref::<String>().unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_inferred_types() {
+    let prog = parse(
+        r#"fn(input: int) -> float {
+        var x;
+        var y: float;
+
+        try {
+            x = (10 / input) as float;
+        } catch err {
+            x = -1.0;
+        }
+
+        y = x;
+        y * 2.0
+    }"#,
+    )
+    .unwrap();
+
+    assert_eq!(Some(&Type::Float), typecheck(&prog).unwrap().types().last());
+
+    for (input, output) in [(5, 4.0), (0, -2.0)] {
+        assert_eq!(Ok(output), vm::eval::<_, f64>(&prog, input));
+        assert_eq!(Ok(output), vm::compile::<_, f64>(prog.clone())(input));
+    }
+
+    let src = r#"fn(input: int) -> int {
+        var a;
+        var b;
+        var c;
+        var d;
+        a = input;
+        a = "one";
+        b = b + 1;
+        while input > 0 {
+            c = 1;
+        }
+        c
+    }"#;
+
+    // The first assignment determines the type; variables that are never
+    // assigned to are ambiguous, even if read
+    let expected = [
+        "7:9: Type mismatch: Cannot assign `Str` to `Int`",
+        "8:13: Variable `b` might be uninitialized here",
+        "12:9: Variable `c` might be uninitialized here",
+        "5:9: Type of variable `d` cannot be inferred, since it's never assigned to \
+         (consider annotating it, e.g. `var d: int`)",
+    ];
+
+    assert_eq!(Vec::from(expected), errors(src));
+}
+
+#[test]
+fn test_catch_scope() {
+    // Each handler's error is visible only inside that handler, so the name
+    // can be reused
+    let prog = parse(
+        r#"fn(input: int) -> str {
+        var a = try { 10 / input; "" } catch e { e };
+        var b = try { format("{}", "abc"[input]) } catch e { e };
+        format("{}|{}", a, b)
+    }"#,
+    )
+    .unwrap();
+
+    for (input, output) in [
+        (0, "Division by zero|a"),
+        (1, "|b"),
+        (5, "|Index out of bounds"),
+    ] {
+        assert_eq!(Ok(output.to_string()), vm::eval(&prog, input));
+        assert_eq!(Ok(output.to_string()), vm::compile(prog.clone())(input));
+    }
+
+    let src = r#"fn(input: int) -> str {
+        var e = "";
+        try { print(1 / input) } catch e { };
+        try { print(1 / input) } catch f { };
+        f
+    }"#;
+
+    let expected = [
+        "3:9: Variable `e` has been already defined",
+        "5:9: Unknown variable: f",
+    ];
+
+    assert_eq!(Vec::from(expected), errors(src));
+}
+
+#[test]
+fn test_inferred_types_from_first_assignment() {
+    // There's no unification - the first assignment the checker reaches
+    // determines the type, even if it's in a branch, and later ones are
+    // checked against it
+    let src = r#"fn(input: int) -> int {
+        var x;
+        var y;
+
+        try {
+            x = 10 / input;
+        } catch err {
+            x = err;
+        }
+
+        while input > 0 {
+            y = 1.5;
+        }
+
+        y = input;
+        x
+    }"#;
+
+    let expected = [
+        "8:13: Type mismatch: Cannot assign `Str` to `Int`",
+        "15:9: Type mismatch: Cannot assign `Int` to `Float`",
+    ];
+
+    assert_eq!(Vec::from(expected), errors(src));
+}
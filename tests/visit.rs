@@ -0,0 +1,186 @@
+use rast_jit_vm::{ast::*, examples, vm};
+
+#[test]
+fn test_visitor() {
+    /// Counts nodes, types and values
+    #[derive(Default)]
+    struct Stats {
+        nodes: usize,
+        types: usize,
+        values: usize,
+        vars: Vec<String>,
+    }
+
+    impl<'s> Visitor<'s> for Stats {
+        fn visit_node(&mut self, node: &Node<'s>) {
+            self.nodes += 1;
+
+            if let Node::Var(name) = node {
+                self.vars.push(name.to_string());
+            }
+
+            walk_node(self, node);
+        }
+
+        fn visit_pattern(&mut self, pat: &Pattern<'s>) {
+            if let Pattern::Var(name) = pat {
+                self.vars.push(format!("@{}", name));
+            }
+
+            walk_pattern(self, pat);
+        }
+
+        fn visit_type(&mut self, ty: &Type) {
+            self.types += 1;
+            walk_type(self, ty);
+        }
+
+        fn visit_value(&mut self, value: &Value<'s>) {
+            self.values += 1;
+            walk_value(self, value);
+        }
+    }
+
+    let prog = parse(
+        r#"fn(input: (int, (float, str))) -> int {
+            var (a, (_, b)) = input;
+            print(b[a] as float, ((1, 2), 3));
+            a
+        }"#,
+    )
+    .unwrap()
+    .without_spans();
+
+    let mut stats = Stats::default();
+
+    stats.visit_program(&prog);
+
+    assert_eq!(10, stats.nodes);
+    assert_eq!(7, stats.types);
+    assert_eq!(5, stats.values);
+    assert_eq!(vec!["@a", "@b", "input", "b", "a", "a"], stats.vars);
+
+    // Spans are nodes on their own, too
+    let mut stats = Stats::default();
+
+    stats.visit_node(&parse_node("a + 1").unwrap());
+
+    assert_eq!(6, stats.nodes);
+}
+
+#[test]
+fn test_visitor_mut() {
+    /// Prefixes all variables (except for `input`) with `_`
+    struct Rename;
+
+    impl<'s> VisitorMut<'s> for Rename {
+        fn visit_node_mut(&mut self, node: &mut Node<'s>) {
+            match node {
+                Node::Var(name) | Node::Declare { name, .. } | Node::Assign { name, .. } => {
+                    if *name != "input" {
+                        *name = Box::leak(format!("_{}", name).into_boxed_str());
+                    }
+                }
+
+                _ => (),
+            }
+
+            walk_node_mut(self, node);
+        }
+    }
+
+    let mut prog = examples::fibonacci();
+
+    Rename.visit_program_mut(&mut prog);
+
+    assert!(prog.to_string().contains("_z = _x + _y"));
+    assert_eq!(55, vm::eval(&prog, 10).unwrap());
+}
+
+#[test]
+fn test_fold() {
+    /// Rewrites `expr * 2` into `expr + expr`
+    struct Strength;
+
+    impl<'s> Fold<'s> for Strength {
+        fn fold_node(&mut self, node: Node<'s>) -> Node<'s> {
+            match walk_fold_node(self, node) {
+                Node::Mul { lhs, rhs } if *rhs == Node::Const(Value::Int(2)) => Node::Add {
+                    lhs: lhs.clone(),
+                    rhs: lhs,
+                },
+
+                node => node,
+            }
+        }
+    }
+
+    let prog = parse("fn(input: int) -> int { var x = input * 2; x * 2 * 2 }")
+        .unwrap()
+        .without_spans();
+
+    let prog = Strength.fold_program(prog);
+
+    assert_eq!(
+        "fn(input: int) -> int {\n    var x = input + input;\n    x + x + (x + x)\n}",
+        prog.to_string()
+    );
+
+    assert_eq!(40, vm::eval(&prog, 5).unwrap());
+}
+
+#[test]
+fn test_defaults_preserve_the_program() {
+    struct Noop;
+
+    impl<'s> Visitor<'s> for Noop {}
+    impl<'s> VisitorMut<'s> for Noop {}
+    impl<'s> Fold<'s> for Noop {}
+
+    for prog in [examples::fibonacci(), examples::mandelbrot()] {
+        Noop.visit_program(&prog);
+
+        let mut prog2 = prog.clone();
+
+        Noop.visit_program_mut(&mut prog2);
+
+        assert_eq!(prog, prog2);
+        assert_eq!(prog, Noop.fold_program(prog.clone()));
+    }
+}
+
+#[test]
+fn test_nodes_are_visited_in_evaluation_order() {
+    /// Finds pre-order index of the first division
+    #[derive(Default)]
+    struct FindDiv {
+        nodes: usize,
+        div: Option<usize>,
+    }
+
+    impl<'s> Visitor<'s> for FindDiv {
+        fn visit_node(&mut self, node: &Node<'s>) {
+            if let Node::Div { .. } = node {
+                self.div.get_or_insert(self.nodes);
+            }
+
+            self.nodes += 1;
+            walk_node(self, node);
+        }
+    }
+
+    // Runtime errors point at nodes by their pre-order index, which must
+    // agree with the visitor's - including for destructuring, whose pattern
+    // comes before the value in the source code
+    let prog = parse("fn(input: int) -> int { var (a, b) = { print(1 / input); (1, 2) }; a + b }")
+        .unwrap();
+
+    let mut find = FindDiv::default();
+
+    find.visit_program(&prog);
+
+    let div = find.div.unwrap();
+
+    assert_eq!(div, vm::eval::<_, i64>(&prog, 0).unwrap_err().node);
+    assert_eq!(div, vm::compile::<_, i64>(prog)(0).unwrap_err().node);
+}